@@ -1,8 +1,8 @@
-use std::env;
 use std::time::Duration;
 
 use anyhow::Context;
 use deadpool_diesel::postgres::{Object, Pool as DbPool};
+use shared::config::DbPoolConfig;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -10,17 +10,10 @@ pub struct AppState {
 }
 
 impl AppState {
-    pub async fn new(db_url: String) -> anyhow::Result<Self> {
-        let max_pool_size = env::var("DATABASE_POOL_SIZE")
-            .unwrap_or_else(|_| 8.to_string())
-            .parse::<usize>()
-            .unwrap_or(8_usize);
-
-        let max_conn_retries = env::var("DATABASE_MAX_CONN_RETRIES")
-            .unwrap_or_else(|_| 5.to_string())
-            .parse::<u32>()
-            .unwrap_or(5);
-
+    pub async fn new(
+        db_url: String,
+        db_pool: DbPoolConfig,
+    ) -> anyhow::Result<Self> {
         let pool = tryhard::retry_fn(|| async {
             let pool_manager = deadpool_diesel::Manager::from_config(
                 db_url.clone(),
@@ -30,12 +23,16 @@ impl AppState {
                         deadpool_diesel::RecyclingMethod::Verified,
                 },
             );
+            // NB: timeouts are only enforced with a runtime set
             DbPool::builder(pool_manager)
-                .max_size(max_pool_size)
+                .max_size(db_pool.database_pool_size)
+                .runtime(deadpool_diesel::Runtime::Tokio1)
+                .wait_timeout(db_pool.wait_timeout())
+                .create_timeout(db_pool.connect_timeout())
                 .build()
                 .context("Failed to build Postgres db pool")
         })
-        .retries(max_conn_retries)
+        .retries(db_pool.database_max_conn_retries)
         .exponential_backoff(Duration::from_millis(100))
         .max_delay(Duration::from_secs(5))
         .await?;
@@ -49,4 +46,10 @@ impl AppState {
             .await
             .context("Failed to get db connection handle from deadpool")
     }
+
+    /// Close the db pool, dropping its idle connections. Connections
+    /// still in use are dropped once returned to the pool.
+    pub fn close(&self) {
+        self.db.close();
+    }
 }
@@ -1,26 +1,341 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
 use clap_verbosity_flag::{InfoLevel, LevelFilter, Verbosity};
+use shared::config::DbPoolConfig;
+use shared::telemetry;
+use tendermint_rpc::client::CompatMode;
 use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::filter::LevelFilter as TracingLevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::services::retry::RetryStrategy;
 
 #[derive(clap::Parser)]
+#[command(subcommand_negates_reqs = true)]
 pub struct AppConfig {
+    /// CometBFT RPC endpoints, separated by commas. Requests fail over
+    /// to the next endpoint if the active one becomes unreachable.
+    #[clap(long, env, value_delimiter = ',', required = true)]
+    pub cometbft_url: Vec<String>,
+
+    /// Spread requests across all healthy CometBFT endpoints, rather
+    /// than sending them to the same endpoint until it fails
     #[clap(long, env)]
-    pub cometbft_url: String,
+    pub rpc_round_robin: bool,
 
+    /// Interval at which unreachable CometBFT endpoints are rechecked,
+    /// in seconds
+    #[clap(long, env, default_value_t = 30)]
+    pub rpc_recheck_interval: u64,
+
+    /// RPC dialect of the CometBFT endpoints (e.g. `v0.37`). Detected
+    /// from the version reported by each endpoint, if unset.
+    #[clap(long, env)]
+    pub compat_mode: Option<CompatMode>,
+
+    /// Link to the Postgres database. Builds with the `test-utils`
+    /// feature also accept `memory://`, to keep all indexed data in
+    /// memory
     #[clap(long, env)]
     pub database_url: String,
 
+    #[command(flatten)]
+    pub db_pool: DbPoolConfig,
+
+    /// Multiplier of the exponential backoff between retries, in
+    /// seconds (defaults to 5)
     #[clap(long, env)]
     pub interval: Option<u64>,
 
+    /// Base of the exponential backoff between retries. A base of 1
+    /// retries at a fixed interval.
+    #[clap(
+        long,
+        env,
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub retry_base: u64,
+
+    /// Maximum interval between retries, in seconds (defaults to 60)
+    #[clap(long, env, alias = "max-backoff")]
+    pub retry_max_interval: Option<u64>,
+
+    /// Whether the interval between retries is fixed, or grows
+    /// exponentially
+    #[clap(long, env, value_enum, default_value_t = RetryStrategy::default())]
+    pub retry_strategy: RetryStrategy,
+
+    /// Maximum number of retries of a failed operation, after which the
+    /// crawler exits with an error
     #[clap(long, env)]
+    pub max_retries: Option<usize>,
+
+    /// Maximum time spent retrying a failed operation, in seconds, after
+    /// which the crawler exits with an error
+    #[clap(long, env)]
+    pub retry_max_elapsed: Option<u64>,
+
+    /// Start indexing after this block height, skipping the history
+    /// before the MASP was activated. Recorded in the database, such
+    /// that later runs carry on from it even when it is not passed.
+    #[clap(long, env, alias = "start-height")]
     pub starting_block_height: Option<u64>,
 
+    /// Only persist the notes map, chain state and shielded txs. The
+    /// commitment tree and witness map are kept in memory, and rebuilt
+    /// from the stored txs on restart.
+    #[clap(long, env)]
+    pub notes_map_only: bool,
+
+    /// File created once the crawler catches up with the tip of the
+    /// chain, and removed if it falls behind again
+    #[clap(long, env)]
+    pub sync_marker_path: Option<PathBuf>,
+
+    /// Number of blocks the crawler may lag behind the tip of the
+    /// chain, while still being considered synced
+    #[clap(long, env, default_value_t = 2)]
+    pub sync_lag_threshold: u64,
+
+    /// Maximum number of blocks committed to the database in a single
+    /// transaction. Batches are flushed early once the crawler reaches
+    /// the tip of the chain.
+    #[clap(
+        long,
+        env,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub commit_batch_size: u64,
+
+    /// Number of blocks fetched concurrently from CometBFT ahead of the
+    /// block being processed. Blocks are still processed and committed
+    /// in height order, and a depth of 0 disables prefetching.
+    #[clap(long, env, alias = "concurrency", default_value_t = 8)]
+    pub prefetch_depth: u64,
+
+    /// Persist the full witness map every this many blocks. In between
+    /// checkpoints, only the shielded txs of each block are persisted,
+    /// from which witnesses are rebuilt on restart. A value of 1 persists
+    /// the witness map along with every block.
+    #[clap(
+        long,
+        env,
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub witness_checkpoint_blocks: u64,
+
+    /// Persist the witness map every this many seconds, rather than
+    /// along with every block. May be combined with
+    /// `witness_checkpoint_blocks`, whichever elapses first.
+    #[clap(long, env)]
+    pub witness_checkpoint_interval: Option<u64>,
+
+    /// Number of most recent witness map checkpoints kept in the
+    /// database, older ones being deleted as new ones are persisted. All
+    /// checkpoints are kept if unset.
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub witness_checkpoints_kept: Option<u64>,
+
+    /// Only keep the shielded txs, notes map entries, nullifiers and
+    /// compact outputs of this many of the last synced blocks, pruning
+    /// older ones after each commit. Commitment trees and witness map
+    /// checkpoints are kept, and so are the txs committed after the last
+    /// checkpoint. Not supported in notes map only mode.
+    #[clap(long, env, value_parser = clap::value_parser!(u64).range(1..))]
+    pub retain_blocks: Option<u64>,
+
+    /// Flush the current batch as soon as a block with masp txs is seen
+    #[clap(long, env)]
+    pub flush_on_masp_txs: bool,
+
+    /// Maximum number of committed blocks that may be rolled back when
+    /// a chain reorg is detected, before the crawler aborts
+    #[clap(long, env, default_value_t = 100)]
+    pub max_rollback_depth: u64,
+
+    /// Number of times a block that cannot be decoded, or whose masp txs
+    /// cannot be applied, is processed, backing off between attempts,
+    /// before it is recorded as failed and the crawler exits, unless
+    /// `--skip-failed-blocks` is set. Failed blocks can be processed
+    /// again with the `reprocess` subcommand.
+    #[clap(
+        long,
+        env,
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub max_block_attempts: u64,
+
+    /// Commit blocks that permanently failed to be processed without
+    /// their masp txs, and move on to the next block, rather than exiting
+    /// with an error.
+    ///
+    /// WARNING: this corrupts the index. The commitment tree diverges
+    /// from the one on chain, such that the notes, witnesses and anchors
+    /// of every later block are wrong, and served as such. Only use it
+    /// to keep serving the blocks below a failure until it is fixed,
+    /// then process the skipped blocks again with the `reprocess`
+    /// subcommand. Blocks that could not be fetched or decoded are never
+    /// skipped.
+    #[clap(long, env)]
+    pub skip_failed_blocks: bool,
+
+    /// Time given to in-flight commits to finish after a shutdown was
+    /// requested, in seconds, before the process exits regardless
+    #[clap(long, env, default_value_t = 30)]
+    pub shutdown_timeout: u64,
+
+    /// Port on which Prometheus metrics are served, under `/metrics`
+    #[clap(long, env)]
+    pub metrics_port: Option<u16>,
+
+    /// Address on which the admin API is served (e.g. `127.0.0.1:9001`),
+    /// to inspect the crawler, pause or resume indexing, and request
+    /// pruning or verification runs
+    #[clap(long, env, requires = "admin_token")]
+    pub admin_addr: Option<SocketAddr>,
+
+    /// Bearer token required in the `Authorization` header of every
+    /// request to the admin API
+    #[clap(long, env)]
+    pub admin_token: Option<String>,
+
+    /// File to which a NDJSON record with the number of masp txs, notes
+    /// and fee unshieldings of each committed block is appended
+    #[clap(long, env)]
+    pub block_stats_path: Option<PathBuf>,
+
+    /// Webhook URLs, separated by commas, to which a signed JSON payload
+    /// is POSTed for each committed block with masp txs
+    #[clap(long, env, value_delimiter = ',', requires = "webhook_secret")]
+    pub webhook_url: Vec<String>,
+
+    /// Secret with which webhook payloads are signed, using HMAC-SHA256
+    #[clap(long, env)]
+    pub webhook_secret: Option<String>,
+
+    /// Maximum number of retries of a failed webhook delivery, after
+    /// which the notification is dropped
+    #[clap(long, env, default_value_t = 5)]
+    pub webhook_max_retries: usize,
+
+    /// OTLP collector endpoint (e.g. `http://localhost:4317`) to which
+    /// tracing spans are exported over gRPC, if any
+    #[clap(long, env)]
+    pub otlp_endpoint: Option<String>,
+
     #[command(flatten)]
     pub verbosity: Verbosity<InfoLevel>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Delete all indexed data above some block height, and print the
+    /// resulting last synced height
+    Reset(ResetArgs),
+
+    /// Scan the committed blocks, and report any missing height ranges,
+    /// along with any commitment tree, witness map or notes map entry
+    /// diverging from the ones recomputed from the stored txs
+    Verify,
+
+    /// Delete all indexed data from the lowest block recorded as failed
+    /// onwards, such that it is processed again on the next run, and
+    /// print the resulting last synced height
+    Reprocess,
+
+    /// Fetch and process again a range of already indexed blocks, and
+    /// replace their committed data. Blocks outside the range are kept.
+    Reindex(ReindexArgs),
+
+    /// Index the history of the chain up to some height, with several
+    /// workers extracting the masp txs of chunks of blocks concurrently,
+    /// ahead of the single pass applying them in height order, then exit
+    Backfill(BackfillArgs),
+
+    /// Export or import snapshots of the committed state
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
 }
 
-pub fn install_tracing_subscriber(verbosity: Verbosity<InfoLevel>) {
+#[derive(clap::Subcommand)]
+pub enum SnapshotCommand {
+    /// Write the commitment tree, witness map and notes map at the last
+    /// synced height to a file
+    Export {
+        #[clap(long)]
+        out: PathBuf,
+    },
+
+    /// Restore a snapshot into an empty database
+    Import {
+        #[clap(long = "in")]
+        in_path: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+#[group(required = true, multiple = false)]
+pub struct ResetArgs {
+    /// Keep the blocks up to and including this height
+    #[clap(long)]
+    pub to_height: Option<u64>,
+
+    /// Delete all indexed blocks
+    #[clap(long)]
+    pub all: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ReindexArgs {
+    /// First block of the range to reindex
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub from: u64,
+
+    /// Last block of the range to reindex (inclusive)
+    #[clap(long)]
+    pub to: u64,
+}
+
+#[derive(clap::Args)]
+pub struct BackfillArgs {
+    /// Number of chunks of blocks fetched concurrently
+    #[clap(
+        long,
+        default_value_t = 8,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub workers: u64,
+
+    /// Number of consecutive blocks fetched by a worker at a time
+    #[clap(
+        long,
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub chunk_size: u64,
+
+    /// Last block to backfill (inclusive). Defaults to the tip of the
+    /// chain when the backfill starts.
+    #[clap(long)]
+    pub to: Option<u64>,
+}
+
+pub fn install_tracing_subscriber(
+    verbosity: Verbosity<InfoLevel>,
+    otlp_endpoint: Option<&str>,
+) {
     let log_level = match verbosity.log_level_filter() {
         LevelFilter::Off => None,
         LevelFilter::Error => Some(Level::ERROR),
@@ -29,9 +344,25 @@ pub fn install_tracing_subscriber(verbosity: Verbosity<InfoLevel>) {
         LevelFilter::Debug => Some(Level::DEBUG),
         LevelFilter::Trace => Some(Level::TRACE),
     };
-    if let Some(log_level) = log_level {
-        let subscriber =
-            FmtSubscriber::builder().with_max_level(log_level).finish();
-        tracing::subscriber::set_global_default(subscriber).unwrap();
+    let Some(log_level) = log_level else {
+        return;
+    };
+
+    let (otlp_layer, otlp_error) = match otlp_endpoint
+        .map(|endpoint| telemetry::otlp_layer("namada-masp-indexer", endpoint))
+    {
+        Some(Ok(layer)) => (Some(layer), None),
+        Some(Err(err)) => (None, Some(err)),
+        None => (None, None),
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(TracingLevelFilter::from_level(log_level))
+        .with(tracing_subscriber::fmt::layer())
+        .with(otlp_layer);
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+
+    if let Some(reason) = otlp_error {
+        tracing::warn!(?reason, "Failed to set up OTLP export of spans");
     }
 }
@@ -1,20 +1,65 @@
+use chrono::{DateTime, NaiveDateTime};
+use orm::block_hash::BlockHashDb;
 use orm::chain_state::ChainStateteInsertDb;
+use shared::block::Block;
 use shared::height::BlockHeight;
+use shared::id::Id;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct ChainState {
     pub block_height: BlockHeight,
+    pub block_hash: Id,
+    /// Tip of the chain, as seen when the block was processed.
+    pub chain_tip: BlockHeight,
+    /// Time the block was committed at, if known.
+    pub timestamp: Option<NaiveDateTime>,
+    /// Epoch of the block, if known.
+    pub epoch: Option<u64>,
 }
 
 impl ChainState {
-    pub fn new(block_height: BlockHeight) -> Self {
-        Self { block_height }
+    pub fn new(
+        block_height: BlockHeight,
+        block_hash: Id,
+        chain_tip: BlockHeight,
+    ) -> Self {
+        Self {
+            block_height,
+            block_hash,
+            chain_tip,
+            timestamp: None,
+            epoch: None,
+        }
+    }
+
+    /// Build the chain state of a block fetched from CometBFT, along with
+    /// its timestamp and epoch.
+    pub fn from_block(block: &Block, chain_tip: BlockHeight) -> Self {
+        Self {
+            block_height: block.header.height,
+            block_hash: block.hash.clone(),
+            chain_tip,
+            timestamp: DateTime::parse_from_rfc3339(&block.header.timestamp)
+                .ok()
+                .map(|timestamp| timestamp.naive_utc()),
+            epoch: block.epoch,
+        }
     }
 
     pub fn into_db(&self) -> ChainStateteInsertDb {
         ChainStateteInsertDb {
             id: 0, // NB: overwrite old row
             block_height: self.block_height.0 as i32,
+            chain_tip: self.chain_tip.0 as i32,
+        }
+    }
+
+    pub fn block_hash_into_db(&self) -> BlockHashDb {
+        BlockHashDb {
+            block_height: self.block_height.0 as i32,
+            hash: self.block_hash.to_string(),
+            timestamp: self.timestamp,
+            epoch: self.epoch.map(|epoch| epoch as i32),
         }
     }
 }
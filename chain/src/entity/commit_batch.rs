@@ -0,0 +1,262 @@
+use std::fmt;
+
+use namada_sdk::borsh::BorshSerializeExt;
+use namada_sdk::masp_primitives::ff::PrimeField;
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::anchor::AnchorDb;
+use orm::block_hash::BlockHashDb;
+use orm::chain_state::ChainStateteInsertDb;
+use orm::compact_output::CompactOutputInsertDb;
+use orm::notes_index::NotesIndexInsertDb;
+use orm::nullifier::NullifierInsertDb;
+use orm::tree::TreeInsertDb;
+use orm::tx::TxInsertDb;
+use orm::witness::WitnessInsertDb;
+use shared::height::BlockHeight;
+use shared::indexed_tx::IndexedTx;
+
+use super::chain_state::ChainState;
+use super::commitment_tree::CommitmentTree;
+use super::tx_notes_index::TxNoteMap;
+use super::witness_map::WitnessMap;
+
+/// Number of leading bytes of a note ciphertext needed to trial decrypt
+/// it: lead byte, diversifier, asset type, value and rseed.
+pub const COMPACT_CIPHERTEXT_SIZE: usize = 1 + 11 + 32 + 8 + 32;
+
+/// Error returned when committing a batch would leave a gap in, or
+/// overwrite part of, the committed block heights.
+#[derive(Debug)]
+pub struct NonContiguousCommit {
+    pub expected: i32,
+    pub found: i32,
+}
+
+impl fmt::Display for NonContiguousCommit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Non-contiguous commit: expected block height {}, found {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for NonContiguousCommit {}
+
+/// Data of one or more consecutive blocks, waiting to be committed to
+/// storage in a single transaction.
+#[derive(Default, Clone)]
+pub struct CommitBatch {
+    chain_state: Option<ChainState>,
+    num_blocks: usize,
+    pub block_hashes: Vec<BlockHashDb>,
+    pub anchors: Vec<AnchorDb>,
+    pub commitment_trees: Vec<TreeInsertDb>,
+    pub witnesses: Vec<WitnessInsertDb>,
+    pub notes_index: Vec<NotesIndexInsertDb>,
+    pub shielded_txs: Vec<TxInsertDb>,
+    pub nullifiers: Vec<NullifierInsertDb>,
+    pub compact_outputs: Vec<CompactOutputInsertDb>,
+}
+
+impl CommitBatch {
+    /// Add the data of a block to the batch.
+    ///
+    /// The commitment tree and witness map are committed in memory, such
+    /// that the next block builds on top of them, even before the batch
+    /// is written to storage. The witness map is only written to storage
+    /// by [`CommitBatch::checkpoint_witnesses`].
+    pub fn push(
+        &mut self,
+        chain_state: ChainState,
+        commitment_tree: &CommitmentTree,
+        witness_map: &WitnessMap,
+        notes_index: TxNoteMap,
+        shielded_txs: Vec<(IndexedTx, Transaction)>,
+        notes_map_only: bool,
+    ) {
+        // NB: when only the notes map is persisted, the commitment
+        // tree is committed in memory, but never written to storage
+        if notes_map_only {
+            commitment_tree.commit();
+        } else {
+            self.commitment_trees
+                .extend(commitment_tree.into_db(chain_state.block_height));
+        }
+        witness_map.commit();
+
+        self.anchors
+            .push(commitment_tree.anchor_into_db(chain_state.block_height));
+        self.notes_index.extend(notes_index.into_db());
+        self.nullifiers
+            .extend(shielded_txs.iter().flat_map(|(index, tx)| {
+                nullifiers_into_db(
+                    index.block_height.0 as i32,
+                    index.block_index.0 as i32,
+                    index.masp_tx_index.0 as i32,
+                    tx,
+                )
+            }));
+        self.compact_outputs.extend(shielded_txs.iter().flat_map(
+            |(index, tx)| {
+                compact_outputs_into_db(
+                    index.block_height.0 as i32,
+                    index.block_index.0 as i32,
+                    index.masp_tx_index.0 as i32,
+                    tx,
+                )
+            },
+        ));
+        self.shielded_txs
+            .extend(shielded_txs.iter().map(|(index, tx)| TxInsertDb {
+                block_index: index.block_index.0 as i32,
+                tx_bytes: tx.serialize_to_vec(),
+                block_height: index.block_height.0 as i32,
+                masp_tx_index: index.masp_tx_index.0 as i32,
+            }));
+        self.block_hashes.push(chain_state.block_hash_into_db());
+
+        self.num_blocks += 1;
+        self.chain_state = Some(chain_state);
+    }
+
+    /// Persist the witness map along with the highest block in the batch,
+    /// if it changed since it was last persisted.
+    pub fn checkpoint_witnesses(&mut self, witness_map: &WitnessMap) {
+        let Some(block_height) = self.block_height() else {
+            return;
+        };
+
+        self.witnesses
+            .extend(witness_map.into_db(block_height).into_iter().flatten());
+    }
+
+    /// Add a block restored from a snapshot, along with the commitment
+    /// tree, witness map and notes map as of that block.
+    pub fn push_restored(
+        &mut self,
+        chain_state: ChainState,
+        commitment_tree: TreeInsertDb,
+        witnesses: Vec<WitnessInsertDb>,
+        notes_index: Vec<NotesIndexInsertDb>,
+    ) {
+        self.commitment_trees.push(commitment_tree);
+        self.witnesses.extend(witnesses);
+        self.notes_index.extend(notes_index);
+        self.block_hashes.push(chain_state.block_hash_into_db());
+
+        self.num_blocks += 1;
+        self.chain_state = Some(chain_state);
+    }
+
+    /// Number of blocks in the batch.
+    pub fn len(&self) -> usize {
+        self.num_blocks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_blocks == 0
+    }
+
+    /// Highest block height in the batch.
+    pub fn block_height(&self) -> Option<BlockHeight> {
+        self.chain_state.as_ref().map(|state| state.block_height)
+    }
+
+    /// Lowest block height in the batch.
+    pub fn first_block_height(&self) -> Option<BlockHeight> {
+        self.block_hashes
+            .first()
+            .map(|hash| BlockHeight::from(hash.block_height))
+    }
+
+    /// Check whether all the blocks in the batch were already committed,
+    /// given a lookup of the committed block hashes.
+    pub fn is_committed<'a>(
+        &self,
+        committed_hash: impl Fn(i32) -> Option<&'a str>,
+    ) -> bool {
+        !self.block_hashes.is_empty()
+            && self.block_hashes.iter().all(|hash| {
+                committed_hash(hash.block_height) == Some(hash.hash.as_str())
+            })
+    }
+
+    /// Hash of the block at `block_height`, if it is part of the batch.
+    pub fn block_hash(&self, block_height: BlockHeight) -> Option<&str> {
+        self.block_hashes
+            .iter()
+            .find(|hash| hash.block_height == block_height.0 as i32)
+            .map(|hash| hash.hash.as_str())
+    }
+
+    /// Check that the blocks in the batch are consecutive, and that the
+    /// first of them directly follows `last_synced_height`. Any height
+    /// may be committed first, if nothing was committed yet.
+    pub fn check_follows(
+        &self,
+        last_synced_height: Option<i32>,
+    ) -> Result<(), NonContiguousCommit> {
+        let mut expected = last_synced_height.map(|h| h + 1);
+
+        for block_hash in &self.block_hashes {
+            let found = block_hash.block_height;
+
+            if let Some(expected) = expected.filter(|&h| h != found) {
+                return Err(NonContiguousCommit { expected, found });
+            }
+
+            expected = Some(found + 1);
+        }
+
+        Ok(())
+    }
+
+    /// Chain state to persist along with the batch, pointing at its
+    /// highest block height.
+    pub fn chain_state_into_db(&self) -> Option<ChainStateteInsertDb> {
+        self.chain_state.as_ref().map(ChainState::into_db)
+    }
+}
+
+/// Rows of the nullifiers revealed by the spends of the masp tx `tx`.
+pub fn nullifiers_into_db(
+    block_height: i32,
+    block_index: i32,
+    masp_tx_index: i32,
+    tx: &Transaction,
+) -> impl Iterator<Item = NullifierInsertDb> + '_ {
+    tx.sapling_bundle()
+        .into_iter()
+        .flat_map(|bundle| &bundle.shielded_spends)
+        .map(move |spend| NullifierInsertDb {
+            nullifier: spend.nullifier.0.to_vec(),
+            block_height,
+            block_index,
+            masp_tx_index,
+        })
+}
+
+/// Rows of the compact outputs of the masp tx `tx`, holding just what
+/// light clients need to trial decrypt its notes.
+pub fn compact_outputs_into_db(
+    block_height: i32,
+    block_index: i32,
+    masp_tx_index: i32,
+    tx: &Transaction,
+) -> impl Iterator<Item = CompactOutputInsertDb> + '_ {
+    tx.sapling_bundle()
+        .into_iter()
+        .flat_map(|bundle| bundle.shielded_outputs.iter().enumerate())
+        .map(move |(output_index, output)| CompactOutputInsertDb {
+            block_height,
+            block_index,
+            masp_tx_index,
+            output_index: output_index as i32,
+            cmu: output.cmu.to_repr().to_vec(),
+            ephemeral_key: output.ephemeral_key.0.to_vec(),
+            ciphertext: output.enc_ciphertext[..COMPACT_CIPHERTEXT_SIZE]
+                .to_vec(),
+        })
+}
@@ -4,6 +4,7 @@ use anyhow::Context;
 use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
 use namada_sdk::masp_primitives::merkle_tree::CommitmentTree as MaspCommitmentTree;
 use namada_sdk::masp_primitives::sapling::Node;
+use orm::anchor::AnchorDb;
 use orm::tree::{TreeDb, TreeInsertDb};
 use shared::height::BlockHeight;
 use shared::transactional::Transactional;
@@ -48,9 +49,17 @@ impl InnerCommitmentTree {
         self.transactional.as_ref().clone()
     }
 
+    fn get_committed_tree(&self) -> MaspCommitmentTree<Node> {
+        self.transactional.committed().clone()
+    }
+
+    fn commit(&mut self) -> bool {
+        self.transactional.commit()
+    }
+
     #[allow(clippy::wrong_self_convention)]
     fn into_db(&mut self, block_height: BlockHeight) -> Option<TreeInsertDb> {
-        if !self.transactional.commit() {
+        if !self.commit() {
             return None;
         }
         Some(TreeInsertDb {
@@ -88,10 +97,32 @@ impl CommitmentTree {
         self.0.lock().unwrap().get_tree()
     }
 
+    /// Return the tree as of the last commit, without the notes appended
+    /// since.
+    pub fn get_committed_tree(&self) -> MaspCommitmentTree<Node> {
+        self.0.lock().unwrap().get_committed_tree()
+    }
+
+    pub fn commit(&self) -> bool {
+        self.0.lock().unwrap().commit()
+    }
+
     #[allow(clippy::wrong_self_convention)]
     pub fn into_db(&self, block_height: BlockHeight) -> Option<TreeInsertDb> {
         self.0.lock().unwrap().into_db(block_height)
     }
+
+    /// Anchor of the tree at `block_height`, to be recorded in the anchor
+    /// history.
+    pub fn anchor_into_db(&self, block_height: BlockHeight) -> AnchorDb {
+        let inner = self.0.lock().unwrap();
+
+        AnchorDb {
+            block_height: block_height.0 as i32,
+            root: inner.root().serialize_to_vec(),
+            tree_size: inner.size() as i32,
+        }
+    }
 }
 
 impl TryFrom<TreeDb> for CommitmentTree {
@@ -1,4 +1,6 @@
 pub mod chain_state;
+pub mod commit_batch;
 pub mod commitment_tree;
+pub mod snapshot;
 pub mod tx_notes_index;
 pub mod witness_map;
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use anyhow::Context;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use namada_sdk::masp_primitives::merkle_tree::{
+    CommitmentTree as MaspCommitmentTree, IncrementalWitness,
+};
+use namada_sdk::masp_primitives::sapling::Node;
+use orm::notes_index::NotesIndexInsertDb;
+use shared::height::BlockHeight;
+use shared::id::Id;
+
+/// Magic bytes at the start of every snapshot file.
+const MAGIC: &[u8; 8] = b"MASPSNAP";
+
+/// Version of the snapshot format, to be bumped on every change to the
+/// layout of the encoded fields.
+const FORMAT_VERSION: u32 = 2;
+
+/// Committed state of the crawler at some block height, from which a
+/// fresh database can be bootstrapped.
+pub struct Snapshot {
+    pub chain_id: String,
+    pub block_height: BlockHeight,
+    pub block_hash: Id,
+    pub commitment_tree: MaspCommitmentTree<Node>,
+    pub witnesses: HashMap<usize, IncrementalWitness<Node>>,
+    pub notes_index: Vec<NotesIndexInsertDb>,
+}
+
+impl Snapshot {
+    /// Encode the snapshot, preceded by the format version header.
+    pub fn write(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        writer
+            .write_all(MAGIC)
+            .context("Failed to write snapshot header")?;
+        FORMAT_VERSION.serialize(writer)?;
+
+        self.chain_id.serialize(writer)?;
+        self.block_height.0.serialize(writer)?;
+        self.block_hash.to_string().serialize(writer)?;
+        self.commitment_tree.serialize(writer)?;
+
+        let mut witnesses = self.witnesses.iter().collect::<Vec<_>>();
+        witnesses.sort_unstable_by_key(|(note_pos, _)| **note_pos);
+
+        (witnesses.len() as u64).serialize(writer)?;
+        for (note_pos, witness) in witnesses {
+            (*note_pos as u64).serialize(writer)?;
+            witness.serialize(writer)?;
+        }
+
+        (self.notes_index.len() as u64).serialize(writer)?;
+        for note in &self.notes_index {
+            (
+                note.block_height,
+                note.block_index,
+                note.masp_tx_index,
+                note.note_position,
+                note.is_fee_unshielding,
+            )
+                .serialize(writer)?;
+        }
+
+        writer.flush().context("Failed to write snapshot")
+    }
+
+    /// Decode a snapshot, rejecting unknown format versions.
+    pub fn read(reader: &mut impl Read) -> anyhow::Result<Self> {
+        let mut magic = [0u8; MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .context("Failed to read snapshot header")?;
+
+        if &magic != MAGIC {
+            anyhow::bail!("Not a masp indexer snapshot");
+        }
+
+        let version = u32::deserialize_reader(reader)?;
+        if version != FORMAT_VERSION {
+            anyhow::bail!(
+                "Unsupported snapshot format version {version}, expected \
+                 {FORMAT_VERSION}"
+            );
+        }
+
+        let chain_id = String::deserialize_reader(reader)?;
+        let block_height = BlockHeight(u64::deserialize_reader(reader)?);
+        let block_hash = Id::Hash(String::deserialize_reader(reader)?);
+        let commitment_tree =
+            MaspCommitmentTree::<Node>::deserialize_reader(reader)
+                .context("Failed to deserialize snapshot commitment tree")?;
+
+        let num_witnesses = u64::deserialize_reader(reader)?;
+        let witnesses = (0..num_witnesses)
+            .map(|_| {
+                let note_pos = u64::deserialize_reader(reader)? as usize;
+                let witness =
+                    IncrementalWitness::<Node>::deserialize_reader(reader)?;
+                anyhow::Ok((note_pos, witness))
+            })
+            .collect::<anyhow::Result<_>>()
+            .context("Failed to deserialize snapshot witness map")?;
+
+        let num_notes = u64::deserialize_reader(reader)?;
+        let notes_index = (0..num_notes)
+            .map(|_| {
+                let (
+                    block_height,
+                    block_index,
+                    masp_tx_index,
+                    note_position,
+                    is_fee_unshielding,
+                ) = <(i32, i32, i32, i32, bool)>::deserialize_reader(reader)?;
+                anyhow::Ok(NotesIndexInsertDb {
+                    block_index,
+                    note_position,
+                    block_height,
+                    masp_tx_index,
+                    is_fee_unshielding,
+                })
+            })
+            .collect::<anyhow::Result<_>>()
+            .context("Failed to deserialize snapshot notes map")?;
+
+        Ok(Self {
+            chain_id,
+            block_height,
+            block_hash,
+            commitment_tree,
+            witnesses,
+            notes_index,
+        })
+    }
+
+    /// Check that the commitment tree, witness map and notes map of the
+    /// snapshot are consistent with each other.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let commitment_tree_len = self.commitment_tree.size();
+        let witness_map_len = self.witnesses.len();
+
+        if commitment_tree_len == 0 && witness_map_len != 0
+            || commitment_tree_len != 0 && witness_map_len == 0
+        {
+            anyhow::bail!(
+                "Invalid snapshot: Commitment tree size is \
+                 {commitment_tree_len}, and witness map size is \
+                 {witness_map_len}"
+            );
+        }
+
+        let root = self.commitment_tree.root();
+
+        for (note_pos, witness) in &self.witnesses {
+            if *note_pos >= commitment_tree_len || witness.root() != root {
+                anyhow::bail!(
+                    "Invalid snapshot: Witness of note {note_pos} does not \
+                     match the commitment tree"
+                );
+            }
+        }
+
+        let block_height = self.block_height.0 as i32;
+
+        for note in &self.notes_index {
+            if note.note_position as usize >= commitment_tree_len
+                || note.block_height > block_height
+            {
+                anyhow::bail!(
+                    "Invalid snapshot: Note {} at height {} is not part of \
+                     the commitment tree",
+                    note.note_position,
+                    note.block_height
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
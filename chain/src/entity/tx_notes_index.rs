@@ -4,11 +4,16 @@ use orm::notes_index::NotesIndexInsertDb;
 use shared::indexed_tx::IndexedTx;
 
 #[derive(Default, Clone, Debug)]
-pub struct TxNoteMap(BTreeMap<IndexedTx, usize>);
+pub struct TxNoteMap(BTreeMap<IndexedTx, (usize, bool)>);
 
 impl TxNoteMap {
-    pub fn insert(&mut self, indexed_tx: IndexedTx, note_pos: usize) {
-        self.0.insert(indexed_tx, note_pos);
+    pub fn insert(
+        &mut self,
+        indexed_tx: IndexedTx,
+        note_pos: usize,
+        is_fee_unshielding: bool,
+    ) {
+        self.0.insert(indexed_tx, (note_pos, is_fee_unshielding));
     }
 
     pub fn is_empty(&self) -> bool {
@@ -26,12 +31,13 @@ impl TxNoteMap {
                         batch_index,
                         ..
                     },
-                    &note_pos,
+                    &(note_pos, is_fee_unshielding),
                 )| NotesIndexInsertDb {
                     block_index: block_index.0 as i32,
                     note_position: note_pos as i32,
                     block_height: block_height.0 as i32,
                     masp_tx_index: batch_index as i32,
+                    is_fee_unshielding,
                 },
             )
             .collect()
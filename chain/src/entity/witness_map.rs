@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use namada_sdk::borsh::BorshSerializeExt;
-use namada_sdk::masp_primitives::merkle_tree::IncrementalWitness;
+use namada_sdk::masp_primitives::merkle_tree::{
+    CommitmentTree as MaspCommitmentTree, IncrementalWitness,
+};
 use namada_sdk::masp_primitives::sapling::Node;
 use orm::witness::WitnessInsertDb;
 use shared::height::BlockHeight;
@@ -11,6 +13,8 @@ use shared::transactional::Transactional;
 #[derive(Default, Debug)]
 struct InnerWitnessMap {
     transactional: Transactional<HashMap<usize, IncrementalWitness<Node>>>,
+    /// Whether changes were committed since the map was last persisted.
+    unpersisted: bool,
 }
 
 impl InnerWitnessMap {
@@ -19,6 +23,7 @@ impl InnerWitnessMap {
     ) -> Self {
         Self {
             transactional: Transactional::new(witness_map),
+            unpersisted: false,
         }
     }
 
@@ -30,15 +35,32 @@ impl InnerWitnessMap {
         self.transactional.rollback();
     }
 
-    fn update(&mut self, node: Node) -> Result<(), usize> {
-        for (note_pos, witness) in self.transactional.as_mut().iter_mut() {
-            witness.append(node).map_err(|()| *note_pos)?;
-        }
+    fn append_notes(
+        &mut self,
+        commitment_tree: &mut MaspCommitmentTree<Node>,
+        nodes: &[Node],
+    ) -> anyhow::Result<()> {
+        let first_note_pos = commitment_tree.size();
+        let witnesses = self.transactional.as_mut();
+
+        let new_witnesses = shared::witness::append_notes(
+            commitment_tree,
+            &mut *witnesses,
+            nodes,
+        )?;
+        witnesses.extend((first_note_pos..).zip(new_witnesses));
+
         Ok(())
     }
 
-    fn insert(&mut self, note_pos: usize, witness: IncrementalWitness<Node>) {
-        self.transactional.as_mut().insert(note_pos, witness);
+    fn get_witnesses(&self) -> HashMap<usize, IncrementalWitness<Node>> {
+        self.transactional.as_ref().clone()
+    }
+
+    fn commit(&mut self) -> bool {
+        let committed = self.transactional.commit();
+        self.unpersisted |= committed;
+        committed
     }
 
     #[allow(clippy::wrong_self_convention)]
@@ -46,7 +68,8 @@ impl InnerWitnessMap {
         &mut self,
         block_height: BlockHeight,
     ) -> Option<Vec<WitnessInsertDb>> {
-        if !self.transactional.commit() {
+        self.commit();
+        if !std::mem::take(&mut self.unpersisted) {
             return None;
         }
         Some(
@@ -79,12 +102,23 @@ impl WitnessMap {
         self.0.lock().unwrap().rollback()
     }
 
-    pub fn update(&self, node: Node) -> Result<(), usize> {
-        self.0.lock().unwrap().update(node)
+    /// Append the note commitments `nodes` of a block to
+    /// `commitment_tree`, which must not hold them yet, along with their
+    /// witnesses, and update the witnesses already in the map with them.
+    pub fn append_notes(
+        &self,
+        commitment_tree: &mut MaspCommitmentTree<Node>,
+        nodes: &[Node],
+    ) -> anyhow::Result<()> {
+        self.0.lock().unwrap().append_notes(commitment_tree, nodes)
+    }
+
+    pub fn get_witnesses(&self) -> HashMap<usize, IncrementalWitness<Node>> {
+        self.0.lock().unwrap().get_witnesses()
     }
 
-    pub fn insert(&self, note_pos: usize, witness: IncrementalWitness<Node>) {
-        self.0.lock().unwrap().insert(note_pos, witness)
+    pub fn commit(&self) -> bool {
+        self.0.lock().unwrap().commit()
     }
 
     #[allow(clippy::wrong_self_convention)]
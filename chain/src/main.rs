@@ -1,122 +1,1684 @@
+#![allow(async_fn_in_trait)]
+
 pub mod appstate;
 pub mod config;
 pub mod entity;
 pub mod services;
+pub mod storage;
+#[cfg(test)]
+mod tests;
 
 use std::collections::HashSet;
 use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::atomic::{self, AtomicBool};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
-use clap::Parser;
+use namada_sdk::borsh::BorshSerializeExt;
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::tree::TreeInsertDb;
+use orm::witness::WitnessInsertDb;
 use shared::block::Block;
+use shared::config::DbPoolConfig;
 use shared::error::{IntoMainError, MainError};
 use shared::height::{BlockHeight, FollowingHeights};
+use shared::id::Id;
 use shared::indexed_tx::IndexedTx;
-use tendermint_rpc::HttpClient;
-use tendermint_rpc::client::CompatMode;
-use tokio::signal;
+use shared::telemetry;
 use tokio::time::sleep;
 use tokio_retry::RetryIf;
-use tokio_retry::strategy::{FixedInterval, jitter};
+use tracing::Instrument;
 
 use crate::appstate::AppState;
-use crate::config::AppConfig;
+use crate::config::{
+    AppConfig, BackfillArgs, Command, ReindexArgs, ResetArgs, SnapshotCommand,
+};
 use crate::entity::chain_state::ChainState;
+use crate::entity::commit_batch::{CommitBatch, NonContiguousCommit};
 use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::snapshot::Snapshot;
 use crate::entity::tx_notes_index::TxNoteMap;
 use crate::entity::witness_map::WitnessMap;
+use crate::services::admin::{AdminCommand, AdminHandle, VerificationReport};
+use crate::services::backfill::Backfiller;
+use crate::services::block_stats::{BlockCounts, BlockStatsSink};
+use crate::services::failover::{FailoverClient, is_connection_error};
+use crate::services::prefetch::{BlockSource, Prefetcher};
+use crate::services::retry::RetryPolicy;
+use crate::services::shutdown::ExitHandle;
+use crate::services::sync_marker::SyncMarker;
+use crate::services::webhook::{WebhookConfig, WebhookDispatcher};
+use crate::services::witness_checkpoint::WitnessCheckpoint;
 use crate::services::{
+    admin as admin_service, audit as audit_service,
     cometbft as cometbft_service, db as db_service, masp as masp_service,
-    rpc as rpc_service,
+    metrics, rpc as rpc_service,
 };
+use crate::storage::Storage;
+#[cfg(any(test, feature = "test-utils"))]
+use crate::storage::memory::InMemoryStorage;
+use crate::storage::postgres::PostgresStorage;
+#[cfg(feature = "rocksdb")]
+use crate::storage::rocksdb::RocksDbStorage;
 
 const VERSION_STRING: &str = env!("VERGEN_GIT_SHA");
 const DEFAULT_INTERVAL: u64 = 5;
+const DEFAULT_MAX_INTERVAL: u64 = 60;
 
 #[tokio::main]
 async fn main() -> Result<(), MainError> {
     let AppConfig {
         cometbft_url,
         database_url,
+        db_pool,
+        rpc_round_robin,
+        rpc_recheck_interval,
+        compat_mode,
         interval,
+        retry_base,
+        retry_max_interval,
+        retry_strategy,
+        retry_max_elapsed,
+        max_retries,
         verbosity,
         starting_block_height,
-    } = AppConfig::parse();
+        notes_map_only,
+        sync_marker_path,
+        sync_lag_threshold,
+        commit_batch_size,
+        prefetch_depth,
+        witness_checkpoint_blocks,
+        witness_checkpoint_interval,
+        witness_checkpoints_kept,
+        retain_blocks,
+        flush_on_masp_txs,
+        max_rollback_depth,
+        max_block_attempts,
+        skip_failed_blocks,
+        shutdown_timeout,
+        metrics_port,
+        admin_addr,
+        admin_token,
+        block_stats_path,
+        webhook_url,
+        webhook_secret,
+        webhook_max_retries,
+        otlp_endpoint,
+        command,
+    } = shared::config::parse::<AppConfig>();
+
+    config::install_tracing_subscriber(verbosity, otlp_endpoint.as_deref());
+
+    let (reindex_args, backfill_args) = match command {
+        Some(Command::Reset(args)) => {
+            return reset(database_url, db_pool, args, notes_map_only).await;
+        }
+        Some(Command::Verify) => {
+            return verify(database_url, db_pool, notes_map_only).await;
+        }
+        Some(Command::Reprocess) => {
+            return reprocess(database_url, db_pool, notes_map_only).await;
+        }
+        Some(Command::Snapshot(command)) => {
+            let client = FailoverClient::new(
+                &cometbft_url,
+                compat_mode,
+                rpc_round_robin,
+            )
+            .await
+            .into_rpc_error()?;
 
-    config::install_tracing_subscriber(verbosity);
+            return match command {
+                SnapshotCommand::Export { out } => {
+                    export_snapshot(
+                        database_url,
+                        db_pool,
+                        client,
+                        notes_map_only,
+                        out,
+                    )
+                    .await
+                }
+                SnapshotCommand::Import { in_path } => {
+                    import_snapshot(
+                        database_url,
+                        db_pool,
+                        client,
+                        notes_map_only,
+                        in_path,
+                    )
+                    .await
+                }
+            };
+        }
+        Some(Command::Reindex(args)) => (Some(args), None),
+        Some(Command::Backfill(args)) => (None, Some(args)),
+        None => (None, None),
+    };
 
     tracing::info!(version = VERSION_STRING, "Started the namada-masp-indexer");
-    let exit_handle = must_exit_handle();
+    let exit_handle = ExitHandle::install();
+    exit_handle.enforce_timeout(Duration::from_secs(shutdown_timeout));
+    let sync_marker = SyncMarker::new(sync_marker_path, sync_lag_threshold);
+    let block_stats = BlockStatsSink::new(block_stats_path);
+    let webhooks = WebhookDispatcher::new(
+        webhook_secret
+            .filter(|_| !webhook_url.is_empty())
+            .map(|secret| WebhookConfig {
+                urls: webhook_url,
+                secret,
+                max_retries: webhook_max_retries,
+            }),
+    );
+
+    if let Some(port) = metrics_port {
+        metrics::spawn_server(port);
+    }
+
+    let retry_policy = RetryPolicy::new(
+        retry_strategy,
+        retry_base,
+        Duration::from_secs(interval.unwrap_or(DEFAULT_INTERVAL)),
+        Duration::from_secs(retry_max_interval.unwrap_or(DEFAULT_MAX_INTERVAL)),
+        retry_max_elapsed.map(Duration::from_secs),
+        max_retries,
+    );
+
+    let client =
+        FailoverClient::new(&cometbft_url, compat_mode, rpc_round_robin)
+            .await
+            .into_rpc_error()?;
+    client.spawn_recheck(Duration::from_secs(rpc_recheck_interval));
+
+    if let Some(args) = reindex_args {
+        return reindex(
+            database_url,
+            db_pool,
+            client,
+            exit_handle,
+            retry_policy,
+            notes_map_only,
+            prefetch_depth,
+            args,
+        )
+        .await;
+    }
+
+    let admin = AdminHandle::default();
+    if let (Some(addr), Some(token)) = (admin_addr, admin_token) {
+        admin_service::spawn_server(addr, token, admin.clone(), notes_map_only);
+    }
+
+    let options = CrawlOptions {
+        client,
+        retry_policy,
+        starting_block_height,
+        notes_map_only,
+        max_rollback_depth,
+        max_block_attempts,
+        skip_failed_blocks,
+        commit_batch_size,
+        prefetch_depth,
+        backfill: backfill_args,
+        witness_checkpoint_blocks,
+        witness_checkpoint_interval: witness_checkpoint_interval
+            .map(Duration::from_secs),
+        witness_checkpoints_kept,
+        retain_blocks,
+        flush_on_masp_txs,
+        block_stats,
+        webhooks,
+        admin,
+    };
+
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::warn!(
+            "Using in-memory storage, indexed data will be lost on exit"
+        );
+
+        let result = crawl(
+            InMemoryStorage::default(),
+            exit_handle,
+            sync_marker,
+            options,
+        )
+        .await;
+        telemetry::shutdown();
+
+        return result;
+    }
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+
+        let result = crawl(storage, exit_handle, sync_marker, options).await;
+        telemetry::shutdown();
+
+        return result;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    db_service::backfill_nullifiers(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    db_service::backfill_compact_outputs(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    let result = crawl(
+        PostgresStorage::new(app_state.clone()),
+        exit_handle,
+        sync_marker,
+        options,
+    )
+    .await;
+    app_state.close();
+    telemetry::shutdown();
+
+    result
+}
+
+async fn reset(
+    database_url: String,
+    db_pool: DbPoolConfig,
+    ResetArgs { to_height, all }: ResetArgs,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::error!("Cannot reset in-memory storage");
+        return Err(MainError);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+        return reset_storage(storage, to_height, all, notes_map_only).await;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    let storage = PostgresStorage::new(app_state);
+    reset_storage(storage, to_height, all, notes_map_only).await
+}
+
+/// Roll `storage` back to `to_height`, or delete all of its blocks if
+/// `all` is set, and print the resulting last synced height.
+async fn reset_storage<S: Storage>(
+    storage: S,
+    to_height: Option<u64>,
+    all: bool,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    let last_block_height =
+        storage.get_last_synced_block().await.into_db_error()?;
+    let target_height = if all {
+        None
+    } else {
+        to_height.map(BlockHeight::from)
+    };
+
+    // NB: rolling back to a height past the last synced
+    // height would move the chain state forward
+    if target_height < last_block_height {
+        storage.rollback(target_height).await.into_db_error()?;
+    } else {
+        tracing::info!(
+            ?last_block_height,
+            "Nothing to reset above the target height"
+        );
+    }
+
+    let (last_block_height, ..) =
+        load_committed_state(&storage, None, notes_map_only).await?;
+
+    println!("{}", last_block_height.map_or(0, |h| h.0));
+
+    Ok(())
+}
+
+/// Delete all indexed data from the lowest recorded failed block
+/// onwards, such that failed blocks are processed again on the next run,
+/// and print the resulting last synced height.
+async fn reprocess(
+    database_url: String,
+    db_pool: DbPoolConfig,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::error!("Cannot reprocess blocks of in-memory storage");
+        return Err(MainError);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+        return reprocess_storage(storage, notes_map_only).await;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    let storage = PostgresStorage::new(app_state);
+    reprocess_storage(storage, notes_map_only).await
+}
+
+/// Roll `storage` back below its lowest recorded failed block, and print
+/// the resulting last synced height.
+async fn reprocess_storage<S: Storage>(
+    storage: S,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    let failed_blocks = storage.get_failed_blocks().await.into_db_error()?;
+
+    let Some(first_failed) = failed_blocks.first() else {
+        tracing::info!("No failed blocks were recorded");
+        return Ok(());
+    };
+
+    for failed_block in &failed_blocks {
+        tracing::info!(
+            block_height = failed_block.block_height,
+            skipped = failed_block.skipped,
+            reason = failed_block.error,
+            "Scheduling failed block to be reprocessed"
+        );
+    }
+
+    // NB: rolling back also deletes the records of the failed blocks
+    let target_height = (first_failed.block_height > 0)
+        .then(|| BlockHeight(first_failed.block_height as u64 - 1));
+    storage.rollback(target_height).await.into_db_error()?;
+
+    let (last_block_height, ..) =
+        load_committed_state(&storage, None, notes_map_only).await?;
+
+    println!("{}", last_block_height.map_or(0, |h| h.0));
+
+    Ok(())
+}
+
+/// Fetch and process the blocks in the range given by `args` again, on
+/// top of the state committed below it, and replace their committed
+/// data. Blocks after the range must end up with the same commitment
+/// tree, otherwise they would have to be reindexed as well.
+async fn reindex(
+    database_url: String,
+    db_pool: DbPoolConfig,
+    client: FailoverClient,
+    exit_handle: ExitHandle,
+    retry_policy: RetryPolicy,
+    notes_map_only: bool,
+    prefetch_depth: u64,
+    ReindexArgs { from, to }: ReindexArgs,
+) -> Result<(), MainError> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::error!("Cannot reindex blocks of in-memory storage");
+        return Err(MainError);
+    }
+
+    let (from, to) = (BlockHeight(from), BlockHeight(to));
+
+    if from > to {
+        tracing::error!(%from, %to, "Invalid range of blocks to reindex");
+        return Err(MainError);
+    }
+
+    let options = ReindexOptions {
+        client,
+        exit_handle,
+        retry_policy,
+        notes_map_only,
+        prefetch_depth,
+    };
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+        return reindex_storage(storage, options, from, to).await;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    let storage = PostgresStorage::new(app_state);
+    reindex_storage(storage, options, from, to).await
+}
+
+/// Options of the `reindex` subcommand, shared by all storage backends.
+struct ReindexOptions {
+    client: FailoverClient,
+    exit_handle: ExitHandle,
+    retry_policy: RetryPolicy,
+    notes_map_only: bool,
+    prefetch_depth: u64,
+}
+
+/// Reindex the blocks between `from` and `to` (inclusive) of `storage`.
+async fn reindex_storage<S: Storage>(
+    storage: S,
+    ReindexOptions {
+        client,
+        exit_handle,
+        retry_policy,
+        notes_map_only,
+        prefetch_depth,
+    }: ReindexOptions,
+    from: BlockHeight,
+    to: BlockHeight,
+) -> Result<(), MainError> {
+    let last_block_height =
+        storage.get_last_synced_block().await.into_db_error()?;
+
+    if last_block_height.is_none_or(|height| height < to) {
+        tracing::error!(
+            %to,
+            ?last_block_height,
+            "Cannot reindex blocks past the last synced height"
+        );
+        return Err(MainError);
+    }
+
+    verify_chain_id(&storage, &client).await?;
+
+    if let Some(pruned_height) =
+        storage.get_pruned_height().await.into_db_error()?
+    {
+        tracing::error!(
+            %pruned_height,
+            "Cannot reindex blocks after shielded txs were pruned"
+        );
+        return Err(MainError);
+    }
+
+    // NB: replay the stored txs twice, to rebuild the state the range
+    // is processed on top of, and the commitment tree it must end up
+    // with for the blocks after it to remain valid
+    let commitment_tree = CommitmentTree::default();
+    let witness_map = WitnessMap::default();
+    let expected_tree = CommitmentTree::default();
+
+    storage
+        .replay_shielded_txs(None, {
+            let commitment_tree = commitment_tree.clone();
+            let witness_map = witness_map.clone();
+            let expected_tree = expected_tree.clone();
+            let expected_witness_map = WitnessMap::default();
+
+            move |block_height, shielded_txs| {
+                if block_height < from {
+                    masp_service::replay_block(
+                        &commitment_tree,
+                        &witness_map,
+                        block_height,
+                        &shielded_txs,
+                    )?;
+                }
+                if block_height <= to {
+                    masp_service::replay_block(
+                        &expected_tree,
+                        &expected_witness_map,
+                        block_height,
+                        &shielded_txs,
+                    )?;
+                }
+                Ok(())
+            }
+        })
+        .await
+        .into_db_error()?;
+
+    let sync_marker = SyncMarker::new(None, 0);
+    let block_source =
+        BlockSource::Prefetch(Prefetcher::new(client.clone(), prefetch_depth));
+    let mut batch = CommitBatch::default();
+
+    for block_height in (from.0..=to.0).map(BlockHeight) {
+        let outcome = RetryIf::spawn(
+            retry_policy.strategy(),
+            || {
+                build_masp_data_at_height(
+                    block_height,
+                    &exit_handle,
+                    &sync_marker,
+                    client.clone(),
+                    witness_map.clone(),
+                    commitment_tree.clone(),
+                    storage.clone(),
+                    &batch,
+                    &block_source,
+                )
+            },
+            retry_policy.condition(&exit_handle, "build_block"),
+        )
+        .await?;
+
+        match outcome {
+            BlockOutcome::Built(block) => batch.push(
+                block.chain_state,
+                &commitment_tree,
+                &witness_map,
+                block.tx_notes_index,
+                block.shielded_txs,
+                notes_map_only,
+            ),
+            BlockOutcome::Interrupted => return Ok(()),
+            BlockOutcome::ReorgDetected => {
+                tracing::error!(
+                    %block_height,
+                    "Block does not extend the committed blocks, reset the \
+                     index below the reorg instead"
+                );
+                return Err(MainError);
+            }
+            BlockOutcome::Failed { reason, .. } => {
+                tracing::error!(
+                    %block_height,
+                    %reason,
+                    "Block failed to be reindexed"
+                );
+                return Err(MainError);
+            }
+        }
+    }
+
+    if commitment_tree.root() != expected_tree.root() {
+        tracing::error!(
+            %from,
+            %to,
+            "Reindexed blocks changed the commitment tree, reindex all the \
+             blocks up to the last synced height instead"
+        );
+        return Err(MainError);
+    }
+
+    if !notes_map_only {
+        batch.checkpoint_witnesses(&witness_map);
+    }
+    storage
+        .replace_blocks(Arc::new(batch))
+        .await
+        .into_db_error()?;
+
+    tracing::info!(%from, %to, "Reindexed blocks");
+
+    Ok(())
+}
+
+/// Print the ranges of block heights missing from the index, one per
+/// line. Only blocks committed alongside their hash can be verified.
+async fn verify(
+    database_url: String,
+    db_pool: DbPoolConfig,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::error!("Cannot verify in-memory storage");
+        return Err(MainError);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+        return verify_storage(storage, notes_map_only).await;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    let storage = PostgresStorage::new(app_state);
+    verify_storage(storage, notes_map_only).await
+}
+
+/// Print the missing block ranges of `storage`, and the divergences
+/// found by auditing it.
+async fn verify_storage<S: Storage>(
+    storage: S,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    let missing_ranges =
+        storage.get_missing_block_ranges().await.into_db_error()?;
+
+    for range in &missing_ranges {
+        println!("{}-{}", range.start(), range.end());
+    }
+
+    let divergences = audit_service::audit(&storage, notes_map_only)
+        .await
+        .into_db_error()?;
+
+    for divergence in &divergences {
+        println!("{divergence}");
+    }
+
+    if missing_ranges.is_empty() && divergences.is_empty() {
+        tracing::info!("No missing block heights or divergences were found");
+        return Ok(());
+    }
+
+    if !missing_ranges.is_empty() {
+        tracing::error!(
+            num_ranges = missing_ranges.len(),
+            "Found missing block heights"
+        );
+    }
+    if !divergences.is_empty() {
+        tracing::error!(
+            num_divergences = divergences.len(),
+            "Found divergences in the committed data"
+        );
+    }
+
+    Err(MainError)
+}
+
+async fn export_snapshot(
+    database_url: String,
+    db_pool: DbPoolConfig,
+    client: FailoverClient,
+    notes_map_only: bool,
+    out: PathBuf,
+) -> Result<(), MainError> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::error!("Cannot export a snapshot of in-memory storage");
+        return Err(MainError);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+        return export_storage_snapshot(storage, client, notes_map_only, out)
+            .await;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    let storage = PostgresStorage::new(app_state);
+    export_storage_snapshot(storage, client, notes_map_only, out).await
+}
+
+/// Write a snapshot of the last committed state of `storage` to `out`.
+async fn export_storage_snapshot<S: Storage>(
+    storage: S,
+    client: FailoverClient,
+    notes_map_only: bool,
+    out: PathBuf,
+) -> Result<(), MainError> {
+    // NB: snapshots carry the full notes map
+    if let Some(pruned_height) =
+        storage.get_pruned_height().await.into_db_error()?
+    {
+        tracing::error!(
+            %pruned_height,
+            "Cannot export a snapshot after notes were pruned"
+        );
+        return Err(MainError);
+    }
+
+    let (last_block_height, commitment_tree, witness_map) =
+        load_committed_state(&storage, None, notes_map_only).await?;
+
+    let Some(block_height) = last_block_height else {
+        tracing::error!("No blocks have been synced yet, nothing to export");
+        return Err(MainError);
+    };
+
+    let block_hash =
+        match storage.get_block_hash(block_height).await.into_db_error()? {
+            Some(hash) => Id::Hash(hash),
+            None => client
+                .call(|client| {
+                    cometbft_service::query_block_hash(client, block_height)
+                })
+                .await
+                .into_rpc_error()?,
+        };
+
+    let snapshot = Snapshot {
+        chain_id: client
+            .call(cometbft_service::query_chain_id)
+            .await
+            .into_rpc_error()?,
+        block_height,
+        block_hash,
+        commitment_tree: commitment_tree.get_tree(),
+        witnesses: witness_map.get_witnesses(),
+        notes_index: storage.get_notes_index().await.into_db_error()?,
+    };
+
+    snapshot.validate().into_main_error("Snapshot error")?;
+
+    File::create(&out)
+        .with_context(|| format!("Failed to create {}", out.display()))
+        .and_then(|file| snapshot.write(&mut BufWriter::new(file)))
+        .into_main_error("Snapshot error")?;
+
+    tracing::info!(
+        %block_height,
+        chain_id = snapshot.chain_id,
+        path = %out.display(),
+        "Exported snapshot"
+    );
+
+    Ok(())
+}
+
+async fn import_snapshot(
+    database_url: String,
+    db_pool: DbPoolConfig,
+    client: FailoverClient,
+    notes_map_only: bool,
+    in_path: PathBuf,
+) -> Result<(), MainError> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if database_url.starts_with(InMemoryStorage::URL_PREFIX) {
+        tracing::error!("Cannot import a snapshot into in-memory storage");
+        return Err(MainError);
+    }
+
+    // NB: in notes map only mode, the commitment tree and witness map
+    // are rebuilt from stored txs, which snapshots do not include
+    if notes_map_only {
+        tracing::error!("Cannot import a snapshot in notes map only mode");
+        return Err(MainError);
+    }
+
+    let snapshot = File::open(&in_path)
+        .with_context(|| format!("Failed to open {}", in_path.display()))
+        .and_then(|file| Snapshot::read(&mut BufReader::new(file)))
+        .and_then(|snapshot| snapshot.validate().map(|()| snapshot))
+        .into_main_error("Snapshot error")?;
+
+    let chain_id = client
+        .call(cometbft_service::query_chain_id)
+        .await
+        .into_rpc_error()?;
+
+    if snapshot.chain_id != chain_id {
+        tracing::error!(
+            snapshot_chain_id = snapshot.chain_id,
+            chain_id,
+            "Snapshot was taken on a different chain"
+        );
+        return Err(MainError);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = database_url.strip_prefix(RocksDbStorage::URL_PREFIX) {
+        let storage = RocksDbStorage::open(path).into_db_error()?;
+        return import_storage_snapshot(
+            storage,
+            snapshot,
+            chain_id,
+            notes_map_only,
+        )
+        .await;
+    }
+
+    let app_state =
+        AppState::new(database_url, db_pool).await.into_db_error()?;
+
+    run_migrations(&app_state).await?;
+
+    let storage = PostgresStorage::new(app_state);
+    import_storage_snapshot(storage, snapshot, chain_id, notes_map_only).await
+}
+
+/// Commit the state of `snapshot`, taken on chain `chain_id`, to
+/// `storage`, which must be empty.
+async fn import_storage_snapshot<S: Storage>(
+    storage: S,
+    snapshot: Snapshot,
+    chain_id: String,
+    notes_map_only: bool,
+) -> Result<(), MainError> {
+    if let Some(last_block_height) =
+        storage.get_last_synced_block().await.into_db_error()?
+    {
+        tracing::error!(
+            %last_block_height,
+            "Snapshots can only be imported into an empty database"
+        );
+        return Err(MainError);
+    }
+
+    let Snapshot {
+        block_height,
+        block_hash,
+        commitment_tree,
+        witnesses,
+        notes_index,
+        ..
+    } = snapshot;
+
+    let mut batch = CommitBatch::default();
+    batch.push_restored(
+        ChainState::new(block_height, block_hash, block_height),
+        TreeInsertDb {
+            tree: commitment_tree.serialize_to_vec(),
+            block_height: block_height.0 as i32,
+        },
+        witnesses
+            .iter()
+            .map(|(note_pos, witness)| WitnessInsertDb {
+                witness_bytes: witness.serialize_to_vec(),
+                witness_idx: *note_pos as i32,
+                block_height: block_height.0 as i32,
+            })
+            .collect(),
+        notes_index,
+    );
+
+    storage.set_chain_id(chain_id).await.into_db_error()?;
+    storage.commit(Arc::new(batch)).await.into_db_error()?;
+
+    let (last_block_height, ..) =
+        load_committed_state(&storage, None, notes_map_only).await?;
+
+    tracing::info!(?last_block_height, "Imported snapshot");
+
+    Ok(())
+}
+
+/// Options of the crawler, shared by all storage backends.
+struct CrawlOptions {
+    client: FailoverClient,
+    retry_policy: RetryPolicy,
+    starting_block_height: Option<u64>,
+    notes_map_only: bool,
+    max_rollback_depth: u64,
+    max_block_attempts: u64,
+    skip_failed_blocks: bool,
+    commit_batch_size: u64,
+    prefetch_depth: u64,
+    /// Backfill the history of the chain up to some height with several
+    /// workers, and exit once it is committed.
+    backfill: Option<BackfillArgs>,
+    witness_checkpoint_blocks: u64,
+    witness_checkpoint_interval: Option<Duration>,
+    witness_checkpoints_kept: Option<u64>,
+    retain_blocks: Option<u64>,
+    flush_on_masp_txs: bool,
+    block_stats: BlockStatsSink,
+    webhooks: WebhookDispatcher,
+    admin: AdminHandle,
+}
+
+/// Outcome of processing a block.
+enum BlockOutcome {
+    /// The block was built, and is ready to be committed.
+    Built(BuiltBlock),
+    /// Processing was interrupted by a shutdown request.
+    Interrupted,
+    /// The parent hash of the block does not match the hash of the
+    /// last committed block.
+    ReorgDetected,
+    /// The block could not be decoded, or its masp txs could not be
+    /// applied to the commitment tree and witness map. Retrying will most
+    /// likely fail again.
+    Failed {
+        reason: String,
+        /// The block without any masp txs, committed instead if failed
+        /// blocks are skipped. Missing if the block was never decoded.
+        empty_block: Option<BuiltBlock>,
+    },
+}
+
+/// Masp data of a block, waiting to be added to a [`CommitBatch`].
+struct BuiltBlock {
+    chain_state: ChainState,
+    chain_tip: BlockHeight,
+    counts: BlockCounts,
+    tx_notes_index: TxNoteMap,
+    shielded_txs: Vec<(IndexedTx, Transaction)>,
+}
+
+async fn crawl<S: Storage>(
+    storage: S,
+    exit_handle: ExitHandle,
+    sync_marker: SyncMarker,
+    options: CrawlOptions,
+) -> Result<(), MainError> {
+    let CrawlOptions {
+        client,
+        retry_policy,
+        starting_block_height,
+        notes_map_only,
+        max_rollback_depth,
+        max_block_attempts,
+        skip_failed_blocks,
+        commit_batch_size,
+        prefetch_depth,
+        backfill,
+        witness_checkpoint_blocks,
+        witness_checkpoint_interval,
+        witness_checkpoints_kept,
+        retain_blocks,
+        flush_on_masp_txs,
+        mut block_stats,
+        mut webhooks,
+        admin,
+    } = options;
+
+    verify_chain_id(&storage, &client).await?;
+
+    if notes_map_only {
+        let pruned_height =
+            storage.get_pruned_height().await.into_db_error()?;
+
+        // NB: the notes map is rebuilt from all the stored txs on startup
+        if retain_blocks.is_some() || pruned_height.is_some() {
+            tracing::error!(
+                ?pruned_height,
+                "Shielded txs cannot be pruned in notes map only mode"
+            );
+            return Err(MainError);
+        }
+    }
+
+    let starting_block_height =
+        resolve_start_height(&storage, starting_block_height).await?;
+
+    let (last_block_height, mut commitment_tree, mut witness_map) =
+        load_committed_state(&storage, starting_block_height, notes_map_only)
+            .await?;
+
+    if let Some(height) = last_block_height {
+        metrics::LAST_SYNCED_HEIGHT.set(height.0 as i64);
+    }
+
+    let mut witness_checkpoint = WitnessCheckpoint::new(
+        Some(witness_checkpoint_blocks),
+        witness_checkpoint_interval,
+        last_block_height,
+    );
+    let (block_source, last_backfilled_height) = match backfill {
+        Some(BackfillArgs {
+            workers,
+            chunk_size,
+            to,
+        }) => {
+            let to = match to {
+                Some(to) => BlockHeight(to),
+                None => client
+                    .call(rpc_service::query_last_block_height)
+                    .await
+                    .into_rpc_error()?
+                    .unwrap_or_default(),
+            };
+            let from = last_block_height.map_or(1, |height| height.0 + 1);
+
+            tracing::info!(from, %to, workers, chunk_size, "Backfilling blocks");
+
+            let backfiller = Backfiller::new(
+                client.clone(),
+                from..=to.0,
+                workers,
+                chunk_size,
+            );
+            (BlockSource::Backfill(backfiller), Some(to))
+        }
+        None => {
+            let prefetcher = Prefetcher::new(client.clone(), prefetch_depth);
+            (BlockSource::Prefetch(prefetcher), None)
+        }
+    };
+    let mut heights = FollowingHeights::after(last_block_height);
+    let mut batch = CommitBatch::default();
+    let mut chain_tip = BlockHeight::default();
+    let mut failed_attempts = 0;
+    let mut result = Ok(());
+
+    'crawl: while let Some(block_height) = heights.next() {
+        if exit_handle.must_exit() {
+            break;
+        }
+        if last_backfilled_height.is_some_and(|to| block_height > to) {
+            tracing::info!(%block_height, "Backfill complete");
+            break;
+        }
 
-    let app_state = AppState::new(database_url).await.into_db_error()?;
+        // NB: maintenance runs and pauses only ever observe committed
+        // blocks, so the current batch is flushed beforehand
+        let mut was_paused = false;
+        loop {
+            let commands = admin.take_commands();
+            let paused = admin.is_paused();
 
-    run_migrations(&app_state).await?;
+            if commands.is_empty() && !paused {
+                break;
+            }
 
-    let (last_block_height, commitment_tree, witness_map) =
-        load_committed_state(&app_state, starting_block_height).await?;
+            if let Err(err) = flush_batch(
+                &storage,
+                &mut batch,
+                &sync_marker,
+                &mut block_stats,
+                &mut webhooks,
+                chain_tip,
+                &exit_handle,
+                &retry_policy,
+                witness_checkpoints_kept,
+                retain_blocks,
+            )
+            .await
+            {
+                result = Err(err);
+                break 'crawl;
+            }
 
-    let client = HttpClient::builder(cometbft_url.as_str().parse().unwrap())
-        .compat_mode(CompatMode::V0_37)
-        .build()
-        .unwrap();
-    let client = Arc::new(client);
+            for command in commands {
+                run_admin_command(&storage, &admin, command, notes_map_only)
+                    .await;
+            }
 
-    let internal = interval
-        .map(|millis| millis * 1000)
-        .unwrap_or(DEFAULT_INTERVAL * 1000);
-    let retry_strategy = FixedInterval::from_millis(internal).map(jitter);
+            if paused {
+                if !was_paused {
+                    tracing::info!(%block_height, "Indexing paused");
+                    was_paused = true;
+                }
 
-    for block_height in FollowingHeights::after(last_block_height) {
-        if must_exit(&exit_handle) {
-            break;
+                tokio::select! {
+                    _ = admin.changed() => {}
+                    _ = exit_handle.exited() => break 'crawl,
+                }
+            }
+        }
+        if was_paused {
+            tracing::info!(%block_height, "Indexing resumed");
         }
 
-        _ = RetryIf::spawn(
-            retry_strategy.clone(),
+        // NB: building a block only touches in-memory state, so it can
+        // be abandoned as soon as a shutdown is requested
+        let build = RetryIf::spawn(
+            retry_policy.strategy(),
             || {
                 let client = client.clone();
                 let witness_map = witness_map.clone();
                 let commitment_tree = commitment_tree.clone();
-                let app_state = app_state.clone();
-                let chain_state = ChainState::new(block_height);
+                let storage = storage.clone();
+                let exit_handle = &exit_handle;
+                let sync_marker = &sync_marker;
+                let batch = &batch;
+                let block_source = &block_source;
+
+                async move {
+                    let timer =
+                        metrics::BUILD_AND_COMMIT_DURATION.start_timer();
+
+                    let result = build_masp_data_at_height(
+                        block_height,
+                        exit_handle,
+                        sync_marker,
+                        client,
+                        witness_map,
+                        commitment_tree,
+                        storage,
+                        batch,
+                        block_source,
+                    )
+                    .await;
+
+                    if result.is_ok() {
+                        timer.observe_duration();
+                    } else {
+                        timer.stop_and_discard();
+                    }
+
+                    result
+                }
+            },
+            retry_policy.condition(&exit_handle, "build_block"),
+        )
+        .instrument(tracing::info_span!("process_block", %block_height));
+
+        let outcome = tokio::select! {
+            outcome = build => outcome,
+            _ = exit_handle.exited() => Ok(BlockOutcome::Interrupted),
+        };
+
+        let outcome = match outcome {
+            Ok(BlockOutcome::Failed {
+                reason,
+                empty_block,
+            }) => {
+                failed_attempts += 1;
+
+                if failed_attempts < max_block_attempts {
+                    let delay = retry_policy
+                        .strategy()
+                        .nth(failed_attempts as usize - 1)
+                        .unwrap_or_default();
+
+                    tracing::warn!(
+                        %block_height,
+                        attempt = failed_attempts,
+                        %reason,
+                        ?delay,
+                        "Failed to process block, retrying..."
+                    );
+
+                    tokio::select! {
+                        _ = sleep(delay) => {}
+                        _ = exit_handle.exited() => break,
+                    }
+
+                    heights = FollowingHeights::after(
+                        block_height.0.checked_sub(1).map(BlockHeight),
+                    );
+                    continue;
+                }
+
+                // NB: moving on without the masp txs of the block leaves
+                // the commitment tree diverged from the one on chain,
+                // failing the anchor check of every later masp tx
+                let empty_block = empty_block.filter(|_| skip_failed_blocks);
+
+                tracing::error!(
+                    %block_height,
+                    attempts = failed_attempts,
+                    %reason,
+                    skipped = empty_block.is_some(),
+                    "Block permanently failed to be processed"
+                );
+
+                if let Err(err) = storage
+                    .record_failed_block(
+                        block_height,
+                        reason,
+                        empty_block.is_some(),
+                    )
+                    .await
+                    .into_db_error()
+                {
+                    result = Err(err);
+                    break;
+                }
+
+                let Some(empty_block) = empty_block else {
+                    result = Err(MainError);
+                    break;
+                };
+
+                tracing::warn!(
+                    %block_height,
+                    "Skipping failed block, the commitment tree no longer \
+                     matches the one on chain"
+                );
+                witness_map.rollback();
+                commitment_tree.rollback();
+
+                Ok(BlockOutcome::Built(empty_block))
+            }
+            outcome => outcome,
+        };
+
+        match outcome {
+            Ok(BlockOutcome::Built(block)) => {
+                failed_attempts = 0;
 
-                build_and_commit_masp_data_at_height(
+                let has_masp_txs = !block.shielded_txs.is_empty();
+                chain_tip = block.chain_tip;
+
+                block_stats.stage(block_height, block.counts);
+                webhooks.stage(
                     block_height,
+                    &block.chain_state.block_hash,
+                    block.counts,
+                );
+                batch.push(
+                    block.chain_state,
+                    &commitment_tree,
+                    &witness_map,
+                    block.tx_notes_index,
+                    block.shielded_txs,
+                    notes_map_only,
+                );
+                metrics::COMMITMENT_TREE_SIZE
+                    .set(commitment_tree.size() as i64);
+
+                if !notes_map_only && witness_checkpoint.is_due(block_height) {
+                    batch.checkpoint_witnesses(&witness_map);
+                }
+
+                // NB: flush early near the tip of the chain, such
+                // that committed data lags behind as little as possible
+                let must_flush = batch.len() as u64 >= commit_batch_size
+                    || block_height >= chain_tip
+                    || flush_on_masp_txs && has_masp_txs;
+
+                if must_flush {
+                    if let Err(err) = flush_batch(
+                        &storage,
+                        &mut batch,
+                        &sync_marker,
+                        &mut block_stats,
+                        &mut webhooks,
+                        chain_tip,
+                        &exit_handle,
+                        &retry_policy,
+                        witness_checkpoints_kept,
+                        retain_blocks,
+                    )
+                    .await
+                    {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+            Ok(BlockOutcome::ReorgDetected) => {
+                // NB: the pending blocks may have been orphaned as well,
+                // so they are dropped rather than committed, and crawled
+                // again on top of the common ancestor
+                if !batch.is_empty() {
+                    tracing::warn!(
+                        num_blocks = batch.len(),
+                        "Dropping pending blocks on chain reorg"
+                    );
+                }
+                batch = CommitBatch::default();
+                block_stats.discard();
+                webhooks.discard();
+
+                let common_ancestor = match find_common_ancestor(
+                    &client,
+                    &storage,
                     &exit_handle,
-                    client,
-                    witness_map,
-                    commitment_tree,
-                    app_state,
-                    chain_state,
+                    block_height,
+                    max_rollback_depth,
+                    &retry_policy,
                 )
-            },
-            |_: &MainError| !must_exit(&exit_handle),
-        )
-        .await
+                .await
+                {
+                    Ok(common_ancestor) => common_ancestor,
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                };
+
+                tracing::warn!(
+                    %block_height,
+                    ?common_ancestor,
+                    "Rolling back blocks orphaned by chain reorg"
+                );
+
+                if let Err(err) =
+                    storage.rollback(common_ancestor).await.into_db_error()
+                {
+                    result = Err(err);
+                    break;
+                }
+                metrics::observe_reorg(block_height, common_ancestor);
+                webhooks.rollback(common_ancestor);
+
+                let last_block_height;
+                (last_block_height, commitment_tree, witness_map) =
+                    match load_committed_state(
+                        &storage,
+                        starting_block_height,
+                        notes_map_only,
+                    )
+                    .await
+                    {
+                        Ok(state) => state,
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    };
+
+                metrics::LAST_SYNCED_HEIGHT
+                    .set(last_block_height.map_or(0, |h| h.0 as i64));
+                heights = FollowingHeights::after(last_block_height);
+            }
+            Ok(BlockOutcome::Interrupted | BlockOutcome::Failed { .. }) => {}
+            Err(err) => {
+                // NB: retries only run out on a shutdown, or if the
+                // block keeps failing past the retry limits
+                if !exit_handle.must_exit() {
+                    tracing::error!(
+                        %block_height,
+                        "Block permanently failed to be processed"
+                    );
+                }
+                result = Err(err);
+                break;
+            }
+        }
+    }
+
+    // NB: persist the blocks processed before exiting. commits are never
+    // cancelled on shutdown, such that they either land in full or not
+    // at all. witnesses of blocks committed in previous batches are
+    // rebuilt on restart, if they missed their checkpoint
+    if !notes_map_only {
+        batch.checkpoint_witnesses(&witness_map);
+    }
+    _ = flush_batch(
+        &storage,
+        &mut batch,
+        &sync_marker,
+        &mut block_stats,
+        &mut webhooks,
+        chain_tip,
+        &exit_handle,
+        &retry_policy,
+        witness_checkpoints_kept,
+        retain_blocks,
+    )
+    .await;
+
+    if exit_handle.must_exit() {
+        return Ok(());
+    }
+
+    result
+}
+
+/// Carry out a maintenance run requested through the admin API. Failures
+/// are logged, rather than stopping the crawler.
+async fn run_admin_command<S: Storage>(
+    storage: &S,
+    admin: &AdminHandle,
+    command: AdminCommand,
+    notes_map_only: bool,
+) {
+    let last_synced_height = match storage.get_last_synced_block().await {
+        Ok(height) => height,
+        Err(reason) => {
+            tracing::warn!(
+                ?command,
+                ?reason,
+                "Failed to read the last synced height, skipping admin command"
+            );
+            return;
+        }
+    };
+
+    match command {
+        AdminCommand::Prune { retain_blocks } => {
+            let prune_height = last_synced_height
+                .and_then(|height| height.0.checked_sub(retain_blocks))
+                .filter(|&height| height > 0);
+
+            let Some(prune_height) = prune_height else {
+                tracing::info!(
+                    retain_blocks,
+                    "No blocks to prune, as requested through the admin API"
+                );
+                return;
+            };
+
+            match storage.prune(BlockHeight(prune_height)).await {
+                Ok(pruned_height) => tracing::info!(
+                    ?pruned_height,
+                    "Pruned old shielded txs and notes, as requested through \
+                     the admin API"
+                ),
+                Err(reason) => tracing::warn!(
+                    ?reason,
+                    "Failed to prune old shielded txs and notes"
+                ),
+            }
+        }
+        AdminCommand::Verify => {
+            let missing_ranges = storage.get_missing_block_ranges().await;
+            let divergences =
+                audit_service::audit(storage, notes_map_only).await;
+
+            let (missing_ranges, divergences) =
+                match (missing_ranges, divergences) {
+                    (Ok(missing_ranges), Ok(divergences)) => {
+                        (missing_ranges, divergences)
+                    }
+                    (Err(reason), _) | (_, Err(reason)) => {
+                        tracing::warn!(
+                            ?reason,
+                            "Failed to verify the committed data"
+                        );
+                        return;
+                    }
+                };
+
+            tracing::info!(
+                ?last_synced_height,
+                num_missing_ranges = missing_ranges.len(),
+                num_divergences = divergences.len(),
+                "Verified the committed data, as requested through the admin \
+                 API"
+            );
+
+            admin.record_verification(VerificationReport {
+                last_synced_height: last_synced_height.map(|height| height.0),
+                missing_ranges: missing_ranges
+                    .iter()
+                    .map(|range| format!("{}-{}", range.start(), range.end()))
+                    .collect(),
+                divergences: divergences
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            });
+        }
+    }
+}
+
+/// Commit all the blocks in `batch` to storage, and clear it. Only the
+/// last `witness_checkpoints_kept` witness map checkpoints are kept, if
+/// the batch persisted a new one, and only the shielded txs and notes of
+/// the last `retain_blocks` blocks are kept, if set.
+#[allow(clippy::too_many_arguments)]
+async fn flush_batch<S: Storage>(
+    storage: &S,
+    batch: &mut CommitBatch,
+    sync_marker: &SyncMarker,
+    block_stats: &mut BlockStatsSink,
+    webhooks: &mut WebhookDispatcher,
+    chain_tip: BlockHeight,
+    exit_handle: &ExitHandle,
+    retry_policy: &RetryPolicy,
+    witness_checkpoints_kept: Option<u64>,
+    retain_blocks: Option<u64>,
+) -> Result<(), MainError> {
+    let Some(block_height) = batch.block_height() else {
+        return Ok(());
+    };
+
+    let pending = Arc::new(std::mem::take(batch));
+    let started_at = Instant::now();
+
+    let result = RetryIf::spawn(
+        retry_policy.strategy(),
+        || {
+            let storage = storage.clone();
+            let pending = Arc::clone(&pending);
+
+            async move {
+                let timer = metrics::COMMIT_DURATION.start_timer();
+                let span = tracing::info_span!(
+                    "commit",
+                    %block_height,
+                    num_blocks = pending.len()
+                );
+
+                match storage.commit(pending).instrument(span).await {
+                    Ok(()) => {
+                        metrics::LAST_COMMIT_DURATION
+                            .set(timer.stop_and_record());
+                        Ok(Ok(()))
+                    }
+                    // NB: retrying would fail in the same way, since
+                    // the committed heights are not about to change
+                    Err(err) if is_non_contiguous_commit(&err) => {
+                        tracing::error!(
+                            reason = ?err,
+                            "Refusing to leave a gap in the committed blocks"
+                        );
+                        Ok(Err(MainError))
+                    }
+                    Err(err) => Err(err).into_db_error(),
+                }
+            }
+        },
+        retry_policy.condition(exit_handle, "commit"),
+    )
+    .await
+    .and_then(|result| result);
+
+    if let Err(err) = result {
+        tracing::error!(
+            %block_height,
+            num_blocks = pending.len(),
+            "Failed to commit batch of blocks"
+        );
+        return Err(err);
+    }
+
+    if let Some(keep) =
+        witness_checkpoints_kept.filter(|_| !pending.witnesses.is_empty())
+    {
+        // NB: older checkpoints are only needed to serve past witness
+        // maps, so failing to delete them is not fatal
+        if let Err(err) = storage.prune_witness_checkpoints(keep).await {
+            tracing::warn!(
+                reason = ?err,
+                "Failed to prune old witness map checkpoints"
+            );
+        }
+    }
+
+    let prune_height = retain_blocks
+        .and_then(|retain_blocks| block_height.0.checked_sub(retain_blocks))
+        .filter(|&height| height > 0);
+
+    if let Some(prune_height) = prune_height {
+        // NB: not fatal either, pruning catches up on the next commit
+        if let Err(err) = storage.prune(BlockHeight(prune_height)).await {
+            tracing::warn!(
+                reason = ?err,
+                "Failed to prune old shielded txs and notes"
+            );
+        }
     }
 
+    metrics::observe_committed_block(block_height, pending.shielded_txs.len());
+    sync_marker.update(block_height, chain_tip);
+    block_stats.flush(started_at.elapsed());
+    webhooks.flush();
+
     Ok(())
 }
 
-#[inline]
-fn must_exit(handle: &AtomicBool) -> bool {
-    handle.load(atomic::Ordering::Relaxed)
+fn is_non_contiguous_commit(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.is::<NonContiguousCommit>())
 }
 
-fn must_exit_handle() -> Arc<AtomicBool> {
-    let handle = Arc::new(AtomicBool::new(false));
-    let task_handle = Arc::clone(&handle);
-    tokio::spawn(async move {
-        signal::ctrl_c()
-            .await
-            .expect("Error receiving interrupt signal");
-        tracing::info!("Ctrl-c received");
-        task_handle.store(true, atomic::Ordering::Relaxed);
-    });
-    handle
+/// Walk back from the last committed block below `block_height`, until
+/// a committed block whose hash matches the one on chain is found. Returns
+/// `None` if all committed blocks were orphaned.
+async fn find_common_ancestor<S: Storage>(
+    client: &FailoverClient,
+    storage: &S,
+    exit_handle: &ExitHandle,
+    block_height: BlockHeight,
+    max_rollback_depth: u64,
+    retry_policy: &RetryPolicy,
+) -> Result<Option<BlockHeight>, MainError> {
+    // NB: pending blocks past the last committed one are dropped on a
+    // reorg, so the search starts below them
+    let last_committed = storage
+        .get_last_synced_block()
+        .await
+        .into_db_error()?
+        .map_or(0, |height| height.0)
+        .min(block_height.0.saturating_sub(1));
+
+    // NB: height 0 stands for the state before any block was committed
+    for height in (0..=last_committed).rev().map(BlockHeight) {
+        if last_committed - height.0 > max_rollback_depth {
+            tracing::error!(
+                %block_height,
+                max_rollback_depth,
+                "Chain reorg is deeper than the max rollback depth, aborting"
+            );
+            return Err(MainError);
+        }
+
+        if height.0 == 0 {
+            break;
+        }
+
+        let check = RetryIf::spawn(
+            retry_policy.strategy(),
+            || is_canonical_block(client, storage, height),
+            retry_policy.condition(exit_handle, "check_block_hash"),
+        );
+
+        let is_canonical = tokio::select! {
+            is_canonical = check => is_canonical?,
+            _ = exit_handle.exited() => return Err(MainError),
+        };
+
+        if is_canonical {
+            return Ok(Some(height));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check whether the block committed at `block_height` is still part of
+/// the canonical chain. Heights without a committed block hash are
+/// assumed to be canonical.
+async fn is_canonical_block<S: Storage>(
+    client: &FailoverClient,
+    storage: &S,
+    block_height: BlockHeight,
+) -> Result<bool, MainError> {
+    let Some(committed_hash) =
+        storage.get_block_hash(block_height).await.into_db_error()?
+    else {
+        return Ok(true);
+    };
+
+    let canonical_hash = client
+        .call(|client| cometbft_service::query_block_hash(client, block_height))
+        .await
+        .into_rpc_error()?;
+
+    Ok(committed_hash == canonical_hash.to_string())
 }
 
 async fn run_migrations(app_state: &AppState) -> Result<(), MainError> {
@@ -154,35 +1716,137 @@ async fn run_migrations(app_state: &AppState) -> Result<(), MainError> {
     }
 }
 
-async fn load_committed_state(
-    app_state: &AppState,
+/// Check that the node reports the chain id of the network the stored
+/// data was indexed from. The chain id is recorded if none was recorded
+/// yet, or if nothing was committed since it was.
+async fn verify_chain_id<S: Storage>(
+    storage: &S,
+    client: &FailoverClient,
+) -> Result<(), MainError> {
+    let chain_id = client
+        .call(cometbft_service::query_chain_id)
+        .await
+        .into_rpc_error()?;
+    let recorded_chain_id = storage.get_chain_id().await.into_db_error()?;
+
+    let Some(recorded_chain_id) = recorded_chain_id else {
+        tracing::info!(chain_id, "Recorded chain id");
+        return storage.set_chain_id(chain_id).await.into_db_error();
+    };
+
+    if recorded_chain_id == chain_id {
+        return Ok(());
+    }
+
+    if storage
+        .get_last_synced_block()
+        .await
+        .into_db_error()?
+        .is_some()
+    {
+        tracing::error!(
+            recorded_chain_id,
+            chain_id,
+            "The node is on a different chain than the one indexed in the \
+             database"
+        );
+        return Err(MainError);
+    }
+
+    tracing::info!(chain_id, "Recorded chain id");
+    storage.set_chain_id(chain_id).await.into_db_error()
+}
+
+/// Reconcile the configured start height with the one recorded in
+/// `storage`, and record the one in use.
+///
+/// Once blocks were committed, the start height can no longer change,
+/// since the commitment tree would not match the chain anymore.
+async fn resolve_start_height<S: Storage>(
+    storage: &S,
+    starting_block_height: Option<u64>,
+) -> Result<Option<u64>, MainError> {
+    let configured = starting_block_height.map(BlockHeight::from);
+    let recorded = storage.get_start_height().await.into_db_error()?;
+    let synced_block_height =
+        storage.get_last_synced_block().await.into_db_error()?;
+
+    let start_height = match (configured, recorded) {
+        (Some(configured), Some(recorded))
+            if configured != recorded && synced_block_height.is_some() =>
+        {
+            tracing::error!(
+                %configured,
+                %recorded,
+                "Start height differs from the one blocks were indexed \
+                 from, reset all committed data to change it"
+            );
+            return Err(MainError);
+        }
+        (Some(configured), _) => configured,
+        (None, Some(recorded)) => {
+            tracing::info!(%recorded, "Using recorded start height");
+            return Ok(Some(recorded.0));
+        }
+        (None, None) => return Ok(None),
+    };
+
+    if recorded != Some(start_height) {
+        storage
+            .set_start_height(start_height)
+            .await
+            .into_db_error()?;
+        tracing::info!(%start_height, "Recorded start height");
+    }
+
+    Ok(Some(start_height.0))
+}
+
+async fn load_committed_state<S: Storage>(
+    storage: &S,
     starting_block_height: Option<u64>,
+    notes_map_only: bool,
 ) -> Result<(Option<BlockHeight>, CommitmentTree, WitnessMap), MainError> {
     tracing::info!("Loading last committed state from db...");
 
-    let last_block_height = db_service::get_last_synced_block(
-        app_state.get_db_connection().await.into_db_error()?,
-    )
-    .await
-    .into_db_error()?;
+    let synced_block_height =
+        storage.get_last_synced_block().await.into_db_error()?;
 
     let last_block_height = std::cmp::max(
-        last_block_height,
+        synced_block_height,
         starting_block_height.map(BlockHeight::from),
     );
 
-    let commitment_tree = db_service::get_last_commitment_tree(
-        app_state.get_db_connection().await.into_db_error()?,
-    )
-    .await
-    .into_db_error()?
-    .unwrap_or_default();
+    let (commitment_tree, witness_map) = if notes_map_only {
+        rebuild_committed_state(
+            storage,
+            None,
+            CommitmentTree::default(),
+            WitnessMap::default(),
+        )
+        .await?
+    } else {
+        let commitment_tree = storage
+            .get_last_commitment_tree()
+            .await
+            .into_db_error()?
+            .unwrap_or_default();
 
-    let witness_map = db_service::get_last_witness_map(
-        app_state.get_db_connection().await.into_db_error()?,
-    )
-    .await
-    .into_db_error()?;
+        let (witness_height, witness_map) =
+            storage.get_last_witness_map().await.into_db_error()?;
+
+        if witness_height < synced_block_height {
+            load_witness_checkpoint(
+                storage,
+                witness_height,
+                commitment_tree,
+                witness_map,
+            )
+            .await?
+        } else {
+            (commitment_tree, witness_map)
+        }
+    };
 
     let commitment_tree_len = commitment_tree.size();
     let witness_map_len = witness_map.size();
@@ -201,34 +1865,118 @@ async fn load_committed_state(
     shared::error::ok((last_block_height, commitment_tree, witness_map))
 }
 
-async fn build_and_commit_masp_data_at_height(
+/// Rebuild the witness map from its last checkpoint at `witness_height`,
+/// up to the last synced height, and check that it ends up matching the
+/// last committed commitment tree.
+async fn load_witness_checkpoint<S: Storage>(
+    storage: &S,
+    witness_height: Option<BlockHeight>,
+    last_commitment_tree: CommitmentTree,
+    witness_map: WitnessMap,
+) -> Result<(CommitmentTree, WitnessMap), MainError> {
+    let commitment_tree = match witness_height {
+        Some(height) => storage
+            .get_commitment_tree_at(height)
+            .await
+            .into_db_error()?
+            .unwrap_or_default(),
+        None => CommitmentTree::default(),
+    };
+
+    let (commitment_tree, witness_map) = rebuild_committed_state(
+        storage,
+        witness_height,
+        commitment_tree,
+        witness_map,
+    )
+    .await?;
+
+    if commitment_tree.root() != last_commitment_tree.root() {
+        return Err(anyhow::anyhow!(
+            "Invalid database state: Commitment tree rebuilt from the witness \
+             map checkpoint at {witness_height:?} does not match the last \
+             committed one"
+        ))
+        .into_db_error();
+    }
+
+    shared::error::ok((commitment_tree, witness_map))
+}
+
+/// Replay the shielded txs committed after `after` on top of the given
+/// commitment tree and witness map.
+async fn rebuild_committed_state<S: Storage>(
+    storage: &S,
+    after: Option<BlockHeight>,
+    commitment_tree: CommitmentTree,
+    witness_map: WitnessMap,
+) -> Result<(CommitmentTree, WitnessMap), MainError> {
+    tracing::info!(
+        ?after,
+        "Rebuilding commitment tree and witness map from stored shielded \
+         txs..."
+    );
+
+    storage
+        .replay_shielded_txs(after, {
+            let commitment_tree = commitment_tree.clone();
+            let witness_map = witness_map.clone();
+
+            move |block_height, shielded_txs| {
+                masp_service::replay_block(
+                    &commitment_tree,
+                    &witness_map,
+                    block_height,
+                    &shielded_txs,
+                )
+            }
+        })
+        .await
+        .into_db_error()?;
+
+    tracing::info!(
+        commitment_tree_len = commitment_tree.size(),
+        witness_map_len = witness_map.size(),
+        "Rebuilt commitment tree and witness map"
+    );
+
+    shared::error::ok((commitment_tree, witness_map))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn build_masp_data_at_height<S: Storage>(
     block_height: BlockHeight,
-    exit_handle: &AtomicBool,
-    client: Arc<HttpClient>,
+    exit_handle: &ExitHandle,
+    sync_marker: &SyncMarker,
+    client: FailoverClient,
     witness_map: WitnessMap,
     commitment_tree: CommitmentTree,
-    app_state: AppState,
-    chain_state: ChainState,
-) -> Result<(), MainError> {
-    if must_exit(exit_handle) {
-        return Ok(());
+    storage: S,
+    batch: &CommitBatch,
+    block_source: &BlockSource,
+) -> Result<BlockOutcome, MainError> {
+    if exit_handle.must_exit() {
+        return Ok(BlockOutcome::Interrupted);
     }
 
     // NB: rollback changes from previous failed commit attempts
     witness_map.rollback();
     commitment_tree.rollback();
 
-    let conn_obj = app_state.get_db_connection().await.into_db_error()?;
-
     tracing::info!(
         %block_height,
         "Attempting to process new block"
     );
 
-    if !rpc_service::is_block_committed(&client, &block_height)
+    let chain_tip = client
+        .call(rpc_service::query_last_block_height)
         .await
         .into_rpc_error()?
-    {
+        .unwrap_or_default();
+    metrics::observe_chain_tip(chain_tip);
+
+    if block_height > chain_tip {
+        sync_marker.update(BlockHeight(block_height.0 - 1), chain_tip);
         tracing::warn!(
             %block_height,
             "Block was not processed, retrying..."
@@ -241,10 +1989,24 @@ async fn build_and_commit_masp_data_at_height(
             %block_height,
             "Fetching block data from CometBFT"
         );
-        let block_data =
-            cometbft_service::query_masp_txs_in_block(&client, block_height)
-                .await
-                .into_rpc_error()?;
+        let block_data = match block_source
+            .fetch(block_height, chain_tip)
+            .instrument(tracing::info_span!("fetch_block"))
+            .await
+        {
+            Ok(block_data) => block_data,
+            // NB: the node is retried until it is reachable again, while
+            // errors specific to the block count as failed attempts
+            Err(err) if is_connection_error(&err) => {
+                return Err(err).into_rpc_error();
+            }
+            Err(err) => {
+                return Ok(BlockOutcome::Failed {
+                    reason: format!("{err:#}"),
+                    empty_block: None,
+                });
+            }
+        };
         tracing::info!(
             %block_height,
             "Acquired block data from CometBFT"
@@ -252,6 +2014,29 @@ async fn build_and_commit_masp_data_at_height(
         block_data
     };
 
+    if let Some(parent_hash) = &block_data.header.last_block_hash {
+        let parent_height = BlockHeight(block_height.0 - 1);
+        let committed_parent_hash = match batch.block_hash(parent_height) {
+            Some(hash) => Some(hash.to_owned()),
+            None => storage
+                .get_block_hash(parent_height)
+                .await
+                .into_db_error()?,
+        };
+
+        if committed_parent_hash
+            .is_some_and(|hash| hash != parent_hash.to_string())
+        {
+            tracing::warn!(
+                %block_height,
+                %parent_hash,
+                "Parent hash does not match committed block, detected \
+                 chain reorg"
+            );
+            return Ok(BlockOutcome::ReorgDetected);
+        }
+    }
+
     let mut shielded_txs = Vec::new();
     let mut tx_notes_index = TxNoteMap::default();
 
@@ -261,50 +2046,89 @@ async fn build_and_commit_masp_data_at_height(
         "Processing new masp transactions...",
     );
 
-    let mut note_pos = commitment_tree.size();
+    let first_note_pos = commitment_tree.size();
+    let mut note_commitments = Vec::new();
 
-    for (new_masp_tx_index, mut indexed_tx) in
+    let chain_state = ChainState::from_block(&block_data, chain_tip);
+    let empty_block = || BuiltBlock {
+        chain_state: chain_state.clone(),
+        chain_tip,
+        counts: BlockCounts::default(),
+        tx_notes_index: TxNoteMap::default(),
+        shielded_txs: vec![],
+    };
+
+    let valid_order =
         lookup_valid_commitment_tree(&client, &commitment_tree, &block_data)
-            .await?
-            .into_iter()
-            .enumerate()
+            .instrument(tracing::info_span!("extract_masp_txs"))
+            .await?;
+    let (valid_order, fee_unshields) = match valid_order {
+        Ok(valid_order) => valid_order,
+        Err(err) => {
+            return Ok(BlockOutcome::Failed {
+                reason: format!("{err:#}"),
+                empty_block: Some(empty_block()),
+            });
+        }
+    };
+
+    let update_span = tracing::info_span!("update_witnesses").entered();
+
+    for (new_masp_tx_index, mut indexed_tx) in
+        valid_order.into_iter().enumerate()
     {
         let masp_tx = block_data.get_masp_tx(indexed_tx).unwrap();
+        let is_fee_unshielding = fee_unshields.contains(&indexed_tx);
 
         indexed_tx.masp_tx_index = new_masp_tx_index.into();
 
-        masp_service::update_witness_map_and_note_index(
-            &mut note_pos,
-            &commitment_tree,
-            &mut tx_notes_index,
-            &witness_map,
+        tx_notes_index.insert(
             indexed_tx,
-            masp_tx,
-        )
-        .into_masp_error()?;
+            first_note_pos + note_commitments.len(),
+            is_fee_unshielding,
+        );
+        note_commitments.extend(masp_service::note_commitments(masp_tx));
 
         shielded_txs.push((indexed_tx, masp_tx.clone()));
     }
 
-    db_service::commit(
-        &conn_obj,
+    if let Err(err) = masp_service::update_witness_map(
+        &commitment_tree,
+        &witness_map,
+        &note_commitments,
+    ) {
+        return Ok(BlockOutcome::Failed {
+            reason: format!("{err:#}"),
+            empty_block: Some(empty_block()),
+        });
+    }
+
+    update_span.exit();
+
+    Ok(BlockOutcome::Built(BuiltBlock {
         chain_state,
-        commitment_tree,
-        witness_map,
+        chain_tip,
+        counts: BlockCounts {
+            num_transactions: shielded_txs.len(),
+            first_note_pos,
+            num_notes: note_commitments.len(),
+            num_fee_unshieldings: fee_unshields.len(),
+        },
         tx_notes_index,
         shielded_txs,
-    )
-    .await
-    .into_db_error()?;
-
-    Ok(())
+    }))
 }
 
+/// Find the order in which the masp txs of `block` were applied to the
+/// commitment tree, along with the fee unshieldings among them.
+///
+/// Returns an inner error if the masp txs cannot be applied in any order
+/// yielding an anchor known to the chain, which retrying will not fix.
 async fn lookup_valid_commitment_tree(
-    client: &HttpClient,
+    client: &FailoverClient,
     commitment_tree: &CommitmentTree,
     block: &Block,
-) -> Result<Vec<IndexedTx>, MainError> {
+) -> Result<anyhow::Result<(Vec<IndexedTx>, HashSet<IndexedTx>)>, MainError> {
     use itertools::Itertools;
 
     let all_indexed_txs: Vec<_> = block.indexed_txs().collect();
@@ -330,8 +2154,11 @@ async fn lookup_valid_commitment_tree(
         for indexed_tx in fee_unshield_set {
             let masp_tx = block.get_masp_tx(indexed_tx).unwrap();
 
-            masp_service::update_commitment_tree(commitment_tree, masp_tx)
-                .into_masp_error()?;
+            if let Err(err) =
+                masp_service::update_commitment_tree(commitment_tree, masp_tx)
+            {
+                return Ok(Err(err));
+            }
 
             correct_order.push(indexed_tx);
             fee_unshields.insert(indexed_tx);
@@ -345,25 +2172,31 @@ async fn lookup_valid_commitment_tree(
         {
             let masp_tx = block.get_masp_tx(indexed_tx).unwrap();
 
-            masp_service::update_commitment_tree(commitment_tree, masp_tx)
-                .into_masp_error()?;
+            if let Err(err) =
+                masp_service::update_commitment_tree(commitment_tree, masp_tx)
+            {
+                return Ok(Err(err));
+            }
 
             correct_order.push(indexed_tx);
         }
 
-        if cometbft_service::query_commitment_tree_anchor_existence(
-            client,
-            commitment_tree.root(),
-        )
-        .await
-        .into_masp_error()?
+        let root = commitment_tree.root();
+
+        if client
+            .call(|client| {
+                cometbft_service::query_commitment_tree_anchor_existence(
+                    client, root,
+                )
+            })
+            .await
+            .into_rpc_error()?
         {
-            return Ok(correct_order);
+            return Ok(Ok((correct_order, fee_unshields)));
         }
     }
 
-    Err(anyhow::anyhow!(
+    Ok(Err(anyhow::anyhow!(
         "Couldn't find a valid permutation of fee unshieldings"
-    ))
-    .into_masp_error()
+    )))
 }
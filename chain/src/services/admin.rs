@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Query, State};
+use axum::http::{Request, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tokio::sync::Notify;
+
+use super::metrics;
+
+/// Maintenance run requested through the admin API, carried out by the
+/// crawler in between two blocks.
+#[derive(Debug)]
+pub enum AdminCommand {
+    /// Prune the shielded txs, notes, nullifiers and compact outputs of
+    /// all but the last `retain_blocks` synced blocks.
+    Prune { retain_blocks: u64 },
+    /// Look for missing block heights and divergences in the committed
+    /// data, like the `verify` subcommand.
+    Verify,
+}
+
+/// Outcome of the last verification run requested through the admin
+/// API.
+#[derive(Clone, Serialize)]
+pub struct VerificationReport {
+    pub last_synced_height: Option<u64>,
+    pub missing_ranges: Vec<String>,
+    pub divergences: Vec<String>,
+}
+
+#[derive(Default)]
+struct Shared {
+    paused: AtomicBool,
+    commands: Mutex<VecDeque<AdminCommand>>,
+    last_verification: Mutex<Option<VerificationReport>>,
+    /// Signalled whenever indexing is paused or resumed, or a command
+    /// is queued.
+    changed: Notify,
+}
+
+/// Control state shared between the admin API and the crawler.
+#[derive(Clone, Default)]
+pub struct AdminHandle(Arc<Shared>);
+
+impl AdminHandle {
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::Acquire)
+    }
+
+    /// Take the commands queued since the last call, in the order they
+    /// were requested.
+    pub fn take_commands(&self) -> Vec<AdminCommand> {
+        self.0.commands.lock().unwrap().drain(..).collect()
+    }
+
+    /// Wait until indexing is paused or resumed, or a command is queued.
+    pub async fn changed(&self) {
+        self.0.changed.notified().await
+    }
+
+    pub fn record_verification(&self, report: VerificationReport) {
+        *self.0.last_verification.lock().unwrap() = Some(report);
+    }
+
+    fn pending_commands(&self) -> usize {
+        self.0.commands.lock().unwrap().len()
+    }
+
+    fn last_verification(&self) -> Option<VerificationReport> {
+        self.0.last_verification.lock().unwrap().clone()
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.0.paused.store(paused, Ordering::Release);
+        self.0.changed.notify_one();
+    }
+
+    fn queue(&self, command: AdminCommand) {
+        self.0.commands.lock().unwrap().push_back(command);
+        self.0.changed.notify_one();
+    }
+}
+
+#[derive(Clone)]
+struct AdminState {
+    handle: AdminHandle,
+    token: Arc<str>,
+    notes_map_only: bool,
+}
+
+#[derive(Serialize)]
+struct StatusResponse {
+    last_synced_height: u64,
+    chain_tip: u64,
+    lag: u64,
+    /// Time taken by the last commit, in seconds, if any.
+    last_commit_duration: Option<f64>,
+    paused: bool,
+    pending_commands: usize,
+    last_verification: Option<VerificationReport>,
+}
+
+/// Body of the error responses, shaped like those of the webserver.
+#[derive(Serialize)]
+struct ErrorResponse {
+    code: &'static str,
+    message: String,
+}
+
+fn error_response(
+    status_code: StatusCode,
+    code: &'static str,
+    message: impl Into<String>,
+) -> Response {
+    let body = ErrorResponse {
+        code,
+        message: message.into(),
+    };
+    (status_code, Json(body)).into_response()
+}
+
+#[derive(Deserialize)]
+struct PruneQueryParams {
+    retain_blocks: u64,
+}
+
+/// Serve the admin API on `addr`, authenticating requests with the
+/// bearer `token`. Pauses and maintenance runs are carried out by the
+/// crawler holding `handle`.
+pub fn spawn_server(
+    addr: SocketAddr,
+    token: String,
+    handle: AdminHandle,
+    notes_map_only: bool,
+) {
+    let state = AdminState {
+        handle,
+        token: token.into(),
+        notes_map_only,
+    };
+
+    let router = Router::new()
+        .route("/status", get(get_status))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/prune", post(prune))
+        .route("/verify", post(verify))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_token,
+        ))
+        .with_state(state);
+
+    tokio::spawn(async move {
+        tracing::info!(%addr, "Serving admin API");
+
+        if let Err(reason) = axum::Server::bind(&addr)
+            .serve(router.into_make_service())
+            .await
+        {
+            tracing::error!(?reason, "Admin server shut down unexpectedly");
+        }
+    });
+}
+
+async fn require_token<B>(
+    State(state): State<AdminState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match token {
+        // NB: compared in constant time, such that the token cannot be
+        // guessed from response timings
+        Some(token)
+            if token.as_bytes().ct_eq(state.token.as_bytes()).into() =>
+        {
+            next.run(req).await
+        }
+        Some(_) => error_response(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "Invalid token",
+        ),
+        None => error_response(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "Missing bearer token in the Authorization header",
+        ),
+    }
+}
+
+async fn get_status(State(state): State<AdminState>) -> Json<StatusResponse> {
+    // NB: no commit has been timed yet if the histogram is empty
+    let has_committed = metrics::COMMIT_DURATION.get_sample_count() > 0;
+    let last_commit_duration =
+        has_committed.then(|| metrics::LAST_COMMIT_DURATION.get());
+
+    Json(StatusResponse {
+        last_synced_height: metrics::LAST_SYNCED_HEIGHT.get() as u64,
+        chain_tip: metrics::CHAIN_TIP_HEIGHT.get() as u64,
+        lag: metrics::LAG_BLOCKS.get() as u64,
+        last_commit_duration,
+        paused: state.handle.is_paused(),
+        pending_commands: state.handle.pending_commands(),
+        last_verification: state.handle.last_verification(),
+    })
+}
+
+async fn pause(State(state): State<AdminState>) -> StatusCode {
+    tracing::info!("Pause of indexing requested through the admin API");
+    state.handle.set_paused(true);
+    StatusCode::NO_CONTENT
+}
+
+async fn resume(State(state): State<AdminState>) -> StatusCode {
+    tracing::info!("Resumption of indexing requested through the admin API");
+    state.handle.set_paused(false);
+    StatusCode::NO_CONTENT
+}
+
+async fn prune(
+    State(state): State<AdminState>,
+    Query(params): Query<PruneQueryParams>,
+) -> Response {
+    // NB: the notes map is rebuilt from all the stored txs on startup
+    if state.notes_map_only {
+        return error_response(
+            StatusCode::CONFLICT,
+            "UNAVAILABLE",
+            "Shielded txs cannot be pruned in notes map only mode",
+        );
+    }
+    if params.retain_blocks == 0 {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "INVALID_REQUEST",
+            "retain_blocks must be positive",
+        );
+    }
+
+    state.handle.queue(AdminCommand::Prune {
+        retain_blocks: params.retain_blocks,
+    });
+    StatusCode::ACCEPTED.into_response()
+}
+
+async fn verify(State(state): State<AdminState>) -> StatusCode {
+    state.handle.queue(AdminCommand::Verify);
+    StatusCode::ACCEPTED
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use namada_sdk::masp_primitives::sapling::Node;
+use shared::height::BlockHeight;
+
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::witness_map::WitnessMap;
+use crate::services::masp as masp_service;
+use crate::storage::Storage;
+
+/// Inconsistency between the committed data and the data recomputed
+/// from the stored shielded txs.
+pub struct Divergence {
+    pub block_height: Option<BlockHeight>,
+    pub reason: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.block_height {
+            Some(block_height) => write!(f, "{block_height}: {}", self.reason),
+            None => write!(f, "-: {}", self.reason),
+        }
+    }
+}
+
+/// Notes appended to the commitment tree by a replayed block, and the
+/// resulting root.
+struct ReplayedBlock {
+    block_height: BlockHeight,
+    first_note_position: usize,
+    num_notes: usize,
+    root: Node,
+}
+
+/// Check the invariants of the committed data, returning every
+/// divergence found:
+///
+/// - the stored commitment trees match the ones recomputed from the stored
+///   shielded txs;
+/// - the witnesses of the last witness map checkpoint match the commitment tree
+///   at its height;
+/// - the notes map positions are gapless, and each note was committed at the
+///   height the replayed txs appended it at.
+///
+/// Checks that replay the shielded txs are skipped if any were pruned.
+pub async fn audit<S: Storage>(
+    storage: &S,
+    notes_map_only: bool,
+) -> anyhow::Result<Vec<Divergence>> {
+    let mut divergences = Vec::new();
+
+    let pruned_height = storage.get_pruned_height().await?;
+
+    let replayed = match pruned_height {
+        Some(pruned_height) => {
+            tracing::warn!(
+                %pruned_height,
+                "Shielded txs were pruned, skipping the checks replaying them"
+            );
+            None
+        }
+        None => Some(replay(storage).await?),
+    };
+
+    if let Some(blocks) = replayed.as_deref().filter(|_| !notes_map_only) {
+        audit_commitment_trees(storage, blocks, &mut divergences).await?;
+    }
+
+    if !notes_map_only {
+        audit_witness_map(storage, &mut divergences).await?;
+    }
+
+    audit_notes_index(
+        storage,
+        replayed.as_deref(),
+        pruned_height.is_some(),
+        &mut divergences,
+    )
+    .await?;
+
+    Ok(divergences)
+}
+
+/// Rebuild the commitment tree from all the stored shielded txs, and
+/// return the notes appended by each block.
+async fn replay<S: Storage>(storage: &S) -> anyhow::Result<Vec<ReplayedBlock>> {
+    let commitment_tree = CommitmentTree::default();
+    let blocks = Arc::new(Mutex::new(Vec::new()));
+
+    storage
+        .replay_shielded_txs(None, {
+            let commitment_tree = commitment_tree.clone();
+            let blocks = Arc::clone(&blocks);
+            // NB: witnesses are checked against the stored trees
+            // instead, which is way cheaper than tracking them all
+            let witness_map = WitnessMap::default();
+
+            move |block_height, shielded_txs| {
+                let first_note_position = commitment_tree.size();
+
+                masp_service::replay_block(
+                    &commitment_tree,
+                    &witness_map,
+                    block_height,
+                    &shielded_txs,
+                )
+                .with_context(|| {
+                    format!(
+                        "Failed to replay the shielded txs of block \
+                         {block_height}"
+                    )
+                })?;
+
+                blocks.lock().unwrap().push(ReplayedBlock {
+                    block_height,
+                    first_note_position,
+                    num_notes: commitment_tree.size() - first_note_position,
+                    root: commitment_tree.root(),
+                });
+
+                Ok(())
+            }
+        })
+        .await?;
+
+    let blocks = std::mem::take(&mut *blocks.lock().unwrap());
+
+    tracing::info!(
+        num_blocks = blocks.len(),
+        commitment_tree_len = commitment_tree.size(),
+        "Replayed the stored shielded txs"
+    );
+
+    Ok(blocks)
+}
+
+async fn audit_commitment_trees<S: Storage>(
+    storage: &S,
+    blocks: &[ReplayedBlock],
+    divergences: &mut Vec<Divergence>,
+) -> anyhow::Result<()> {
+    for block in blocks {
+        let stored_tree = storage
+            .get_commitment_tree_at(block.block_height)
+            .await?
+            .unwrap_or_default();
+        let commitment_tree_len = block.first_note_position + block.num_notes;
+
+        if stored_tree.size() != commitment_tree_len
+            || stored_tree.root() != block.root
+        {
+            divergences.push(Divergence {
+                block_height: Some(block.block_height),
+                reason: format!(
+                    "Commitment tree of size {} does not match the one of \
+                     size {commitment_tree_len} recomputed from the stored txs",
+                    stored_tree.size()
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+async fn audit_witness_map<S: Storage>(
+    storage: &S,
+    divergences: &mut Vec<Divergence>,
+) -> anyhow::Result<()> {
+    let (block_height, witness_map) = storage.get_last_witness_map().await?;
+
+    let commitment_tree = match block_height {
+        Some(block_height) => {
+            storage.get_commitment_tree_at(block_height).await?
+        }
+        None => storage.get_last_commitment_tree().await?,
+    }
+    .unwrap_or_default();
+    let commitment_tree_len = commitment_tree.size();
+    let root = commitment_tree.root();
+
+    if (commitment_tree_len == 0) != (witness_map.size() == 0) {
+        divergences.push(Divergence {
+            block_height,
+            reason: format!(
+                "Commitment tree size is {commitment_tree_len}, and witness \
+                 map size is {}",
+                witness_map.size()
+            ),
+        });
+    }
+
+    let mut witnesses =
+        witness_map.get_witnesses().into_iter().collect::<Vec<_>>();
+    witnesses.sort_unstable_by_key(|(note_pos, _)| *note_pos);
+
+    for (note_pos, witness) in witnesses {
+        if note_pos >= commitment_tree_len || witness.root() != root {
+            divergences.push(Divergence {
+                block_height,
+                reason: format!(
+                    "Witness of note {note_pos} does not match the commitment \
+                     tree"
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+async fn audit_notes_index<S: Storage>(
+    storage: &S,
+    replayed: Option<&[ReplayedBlock]>,
+    pruned: bool,
+    divergences: &mut Vec<Divergence>,
+) -> anyhow::Result<()> {
+    let notes_index = storage.get_notes_index().await?;
+
+    // NB: the notes of pruned blocks are gone, so
+    // positions only need to be gapless past them
+    let mut expected_position = if pruned {
+        notes_index.first().map_or(0, |note| note.note_position)
+    } else {
+        0
+    };
+
+    for note in &notes_index {
+        if note.note_position != expected_position {
+            divergences.push(Divergence {
+                block_height: Some(BlockHeight::from(note.block_height)),
+                reason: format!(
+                    "Notes map is missing positions {expected_position} to {}",
+                    note.note_position - 1
+                ),
+            });
+        }
+        expected_position = note.note_position + 1;
+    }
+
+    let Some(replayed) = replayed else {
+        return Ok(());
+    };
+
+    let commitment_tree_len = replayed
+        .last()
+        .map_or(0, |block| block.first_note_position + block.num_notes);
+
+    if expected_position as usize != commitment_tree_len {
+        divergences.push(Divergence {
+            block_height: replayed.last().map(|block| block.block_height),
+            reason: format!(
+                "Notes map ends at position {expected_position}, but the \
+                 replayed commitment tree holds {commitment_tree_len} notes"
+            ),
+        });
+    }
+
+    for note in &notes_index {
+        let position = note.note_position as usize;
+        let block = replayed.partition_point(|block| {
+            block.first_note_position + block.num_notes <= position
+        });
+
+        match replayed.get(block) {
+            Some(block) if block.block_height.0 as i32 != note.block_height => {
+                divergences.push(Divergence {
+                    block_height: Some(BlockHeight::from(note.block_height)),
+                    reason: format!(
+                        "Note {position} was appended to the commitment tree \
+                         at height {}",
+                        block.block_height
+                    ),
+                });
+            }
+            Some(_) => {}
+            None => {
+                divergences.push(Divergence {
+                    block_height: Some(BlockHeight::from(note.block_height)),
+                    reason: format!(
+                        "Note {position} is not part of the replayed \
+                         commitment tree"
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
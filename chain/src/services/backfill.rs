@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+use futures::StreamExt;
+use shared::block::Block;
+use shared::height::BlockHeight;
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+
+use super::cometbft as cometbft_service;
+use super::failover::FailoverClient;
+
+/// Fetches the blocks of a range of heights with several workers, each
+/// of them extracting the masp txs of a chunk of consecutive blocks,
+/// while the blocks are still handed out in height order.
+///
+/// At most `workers` chunks are in flight, plus the chunk being taken
+/// and the next one, which bounds the memory spent on staged blocks.
+pub struct Backfiller {
+    client: FailoverClient,
+    staged: Mutex<Staged>,
+    dispatcher: JoinHandle<()>,
+}
+
+struct Staged {
+    receiver: mpsc::Receiver<Vec<Block>>,
+    /// Blocks of the chunk being taken, in height order.
+    blocks: VecDeque<Block>,
+}
+
+impl Backfiller {
+    pub fn new(
+        client: FailoverClient,
+        heights: RangeInclusive<u64>,
+        workers: u64,
+        chunk_size: u64,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(1);
+
+        let chunks = heights.clone().step_by(chunk_size as usize).map(
+            move |first_height| {
+                first_height
+                    ..=(first_height + chunk_size - 1).min(*heights.end())
+            },
+        );
+
+        let dispatcher = tokio::spawn({
+            let client = client.clone();
+
+            async move {
+                // NB: chunks are fetched concurrently, but their blocks
+                // are staged in height order
+                let mut chunks = futures::stream::iter(chunks)
+                    .map(|chunk| {
+                        tokio::spawn(fetch_chunk(client.clone(), chunk))
+                    })
+                    .buffered(workers as usize);
+
+                while let Some(blocks) = chunks.next().await {
+                    let blocks = blocks.unwrap_or_else(|reason| {
+                        tracing::warn!(
+                            %reason,
+                            "Failed to join backfill worker"
+                        );
+                        vec![]
+                    });
+
+                    if sender.send(blocks).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            client,
+            staged: Mutex::new(Staged {
+                receiver,
+                blocks: VecDeque::new(),
+            }),
+            dispatcher,
+        }
+    }
+
+    /// Return the data of the block at `block_height`.
+    ///
+    /// Blocks that were not staged, e.g. because a worker failed to fetch
+    /// them, or because the crawler moved back after a chain reorg, are
+    /// fetched on the spot.
+    pub async fn fetch(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Block> {
+        if let Some(block) = self.take_staged(block_height).await {
+            return Ok(block);
+        }
+
+        self.client
+            .call(|client| {
+                cometbft_service::query_masp_txs_in_block(client, block_height)
+            })
+            .await
+    }
+
+    async fn take_staged(&self, block_height: BlockHeight) -> Option<Block> {
+        let mut staged = self.staged.lock().await;
+
+        loop {
+            // NB: blocks below the requested height are no longer needed
+            while staged
+                .blocks
+                .front()
+                .is_some_and(|block| block.header.height < block_height)
+            {
+                staged.blocks.pop_front();
+            }
+
+            // NB: the block is kept until a later one is requested, in
+            // case processing it has to be retried
+            if let Some(block) = staged.blocks.front() {
+                return (block.header.height == block_height)
+                    .then(|| block.clone());
+            }
+
+            staged.blocks = staged.receiver.recv().await?.into();
+        }
+    }
+}
+
+impl Drop for Backfiller {
+    fn drop(&mut self) {
+        self.dispatcher.abort();
+    }
+}
+
+/// Fetch the blocks at `heights`, stopping at the first block that
+/// cannot be fetched.
+async fn fetch_chunk(
+    client: FailoverClient,
+    heights: RangeInclusive<u64>,
+) -> Vec<Block> {
+    let mut blocks = Vec::with_capacity(heights.clone().count());
+
+    for block_height in heights.map(BlockHeight) {
+        let result = client
+            .call(|client| {
+                cometbft_service::query_masp_txs_in_block(client, block_height)
+            })
+            .await;
+
+        match result {
+            Ok(block) => blocks.push(block),
+            Err(reason) => {
+                tracing::warn!(
+                    %block_height,
+                    ?reason,
+                    "Backfill worker failed to fetch block, leaving the \
+                     rest of its chunk to the crawler"
+                );
+                break;
+            }
+        }
+    }
+
+    blocks
+}
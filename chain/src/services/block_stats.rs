@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+use shared::height::BlockHeight;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+/// Maximum number of records waiting to be written, before new records
+/// start being dropped.
+const BUFFER_CAPACITY: usize = 1024;
+
+/// Masp activity of a single block.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlockCounts {
+    pub num_transactions: usize,
+    /// Position of the first note of the block in the commitment tree.
+    pub first_note_pos: usize,
+    pub num_notes: usize,
+    pub num_fee_unshieldings: usize,
+}
+
+#[derive(Serialize)]
+struct BlockStatsRecord {
+    block_height: u64,
+    num_transactions: usize,
+    num_notes: usize,
+    num_fee_unshieldings: usize,
+    commit_duration_ms: u64,
+}
+
+/// Appends a NDJSON record with the masp activity of each committed
+/// block to a file. Records are written by a background task, such that
+/// a slow disk never stalls the crawler.
+pub struct BlockStatsSink {
+    sender: Option<mpsc::Sender<BlockStatsRecord>>,
+    staged: Vec<(BlockHeight, BlockCounts)>,
+}
+
+impl BlockStatsSink {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let sender = path.map(|path| {
+            let (sender, receiver) = mpsc::channel(BUFFER_CAPACITY);
+            tokio::spawn(write_records(path, receiver));
+            sender
+        });
+
+        Self {
+            sender,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Keep the counts of a block until it is committed.
+    pub fn stage(&mut self, block_height: BlockHeight, counts: BlockCounts) {
+        if self.sender.is_some() {
+            self.staged.push((block_height, counts));
+        }
+    }
+
+    /// Forget the staged blocks, which will not be committed.
+    pub fn discard(&mut self) {
+        self.staged.clear();
+    }
+
+    /// Emit a record for each staged block, after they were committed
+    /// in `commit_duration`.
+    pub fn flush(&mut self, commit_duration: Duration) {
+        let Some(sender) = self.sender.as_ref() else {
+            return;
+        };
+
+        for (block_height, counts) in self.staged.drain(..) {
+            let record = BlockStatsRecord {
+                block_height: block_height.0,
+                num_transactions: counts.num_transactions,
+                num_notes: counts.num_notes,
+                num_fee_unshieldings: counts.num_fee_unshieldings,
+                commit_duration_ms: commit_duration.as_millis() as u64,
+            };
+
+            if let Err(err) = sender.try_send(record) {
+                tracing::warn!(
+                    %block_height,
+                    reason = %err,
+                    "Dropped block stats record"
+                );
+            }
+        }
+    }
+}
+
+async fn write_records(
+    path: PathBuf,
+    mut receiver: mpsc::Receiver<BlockStatsRecord>,
+) {
+    let mut file = match tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+    {
+        Ok(file) => file,
+        Err(reason) => {
+            tracing::warn!(?path, ?reason, "Failed to open block stats file");
+            return;
+        }
+    };
+
+    while let Some(record) = receiver.recv().await {
+        let mut line = serde_json::to_vec(&record)
+            .expect("Serializing block stats should not fail");
+        line.push(b'\n');
+
+        if let Err(reason) = file.write_all(&line).await {
+            tracing::warn!(?path, ?reason, "Failed to write block stats");
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::{Context, anyhow};
 use namada_core::masp_primitives::sapling::Node;
 use shared::block::Block;
+use shared::block_results::locate_tx_outcomes;
 use shared::height::BlockHeight;
+use shared::id::Id;
 use tendermint_rpc::endpoint::{block, block_results};
 use tendermint_rpc::{Client, HttpClient};
 
@@ -22,12 +24,36 @@ pub async fn query_masp_txs_in_block(
     client: &HttpClient,
     height: BlockHeight,
 ) -> anyhow::Result<Block> {
-    let (raw_block, raw_block_results) = futures::try_join!(
+    let (raw_block, raw_block_results, epoch) = futures::try_join!(
         query_raw_block(client, height),
         query_raw_block_results_at_height(client, height),
+        query_epoch_at_height(client, height),
     )?;
 
-    Block::new(raw_block, raw_block_results).map_err(|err| anyhow!(err))
+    // NB: only the masp txs of txs that were actually applied are
+    // indexed, otherwise the commitment tree would hold notes that
+    // never made it on-chain
+    let tx_outcomes =
+        locate_tx_outcomes(&raw_block_results).map_err(|err| anyhow!(err))?;
+
+    Block::new(raw_block, raw_block_results, &tx_outcomes, epoch)
+        .map_err(|err| anyhow!(err))
+}
+
+pub async fn query_chain_id(client: &HttpClient) -> anyhow::Result<String> {
+    let status = client
+        .status()
+        .await
+        .context("Failed to query CometBFT's node status")?;
+    Ok(status.node_info.network.to_string())
+}
+
+pub async fn query_block_hash(
+    client: &HttpClient,
+    height: BlockHeight,
+) -> anyhow::Result<Id> {
+    let raw_block = query_raw_block(client, height).await?;
+    Ok(Id::from(raw_block.block_id.hash))
 }
 
 async fn query_raw_block(
@@ -49,3 +75,13 @@ async fn query_raw_block_results_at_height(
         .await
         .context("Failed to query CometBFT's block results")
 }
+
+async fn query_epoch_at_height(
+    client: &HttpClient,
+    height: BlockHeight,
+) -> anyhow::Result<Option<u64>> {
+    let epoch = namada_sdk::rpc::query_epoch_at_height(client, height.into())
+        .await
+        .context("Failed to query the epoch of the block")?;
+    Ok(epoch.map(|epoch| epoch.0))
+}
@@ -1,33 +1,50 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use anyhow::{Context, anyhow};
 use deadpool_diesel::postgres::Object;
 use diesel::connection::DefaultLoadingMode as DbDefaultLoadingMode;
-use diesel::dsl::max;
+use diesel::dsl::{max, now};
+use diesel::sql_types::Text;
+use diesel::upsert::excluded;
 use diesel::{
-    ExpressionMethods, NullableExpressionMethods, OptionalExtension, QueryDsl,
-    RunQueryDsl, SelectableHelper,
+    ExpressionMethods, NullableExpressionMethods, OptionalExtension,
+    PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
 };
 use diesel_migrations::{
     EmbeddedMigrations, MigrationHarness, embed_migrations,
 };
-use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_sdk::borsh::BorshDeserialize;
 use namada_sdk::masp_primitives::merkle_tree::IncrementalWitness;
 use namada_sdk::masp_primitives::sapling::Node;
 use namada_sdk::masp_primitives::transaction::Transaction;
-use orm::schema::{self, chain_state, commitment_tree, witness};
+use orm::chain_id::ChainIdDb;
+use orm::chain_state::ChainStateteInsertDb;
+use orm::failed_block::FailedBlockDb;
+use orm::notes_index::{NotesIndexDb, NotesIndexInsertDb};
+use orm::pruned_height::PrunedHeightDb;
+use orm::schema::{
+    self, anchors, block_hash, block_index, chain_id, chain_state,
+    commitment_tree, compact_outputs, failed_blocks, notes_index, nullifiers,
+    pruned_height, start_height, tx, witness,
+};
+use orm::start_height::StartHeightDb;
 use orm::tree::TreeDb;
-use orm::tx::TxInsertDb;
+use orm::tx::{COMMITTED_TXS_CHANNEL, TxDb};
 use orm::witness::WitnessDb;
 use shared::error::ContextDbInteractError;
 use shared::height::BlockHeight;
-use shared::indexed_tx::IndexedTx;
 
-use crate::entity::chain_state::ChainState;
+use crate::entity::commit_batch::{
+    CommitBatch, compact_outputs_into_db, nullifiers_into_db,
+};
 use crate::entity::commitment_tree::CommitmentTree;
-use crate::entity::tx_notes_index::TxNoteMap;
 use crate::entity::witness_map::WitnessMap;
 
+/// Maximum number of rows inserted per statement, to stay below the bind
+/// parameter limit of Postgres.
+const MAX_INSERT_ROWS: usize = 4096;
+
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../orm/migrations/");
 
 pub async fn run_migrations(conn: Object) -> anyhow::Result<()> {
@@ -117,21 +134,55 @@ pub async fn get_last_commitment_tree(
     anyhow::Ok(maybe_tree)
 }
 
-pub async fn get_last_witness_map(conn: Object) -> anyhow::Result<WitnessMap> {
-    tracing::debug!("Reading last witness map from db");
+/// Read the commitment tree at the highest height at or below
+/// `block_height`.
+pub async fn get_commitment_tree_at(
+    conn: Object,
+    block_height: BlockHeight,
+) -> anyhow::Result<Option<CommitmentTree>> {
+    tracing::debug!(%block_height, "Reading commitment tree from db");
 
-    let witnesses = conn
+    let maybe_tree = conn
         .interact(move |conn| {
-            diesel::alias!(witness as witness_alias: WitnessMapAlias);
-
-            let max_block_height = witness_alias
-                .select(max(witness_alias.field(witness::dsl::block_height)))
-                .single_value();
-
-            witness::dsl::witness
+            commitment_tree::dsl::commitment_tree
                 .filter(
-                    witness::dsl::block_height.nullable().eq(max_block_height),
+                    commitment_tree::dsl::block_height
+                        .le(block_height.0 as i32),
                 )
+                .order(commitment_tree::dsl::block_height.desc())
+                .select(TreeDb::as_select())
+                .first(conn)
+                .optional()
+                .context("Failed to read commitment tree from db")
+        })
+        .await
+        .context_db_interact_error()??;
+
+    maybe_tree
+        .map(|tree| {
+            tree.try_into().context(
+                "Failed to deserialize commitment tree from db row data",
+            )
+        })
+        .transpose()
+}
+
+/// Read the last persisted witness map, along with the height it was
+/// persisted at.
+pub async fn get_last_witness_map(
+    conn: Object,
+) -> anyhow::Result<(Option<BlockHeight>, WitnessMap)> {
+    tracing::debug!("Reading last witness map from db");
+
+    let (block_height, witnesses) = conn
+        .interact(move |conn| {
+            let block_height = witness::dsl::witness
+                .select(max(witness::dsl::block_height))
+                .first::<Option<i32>>(conn)
+                .context("Failed to read last witness map height from db")?;
+
+            let witnesses = witness::dsl::witness
+                .filter(witness::dsl::block_height.nullable().eq(block_height))
                 .select(WitnessDb::as_select())
                 .load_iter::<_, DbDefaultLoadingMode>(conn)
                 .context("Failed to query note witnesses from db")?
@@ -165,134 +216,283 @@ pub async fn get_last_witness_map(conn: Object) -> anyhow::Result<WitnessMap> {
                     accum.insert(note_index, witness_node);
                     tracing::trace!("Inserted data into witness map");
                     anyhow::Ok(accum)
-                })
+                })?;
+
+            anyhow::Ok((block_height, witnesses))
         })
         .await
         .context_db_interact_error()??;
 
-    tracing::debug!("Read and deserialized witness map from db");
+    tracing::debug!(?block_height, "Read and deserialized witness map from db");
 
-    Ok(WitnessMap::new(witnesses))
+    Ok((
+        block_height.map(BlockHeight::from),
+        WitnessMap::new(witnesses),
+    ))
 }
 
-#[allow(clippy::too_many_arguments)]
-pub async fn commit(
-    conn: &Object,
-    chain_state: ChainState,
-    commitment_tree: CommitmentTree,
-    witness_map: WitnessMap,
-    notes_index: TxNoteMap,
-    shielded_txs: Vec<(IndexedTx, Transaction)>,
-) -> anyhow::Result<()> {
-    tracing::info!(
-        block_height = %chain_state.block_height,
-        "Beginning block commit"
-    );
+pub async fn replay_shielded_txs<F>(
+    conn: Object,
+    after: Option<BlockHeight>,
+    mut replay_block: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(BlockHeight, Vec<Transaction>) -> anyhow::Result<()>
+        + Send
+        + 'static,
+{
+    tracing::debug!("Replaying shielded txs from db");
 
     conn.interact(move |conn| {
+        let mut current_block: Option<(BlockHeight, Vec<Transaction>)> = None;
+
+        let after = after.map_or(-1, |h| h.0 as i32);
+
+        for maybe_tx in tx::dsl::tx
+            .filter(tx::dsl::block_height.gt(after))
+            .order((tx::dsl::block_height.asc(), tx::dsl::masp_tx_index.asc()))
+            .select(TxDb::as_select())
+            .load_iter::<_, DbDefaultLoadingMode>(conn)
+            .context("Failed to query shielded txs from db")?
+        {
+            let tx = maybe_tx.context("Failed to get shielded tx row data from db")?;
+            let block_height = BlockHeight::from(tx.block_height);
+            let masp_tx = Transaction::try_from_slice(&tx.tx_bytes)
+                .context("Failed to deserialize shielded tx from db")?;
+
+            match &mut current_block {
+                Some((height, txs)) if *height == block_height => {
+                    txs.push(masp_tx);
+                }
+                _ => {
+                    if let Some((height, txs)) =
+                        current_block.replace((block_height, vec![masp_tx]))
+                    {
+                        tracing::debug!(block_height = %height, "Replaying block");
+                        replay_block(height, txs)?;
+                    }
+                }
+            }
+        }
+
+        if let Some((height, txs)) = current_block {
+            tracing::debug!(block_height = %height, "Replaying block");
+            replay_block(height, txs)?;
+        }
+
+        anyhow::Ok(())
+    })
+    .await
+    .context_db_interact_error()??;
+
+    tracing::debug!("Replayed shielded txs from db");
+
+    Ok(())
+}
+
+/// Extract the nullifiers of shielded txs committed before nullifiers
+/// were indexed. Does nothing once any nullifier has been stored.
+pub async fn backfill_nullifiers(conn: Object) -> anyhow::Result<()> {
+    conn.interact(|conn| {
         conn.build_transaction()
             .read_write()
             .run(|transaction_conn| {
-                if let Some(commitment_tree_db) =
-                    commitment_tree.into_db(chain_state.block_height)
+                let has_nullifiers = nullifiers::table
+                    .select(nullifiers::dsl::id)
+                    .first::<i32>(transaction_conn)
+                    .optional()
+                    .context("Failed to read nullifiers from db")?
+                    .is_some();
+
+                if has_nullifiers {
+                    return anyhow::Ok(());
+                }
+
+                // NB: only the (small) nullifier rows are kept in memory,
+                // while the txs are streamed from the db
+                let mut rows = vec![];
+                for maybe_tx in tx::dsl::tx
+                    .order(tx::dsl::id.asc())
+                    .select(TxDb::as_select())
+                    .load_iter::<_, DbDefaultLoadingMode>(transaction_conn)
+                    .context("Failed to query shielded txs from db")?
                 {
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committing commitment tree"
-                    );
+                    let tx = maybe_tx.context(
+                        "Failed to get shielded tx row data from db",
+                    )?;
+                    let masp_tx = Transaction::try_from_slice(&tx.tx_bytes)
+                        .context("Failed to deserialize shielded tx from db")?;
 
-                    diesel::insert_into(schema::commitment_tree::table)
-                        .values(&commitment_tree_db)
-                        .on_conflict_do_nothing()
-                        .execute(transaction_conn)
-                        .context("Failed to insert commitment tree into db")?;
+                    rows.extend(nullifiers_into_db(
+                        tx.block_height,
+                        tx.block_index,
+                        tx.masp_tx_index,
+                        &masp_tx,
+                    ));
+                }
 
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committed commitment tree"
-                    );
+                if rows.is_empty() {
+                    return anyhow::Ok(());
                 }
 
-                if let Some(witness_map_db) =
-                    witness_map.into_db(chain_state.block_height)
-                {
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committing witness map"
-                    );
+                tracing::info!(
+                    num_nullifiers = rows.len(),
+                    "Backfilling nullifiers of committed shielded txs"
+                );
 
-                    diesel::insert_into(schema::witness::table)
-                        .values(&witness_map_db)
+                for nullifiers in rows.chunks(MAX_INSERT_ROWS) {
+                    diesel::insert_into(schema::nullifiers::table)
+                        .values(nullifiers)
                         .on_conflict_do_nothing()
                         .execute(transaction_conn)
-                        .context("Failed to insert witness map into db")?;
+                        .context("Failed to insert nullifiers into db")?;
+                }
 
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committed witness map"
-                    );
+                anyhow::Ok(())
+            })
+    })
+    .await
+    .context_db_interact_error()?
+}
+
+/// Extract the compact outputs of shielded txs committed before compact
+/// outputs were indexed. Does nothing once any compact output has been
+/// stored.
+pub async fn backfill_compact_outputs(conn: Object) -> anyhow::Result<()> {
+    conn.interact(|conn| {
+        conn.build_transaction()
+            .read_write()
+            .run(|transaction_conn| {
+                let has_compact_outputs = compact_outputs::table
+                    .select(compact_outputs::dsl::id)
+                    .first::<i32>(transaction_conn)
+                    .optional()
+                    .context("Failed to read compact outputs from db")?
+                    .is_some();
+
+                if has_compact_outputs {
+                    return anyhow::Ok(());
                 }
 
-                if !notes_index.is_empty() {
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committing notes map"
-                    );
+                // NB: only the compact output rows are kept in memory,
+                // while the txs are streamed from the db
+                let mut rows = vec![];
+                for maybe_tx in tx::dsl::tx
+                    .order(tx::dsl::id.asc())
+                    .select(TxDb::as_select())
+                    .load_iter::<_, DbDefaultLoadingMode>(transaction_conn)
+                    .context("Failed to query shielded txs from db")?
+                {
+                    let tx = maybe_tx.context(
+                        "Failed to get shielded tx row data from db",
+                    )?;
+                    let masp_tx = Transaction::try_from_slice(&tx.tx_bytes)
+                        .context("Failed to deserialize shielded tx from db")?;
 
-                    let notes_index_db = notes_index.into_db();
-                    diesel::insert_into(schema::notes_index::table)
-                        .values(&notes_index_db)
-                        .on_conflict_do_nothing()
-                        .execute(transaction_conn)
-                        .context("Failed to insert notes map into db")?;
+                    rows.extend(compact_outputs_into_db(
+                        tx.block_height,
+                        tx.block_index,
+                        tx.masp_tx_index,
+                        &masp_tx,
+                    ));
+                }
 
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committed notes map"
-                    );
+                if rows.is_empty() {
+                    return anyhow::Ok(());
                 }
 
-                if !shielded_txs.is_empty() {
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committing shielded txs"
-                    );
+                tracing::info!(
+                    num_compact_outputs = rows.len(),
+                    "Backfilling compact outputs of committed shielded txs"
+                );
 
-                    let shielded_txs_db = shielded_txs
-                        .iter()
-                        .map(|(index, tx)| TxInsertDb {
-                            block_index: index.block_index.0 as i32,
-                            tx_bytes: tx.serialize_to_vec(),
-                            block_height: index.block_height.0 as i32,
-                            masp_tx_index: index.masp_tx_index.0 as i32,
-                        })
-                        .collect::<Vec<TxInsertDb>>();
-                    diesel::insert_into(schema::tx::table)
-                        .values(&shielded_txs_db)
+                for compact_outputs in rows.chunks(MAX_INSERT_ROWS) {
+                    diesel::insert_into(schema::compact_outputs::table)
+                        .values(compact_outputs)
                         .on_conflict_do_nothing()
                         .execute(transaction_conn)
-                        .context("Failed to insert shielded txs into db")?;
+                        .context("Failed to insert compact outputs into db")?;
+                }
 
-                    tracing::debug!(
-                        block_height = %chain_state.block_height,
-                        "Pre-committed shielded txs"
-                    );
+                anyhow::Ok(())
+            })
+    })
+    .await
+    .context_db_interact_error()?
+}
+
+pub async fn commit(
+    conn: &Object,
+    batch: Arc<CommitBatch>,
+) -> anyhow::Result<()> {
+    let Some(chain_state_db) = batch.chain_state_into_db() else {
+        return Ok(());
+    };
+    let block_height = chain_state_db.block_height;
+    let num_blocks = batch.len();
+
+    tracing::info!(block_height, num_blocks, "Beginning block commit");
+
+    conn.interact(move |conn| {
+        conn.build_transaction()
+            .read_write()
+            .run(|transaction_conn| {
+                let last_synced_height = chain_state::dsl::chain_state
+                    .select(max(chain_state::dsl::block_height))
+                    .first::<Option<i32>>(transaction_conn)
+                    .context("Failed to read last synced height from db")?;
+
+                // NB: a previous attempt may have been committed, even
+                // though its result never reached us, or the process may
+                // have been restarted in between
+                if last_synced_height.is_some_and(|h| h >= block_height) {
+                    let first_height = batch
+                        .first_block_height()
+                        .map_or(block_height, |h| h.0 as i32);
+
+                    let committed_hashes: HashMap<i32, String> =
+                        block_hash::dsl::block_hash
+                            .filter(
+                                block_hash::dsl::block_height
+                                    .between(first_height, block_height),
+                            )
+                            .select((
+                                block_hash::dsl::block_height,
+                                block_hash::dsl::hash,
+                            ))
+                            .load::<(i32, String)>(transaction_conn)
+                            .context("Failed to read block hashes from db")?
+                            .into_iter()
+                            .collect();
+
+                    if batch.is_committed(|height| {
+                        committed_hashes.get(&height).map(String::as_str)
+                    }) {
+                        tracing::info!(block_height, "Batch already committed");
+                        return anyhow::Ok(());
+                    }
                 }
 
-                let chain_state_db = chain_state.into_db();
+                batch.check_follows(last_synced_height)?;
+
+                insert_batch(transaction_conn, &batch, block_height)?;
+
                 diesel::insert_into(schema::chain_state::table)
                     .values(&chain_state_db)
                     .on_conflict(schema::chain_state::dsl::id)
                     .do_update()
-                    .set(
+                    .set((
                         schema::chain_state::block_height
                             .eq(chain_state_db.block_height),
-                    )
+                        schema::chain_state::chain_tip
+                            .eq(chain_state_db.chain_tip),
+                        schema::chain_state::committed_at.eq(now),
+                    ))
                     .execute(transaction_conn)
                     .context("Failed to insert last chain state into db")?;
 
                 tracing::debug!(
-                    block_height = %chain_state.block_height,
+                    block_height,
                     "All data was successfully pre-committed, committing..."
                 );
 
@@ -301,17 +501,775 @@ pub async fn commit(
     })
     .await
     .context_db_interact_error()?
+    .with_context(|| {
+        format!("Failed to commit block at height={block_height}")
+    })?;
+
+    tracing::info!(block_height, num_blocks, "Committed new blocks");
+
+    Ok(())
+}
+
+/// Insert all the rows of `batch`, except for its chain state.
+fn insert_batch(
+    transaction_conn: &mut PgConnection,
+    batch: &CommitBatch,
+    block_height: i32,
+) -> anyhow::Result<()> {
+    if !batch.commitment_trees.is_empty() {
+        tracing::debug!(block_height, "Pre-committing commitment trees");
+
+        diesel::insert_into(schema::commitment_tree::table)
+            .values(&batch.commitment_trees)
+            .on_conflict_do_nothing()
+            .execute(transaction_conn)
+            .context("Failed to insert commitment tree into db")?;
+
+        tracing::debug!(block_height, "Pre-committed commitment trees");
+    }
+
+    if !batch.witnesses.is_empty() {
+        tracing::debug!(block_height, "Pre-committing witness maps");
+
+        for witnesses in batch.witnesses.chunks(MAX_INSERT_ROWS) {
+            diesel::insert_into(schema::witness::table)
+                .values(witnesses)
+                .on_conflict_do_nothing()
+                .execute(transaction_conn)
+                .context("Failed to insert witness map into db")?;
+        }
+
+        tracing::debug!(block_height, "Pre-committed witness maps");
+    }
+
+    if !batch.notes_index.is_empty() {
+        tracing::debug!(block_height, "Pre-committing notes map");
+
+        for notes in batch.notes_index.chunks(MAX_INSERT_ROWS) {
+            diesel::insert_into(schema::notes_index::table)
+                .values(notes)
+                .on_conflict_do_nothing()
+                .execute(transaction_conn)
+                .context("Failed to insert notes map into db")?;
+        }
+
+        tracing::debug!(block_height, "Pre-committed notes map");
+    }
+
+    if !batch.shielded_txs.is_empty() {
+        tracing::debug!(block_height, "Pre-committing shielded txs");
+
+        for txs in batch.shielded_txs.chunks(MAX_INSERT_ROWS) {
+            diesel::insert_into(schema::tx::table)
+                .values(txs)
+                .on_conflict_do_nothing()
+                .execute(transaction_conn)
+                .context("Failed to insert shielded txs into db")?;
+        }
+
+        tracing::debug!(block_height, "Pre-committed shielded txs");
+    }
+
+    // NB: the notes of masp txs are committed without the txs in notes
+    // map only mode, and streamed all the same
+    let masp_tx_heights = batch
+        .notes_index
+        .iter()
+        .map(|note| note.block_height)
+        .chain(batch.shielded_txs.iter().map(|tx| tx.block_height));
+
+    if let Some((from, to)) =
+        masp_tx_heights.clone().min().zip(masp_tx_heights.max())
+    {
+        // NB: notifications are only delivered to listeners
+        // once the transaction commits
+        diesel::sql_query("SELECT pg_notify($1, $2)")
+            .bind::<Text, _>(COMMITTED_TXS_CHANNEL)
+            .bind::<Text, _>(format!("{from}:{to}"))
+            .execute(transaction_conn)
+            .context("Failed to notify committed masp txs")?;
+    }
+
+    if !batch.nullifiers.is_empty() {
+        tracing::debug!(block_height, "Pre-committing nullifiers");
+
+        for nullifiers in batch.nullifiers.chunks(MAX_INSERT_ROWS) {
+            diesel::insert_into(schema::nullifiers::table)
+                .values(nullifiers)
+                .on_conflict_do_nothing()
+                .execute(transaction_conn)
+                .context("Failed to insert nullifiers into db")?;
+        }
+
+        tracing::debug!(block_height, "Pre-committed nullifiers");
+    }
+
+    if !batch.compact_outputs.is_empty() {
+        tracing::debug!(block_height, "Pre-committing compact outputs");
+
+        for compact_outputs in batch.compact_outputs.chunks(MAX_INSERT_ROWS) {
+            diesel::insert_into(schema::compact_outputs::table)
+                .values(compact_outputs)
+                .on_conflict_do_nothing()
+                .execute(transaction_conn)
+                .context("Failed to insert compact outputs into db")?;
+        }
+
+        tracing::debug!(block_height, "Pre-committed compact outputs");
+    }
+
+    for block_hashes in batch.block_hashes.chunks(MAX_INSERT_ROWS) {
+        diesel::insert_into(schema::block_hash::table)
+            .values(block_hashes)
+            .on_conflict(schema::block_hash::dsl::block_height)
+            .do_update()
+            .set((
+                schema::block_hash::hash.eq(excluded(schema::block_hash::hash)),
+                schema::block_hash::timestamp
+                    .eq(excluded(schema::block_hash::timestamp)),
+                schema::block_hash::epoch
+                    .eq(excluded(schema::block_hash::epoch)),
+            ))
+            .execute(transaction_conn)
+            .context("Failed to insert block hashes into db")?;
+    }
+
+    // NB: anchors of replaced blocks are overwritten
+    for anchors in batch.anchors.chunks(MAX_INSERT_ROWS) {
+        diesel::insert_into(schema::anchors::table)
+            .values(anchors)
+            .on_conflict(schema::anchors::dsl::block_height)
+            .do_update()
+            .set((
+                schema::anchors::root.eq(excluded(schema::anchors::root)),
+                schema::anchors::tree_size
+                    .eq(excluded(schema::anchors::tree_size)),
+            ))
+            .execute(transaction_conn)
+            .context("Failed to insert anchors into db")?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_notes_index(
+    conn: Object,
+) -> anyhow::Result<Vec<NotesIndexInsertDb>> {
+    tracing::debug!("Reading notes map from db");
+
+    let notes_index = conn
+        .interact(move |conn| {
+            notes_index::dsl::notes_index
+                .order(notes_index::dsl::note_position.asc())
+                .select(NotesIndexDb::as_select())
+                .load(conn)
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to read notes map from db")?;
+
+    tracing::debug!(num_notes = notes_index.len(), "Read notes map from db");
+
+    Ok(notes_index
+        .into_iter()
+        .map(|note| NotesIndexInsertDb {
+            block_index: note.block_index,
+            note_position: note.note_position,
+            block_height: note.block_height,
+            masp_tx_index: note.masp_tx_index,
+            is_fee_unshielding: note.is_fee_unshielding,
+        })
+        .collect())
+}
+
+pub async fn get_block_hash(
+    conn: Object,
+    block_height: BlockHeight,
+) -> anyhow::Result<Option<String>> {
+    tracing::debug!(%block_height, "Reading block hash from db");
+
+    let hash = conn
+        .interact(move |conn| {
+            block_hash::dsl::block_hash
+                .filter(block_hash::dsl::block_height.eq(block_height.0 as i32))
+                .select(block_hash::dsl::hash)
+                .first::<String>(conn)
+                .optional()
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to read block hash from db")?;
+
+    tracing::debug!(%block_height, ?hash, "Read block hash from db");
+
+    Ok(hash)
+}
+
+/// Read the heights of all committed blocks in ascending order, along
+/// with the last synced height, as of the same snapshot of the db.
+pub async fn get_committed_block_heights(
+    conn: Object,
+) -> anyhow::Result<(Vec<i32>, Option<i32>)> {
+    tracing::debug!("Reading committed block heights from db");
+
+    let (block_heights, last_synced_height) = conn
+        .interact(move |conn| {
+            conn.build_transaction().read_only().repeatable_read().run(
+                |transaction_conn| {
+                    let last_synced_height = chain_state::dsl::chain_state
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(transaction_conn)?;
+
+                    let block_heights = block_hash::dsl::block_hash
+                        .select(block_hash::dsl::block_height)
+                        .order(block_hash::dsl::block_height.asc())
+                        .load::<i32>(transaction_conn)?;
+
+                    diesel::QueryResult::Ok((block_heights, last_synced_height))
+                },
+            )
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to read committed block heights from db")?;
+
+    tracing::debug!(
+        num_blocks = block_heights.len(),
+        ?last_synced_height,
+        "Read committed block heights from db"
+    );
+
+    Ok((block_heights, last_synced_height))
+}
+
+pub async fn prune_witness_checkpoints(
+    conn: Object,
+    keep: u64,
+) -> anyhow::Result<()> {
+    let num_deleted = conn
+        .interact(move |conn| {
+            let oldest_kept = witness::table
+                .select(witness::dsl::block_height)
+                .distinct()
+                .order(witness::dsl::block_height.desc())
+                .offset(keep.saturating_sub(1) as i64)
+                .first::<i32>(conn)
+                .optional()
+                .context("Failed to read witness map checkpoints from db")?;
+
+            let Some(oldest_kept) = oldest_kept else {
+                return anyhow::Ok(0);
+            };
+
+            diesel::delete(
+                witness::table
+                    .filter(witness::dsl::block_height.lt(oldest_kept)),
+            )
+            .execute(conn)
+            .context("Failed to delete witness map checkpoints from db")
+        })
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!(num_deleted, "Pruned old witness map checkpoints");
+
+    Ok(())
+}
+
+pub async fn record_failed_block(
+    conn: Object,
+    failed_block: FailedBlockDb,
+) -> anyhow::Result<()> {
+    conn.interact(move |conn| {
+        diesel::insert_into(failed_blocks::table)
+            .values(&failed_block)
+            .on_conflict(failed_blocks::dsl::block_height)
+            .do_update()
+            .set((
+                failed_blocks::error.eq(&failed_block.error),
+                failed_blocks::skipped.eq(failed_block.skipped),
+                failed_blocks::failed_at.eq(now),
+            ))
+            .execute(conn)
+            .context("Failed to insert failed block into db")
+    })
+    .await
+    .context_db_interact_error()??;
+
+    Ok(())
+}
+
+pub async fn get_failed_blocks(
+    conn: Object,
+) -> anyhow::Result<Vec<FailedBlockDb>> {
+    conn.interact(|conn| {
+        failed_blocks::table
+            .order(failed_blocks::dsl::block_height.asc())
+            .select(FailedBlockDb::as_select())
+            .load(conn)
+            .context("Failed to read failed blocks from db")
+    })
+    .await
+    .context_db_interact_error()?
+}
+
+pub async fn get_start_height(
+    conn: Object,
+) -> anyhow::Result<Option<BlockHeight>> {
+    let block_height = conn
+        .interact(|conn| {
+            start_height::table
+                .select(start_height::dsl::block_height)
+                .first::<i32>(conn)
+                .optional()
+                .context("Failed to read start height from db")
+        })
+        .await
+        .context_db_interact_error()??;
+
+    Ok(block_height.map(BlockHeight::from))
+}
+
+pub async fn set_start_height(
+    conn: Object,
+    block_height: BlockHeight,
+) -> anyhow::Result<()> {
+    let start_height = StartHeightDb {
+        id: 0,
+        block_height: block_height.0 as i32,
+    };
+
+    conn.interact(move |conn| {
+        diesel::insert_into(start_height::table)
+            .values(&start_height)
+            .on_conflict(start_height::dsl::id)
+            .do_update()
+            .set(start_height::block_height.eq(start_height.block_height))
+            .execute(conn)
+            .context("Failed to insert start height into db")
+    })
+    .await
+    .context_db_interact_error()??;
+
+    Ok(())
+}
+
+pub async fn get_chain_id(conn: Object) -> anyhow::Result<Option<String>> {
+    conn.interact(|conn| {
+        chain_id::table
+            .select(chain_id::dsl::chain_id)
+            .first::<String>(conn)
+            .optional()
+            .context("Failed to read chain id from db")
+    })
+    .await
+    .context_db_interact_error()?
+}
+
+pub async fn set_chain_id(
+    conn: Object,
+    chain_id: String,
+) -> anyhow::Result<()> {
+    let row = ChainIdDb { id: 0, chain_id };
+
+    conn.interact(move |conn| {
+        diesel::insert_into(chain_id::table)
+            .values(&row)
+            .on_conflict(chain_id::dsl::id)
+            .do_update()
+            .set(chain_id::chain_id.eq(&row.chain_id))
+            .execute(conn)
+            .context("Failed to insert chain id into db")
+    })
+    .await
+    .context_db_interact_error()??;
+
+    Ok(())
+}
+
+pub async fn get_pruned_height(
+    conn: Object,
+) -> anyhow::Result<Option<BlockHeight>> {
+    let block_height = conn
+        .interact(|conn| {
+            pruned_height::table
+                .select(pruned_height::dsl::block_height)
+                .first::<i32>(conn)
+                .optional()
+                .context("Failed to read pruned height from db")
+        })
+        .await
+        .context_db_interact_error()??;
+
+    Ok(block_height.map(BlockHeight::from))
+}
+
+/// Delete the shielded txs, notes, nullifiers and compact outputs
+/// committed up to and including `block_height`, but never past the last
+/// witness map checkpoint, since the txs after it are replayed on
+/// restart. Returns the resulting pruned height.
+pub async fn prune(
+    conn: Object,
+    block_height: BlockHeight,
+) -> anyhow::Result<Option<BlockHeight>> {
+    let (pruned_height, num_txs, num_notes, num_nullifiers, num_outputs) = conn
+        .interact(move |conn| {
+            conn.build_transaction()
+                .read_write()
+                .run(|transaction_conn| {
+                    let last_checkpoint = witness::table
+                        .select(max(witness::dsl::block_height))
+                        .first::<Option<i32>>(transaction_conn)?;
+                    let pruned = pruned_height::table
+                        .select(pruned_height::dsl::block_height)
+                        .first::<i32>(transaction_conn)
+                        .optional()?;
+
+                    let Some(cutoff) = last_checkpoint
+                        .map(|h| h.min(block_height.0 as i32))
+                        .filter(|&h| pruned.is_none_or(|p| h > p))
+                    else {
+                        return diesel::QueryResult::Ok((pruned, 0, 0, 0, 0));
+                    };
+
+                    let num_txs = diesel::delete(
+                        tx::table.filter(tx::dsl::block_height.le(cutoff)),
+                    )
+                    .execute(transaction_conn)?;
+
+                    let num_notes = diesel::delete(
+                        notes_index::table
+                            .filter(notes_index::dsl::block_height.le(cutoff)),
+                    )
+                    .execute(transaction_conn)?;
+
+                    let num_nullifiers = diesel::delete(
+                        nullifiers::table
+                            .filter(nullifiers::dsl::block_height.le(cutoff)),
+                    )
+                    .execute(transaction_conn)?;
+
+                    let num_outputs =
+                        diesel::delete(compact_outputs::table.filter(
+                            compact_outputs::dsl::block_height.le(cutoff),
+                        ))
+                        .execute(transaction_conn)?;
+
+                    diesel::insert_into(pruned_height::table)
+                        .values(&PrunedHeightDb {
+                            id: 0,
+                            block_height: cutoff,
+                        })
+                        .on_conflict(pruned_height::dsl::id)
+                        .do_update()
+                        .set(pruned_height::block_height.eq(cutoff))
+                        .execute(transaction_conn)?;
+
+                    diesel::QueryResult::Ok((
+                        Some(cutoff),
+                        num_txs,
+                        num_notes,
+                        num_nullifiers,
+                        num_outputs,
+                    ))
+                })
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to prune shielded txs and notes from db")?;
+
+    tracing::debug!(
+        ?pruned_height,
+        num_txs,
+        num_notes,
+        num_nullifiers,
+        num_outputs,
+        "Pruned old shielded txs and notes"
+    );
+
+    Ok(pruned_height.map(BlockHeight::from))
+}
+
+/// Replace the data committed for the blocks in `batch` with the data in
+/// it, leaving the chain state untouched.
+pub async fn replace_blocks(
+    conn: &Object,
+    batch: Arc<CommitBatch>,
+) -> anyhow::Result<()> {
+    let (Some(first_height), Some(block_height)) =
+        (batch.first_block_height(), batch.block_height())
+    else {
+        return Ok(());
+    };
+    let first_height = first_height.0 as i32;
+    let block_height = block_height.0 as i32;
+
+    tracing::info!(first_height, block_height, "Replacing committed blocks");
+
+    conn.interact(move |conn| {
+        conn.build_transaction()
+            .read_write()
+            .run(|transaction_conn| {
+                let last_synced_height = chain_state::dsl::chain_state
+                    .select(max(chain_state::dsl::block_height))
+                    .first::<Option<i32>>(transaction_conn)
+                    .context("Failed to read last synced height from db")?;
+
+                if last_synced_height.is_none_or(|h| h < block_height) {
+                    return Err(anyhow!(
+                        "Cannot replace blocks up to {block_height}, past the \
+                         last synced height {last_synced_height:?}"
+                    ));
+                }
+
+                diesel::delete(
+                    commitment_tree::table.filter(
+                        commitment_tree::dsl::block_height
+                            .between(first_height, block_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete commitment trees from db")?;
+
+                diesel::delete(
+                    witness::table.filter(
+                        witness::dsl::block_height
+                            .between(first_height, block_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete witnesses from db")?;
+
+                diesel::delete(
+                    notes_index::table.filter(
+                        notes_index::dsl::block_height
+                            .between(first_height, block_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete notes index from db")?;
+
+                diesel::delete(tx::table.filter(
+                    tx::dsl::block_height.between(first_height, block_height),
+                ))
+                .execute(transaction_conn)
+                .context("Failed to delete shielded txs from db")?;
+
+                diesel::delete(
+                    nullifiers::table.filter(
+                        nullifiers::dsl::block_height
+                            .between(first_height, block_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete nullifiers from db")?;
+
+                diesel::delete(
+                    compact_outputs::table.filter(
+                        compact_outputs::dsl::block_height
+                            .between(first_height, block_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete compact outputs from db")?;
+
+                diesel::delete(
+                    failed_blocks::table.filter(
+                        failed_blocks::dsl::block_height
+                            .between(first_height, block_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete failed blocks from db")?;
+
+                // NB: the block index covers all the txs above its
+                // height, so it is rebuilt by the block index service
+                diesel::delete(
+                    block_index::table.filter(
+                        block_index::dsl::block_height.ge(first_height),
+                    ),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete block index from db")?;
+
+                insert_batch(transaction_conn, &batch, block_height)
+            })
+    })
+    .await
+    .context_db_interact_error()?
     .with_context(|| {
         format!(
-            "Failed to commit block at height={}",
-            chain_state.block_height
+            "Failed to replace blocks in the range \
+             {first_height}-{block_height}"
         )
     })?;
 
-    tracing::info!(
-        block_height = %chain_state.block_height,
-        "Committed new block"
-    );
+    tracing::info!(first_height, block_height, "Replaced committed blocks");
+
+    Ok(())
+}
+
+/// Delete all the data committed after `block_height`. If `block_height`
+/// is `None`, every committed block is deleted. Fails if the blocks left
+/// could not be rebuilt, because their shielded txs were pruned.
+pub async fn rollback(
+    conn: Object,
+    block_height: Option<BlockHeight>,
+) -> anyhow::Result<()> {
+    tracing::info!(?block_height, "Rolling back committed blocks");
+
+    conn.interact(move |conn| {
+        conn.build_transaction()
+            .read_write()
+            .run(|transaction_conn| {
+                let height = block_height.map_or(-1, |h| h.0 as i32);
+
+                let pruned = pruned_height::table
+                    .select(pruned_height::dsl::block_height)
+                    .first::<i32>(transaction_conn)
+                    .optional()
+                    .context("Failed to read pruned height from db")?;
+
+                match (block_height, pruned) {
+                    (None, _) => {
+                        diesel::delete(pruned_height::table)
+                            .execute(transaction_conn)
+                            .context(
+                                "Failed to delete pruned height from db",
+                            )?;
+                    }
+                    (Some(_), Some(pruned)) => {
+                        // NB: the blocks left must be rebuildable from a
+                        // witness map checkpoint taken after the pruned txs
+                        let checkpoint = witness::table
+                            .select(witness::dsl::block_height)
+                            .filter(
+                                witness::dsl::block_height
+                                    .between(pruned, height),
+                            )
+                            .first::<i32>(transaction_conn)
+                            .optional()
+                            .context(
+                                "Failed to read witness map checkpoints from \
+                                 db",
+                            )?;
+
+                        if checkpoint.is_none() {
+                            anyhow::bail!(
+                                "Cannot roll back to block height {height}, \
+                                 since the shielded txs up to {pruned} were \
+                                 pruned"
+                            );
+                        }
+                    }
+                    (Some(_), None) => {}
+                }
+
+                diesel::delete(
+                    commitment_tree::table
+                        .filter(commitment_tree::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete commitment trees from db")?;
+
+                diesel::delete(
+                    witness::table
+                        .filter(witness::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete witnesses from db")?;
+
+                diesel::delete(
+                    notes_index::table
+                        .filter(notes_index::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete notes index from db")?;
+
+                diesel::delete(
+                    tx::table.filter(tx::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete shielded txs from db")?;
+
+                diesel::delete(
+                    nullifiers::table
+                        .filter(nullifiers::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete nullifiers from db")?;
+
+                diesel::delete(
+                    compact_outputs::table
+                        .filter(compact_outputs::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete compact outputs from db")?;
+
+                diesel::delete(
+                    block_hash::table
+                        .filter(block_hash::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete block hashes from db")?;
+
+                diesel::delete(
+                    anchors::table
+                        .filter(anchors::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete anchors from db")?;
+
+                // NB: the block index is rebuilt from the remaining txs
+                // by the block index service
+                diesel::delete(
+                    block_index::table
+                        .filter(block_index::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete block index from db")?;
+
+                diesel::delete(
+                    failed_blocks::table
+                        .filter(failed_blocks::dsl::block_height.gt(height)),
+                )
+                .execute(transaction_conn)
+                .context("Failed to delete failed blocks from db")?;
+
+                match block_height {
+                    Some(block_height) => {
+                        let chain_state_db = ChainStateteInsertDb {
+                            id: 0,
+                            block_height: block_height.0 as i32,
+                            // NB: only used if no chain state is left,
+                            // otherwise the last known tip is kept
+                            chain_tip: block_height.0 as i32,
+                        };
+                        diesel::insert_into(chain_state::table)
+                            .values(&chain_state_db)
+                            .on_conflict(chain_state::dsl::id)
+                            .do_update()
+                            .set(
+                                chain_state::block_height
+                                    .eq(chain_state_db.block_height),
+                            )
+                            .execute(transaction_conn)
+                            .context("Failed to update chain state in db")?;
+                    }
+                    None => {
+                        diesel::delete(chain_state::table)
+                            .execute(transaction_conn)
+                            .context("Failed to delete chain state from db")?;
+                    }
+                }
+
+                anyhow::Ok(())
+            })
+    })
+    .await
+    .context_db_interact_error()??;
+
+    tracing::info!(?block_height, "Rolled back committed blocks");
 
     Ok(())
 }
@@ -0,0 +1,252 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::time::Duration;
+
+use anyhow::Context;
+use tendermint_rpc::client::CompatMode;
+use tendermint_rpc::error::ErrorDetail;
+use tendermint_rpc::{Client, HttpClient};
+
+use super::metrics;
+
+/// RPC dialect used when it cannot be detected.
+const DEFAULT_COMPAT_MODE: CompatMode = CompatMode::V0_37;
+
+struct Endpoint {
+    url: String,
+    client: HttpClient,
+    has_failed: AtomicBool,
+}
+
+impl Endpoint {
+    fn has_failed(&self) -> bool {
+        self.has_failed.load(atomic::Ordering::Relaxed)
+    }
+
+    fn set_failed(&self, has_failed: bool) {
+        self.has_failed.store(has_failed, atomic::Ordering::Relaxed);
+    }
+}
+
+struct InnerFailoverClient {
+    endpoints: Vec<Endpoint>,
+    active: AtomicUsize,
+    round_robin: bool,
+}
+
+/// CometBFT client that fails over to the next healthy endpoint, once
+/// the active endpoint returns a connection error.
+///
+/// The active endpoint is sticky, i.e. requests keep going to the same
+/// node until it fails, unless requests are spread in a round-robin
+/// fashion across all healthy endpoints.
+#[derive(Clone)]
+pub struct FailoverClient(Arc<InnerFailoverClient>);
+
+impl FailoverClient {
+    /// Build a client for each of `urls`. Unless `compat_mode` is
+    /// given, the RPC dialect of each endpoint is detected from the
+    /// version it reports.
+    pub async fn new(
+        urls: &[String],
+        compat_mode: Option<CompatMode>,
+        round_robin: bool,
+    ) -> anyhow::Result<Self> {
+        let mut endpoints = Vec::with_capacity(urls.len());
+
+        for url in urls {
+            let mut client = HttpClient::builder(
+                url.as_str()
+                    .parse()
+                    .with_context(|| format!("Invalid CometBFT url {url}"))?,
+            )
+            .compat_mode(DEFAULT_COMPAT_MODE)
+            .build()
+            .with_context(|| {
+                format!("Failed to build CometBFT client for {url}")
+            })?;
+
+            let compat_mode = match compat_mode {
+                Some(compat_mode) => compat_mode,
+                None => detect_compat_mode(url, &client).await,
+            };
+            client.set_compat_mode(compat_mode);
+
+            endpoints.push(Endpoint {
+                url: url.clone(),
+                client,
+                has_failed: AtomicBool::new(false),
+            });
+        }
+
+        if endpoints.is_empty() {
+            anyhow::bail!("No CometBFT urls were provided");
+        }
+
+        tracing::info!(url = endpoints[0].url, "Using CometBFT endpoint");
+
+        Ok(Self(Arc::new(InnerFailoverClient {
+            endpoints,
+            active: AtomicUsize::new(0),
+            round_robin,
+        })))
+    }
+
+    /// Run `request` against the active endpoint, switching to the next
+    /// healthy endpoint if it fails with a connection error.
+    pub async fn call<'a, F, Fut, T>(&'a self, request: F) -> anyhow::Result<T>
+    where
+        F: FnOnce(&'a HttpClient) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let index = if self.0.round_robin {
+            self.next_healthy()
+        } else {
+            self.0.active.load(atomic::Ordering::Acquire)
+        };
+        let result = request(&self.0.endpoints[index].client).await;
+
+        if let Err(err) = &result {
+            metrics::RPC_ERRORS.inc();
+
+            if is_connection_error(err) {
+                self.fail_over(index);
+            }
+        }
+
+        result
+    }
+
+    /// Advance the round-robin cursor to the next healthy endpoint, or
+    /// to the next endpoint if all of them have failed.
+    fn next_healthy(&self) -> usize {
+        let endpoints = &self.0.endpoints;
+        let start = self.0.active.fetch_add(1, atomic::Ordering::AcqRel);
+
+        (0..endpoints.len())
+            .map(|offset| start.wrapping_add(offset) % endpoints.len())
+            .find(|&index| !endpoints[index].has_failed())
+            .unwrap_or(start % endpoints.len())
+    }
+
+    fn fail_over(&self, failed: usize) {
+        let endpoints = &self.0.endpoints;
+        endpoints[failed].set_failed(true);
+
+        // NB: in round-robin mode, failed endpoints are
+        // skipped by the following requests
+        if endpoints.len() == 1 || self.0.round_robin {
+            return;
+        }
+
+        // NB: prefer healthy endpoints, but if all of them have
+        // failed, move on to the next one regardless
+        let next = (1..endpoints.len())
+            .map(|offset| (failed + offset) % endpoints.len())
+            .find(|&index| !endpoints[index].has_failed())
+            .unwrap_or((failed + 1) % endpoints.len());
+
+        // NB: if another request already failed over, keep
+        // the endpoint it switched to
+        if self
+            .0
+            .active
+            .compare_exchange(
+                failed,
+                next,
+                atomic::Ordering::AcqRel,
+                atomic::Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            tracing::warn!(
+                failed = endpoints[failed].url,
+                active = endpoints[next].url,
+                "Switched to another CometBFT endpoint"
+            );
+        }
+    }
+
+    /// Periodically check whether failed endpoints have recovered, such
+    /// that they re-enter the pool of healthy endpoints.
+    pub fn spawn_recheck(&self, interval: Duration) {
+        if self.0.endpoints.len() == 1 {
+            return;
+        }
+
+        let inner = Arc::clone(&self.0);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                for endpoint in &inner.endpoints {
+                    if !endpoint.has_failed() {
+                        continue;
+                    }
+
+                    if endpoint.client.status().await.is_ok() {
+                        endpoint.set_failed(false);
+                        tracing::info!(
+                            url = endpoint.url,
+                            "CometBFT endpoint has recovered"
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Select the RPC dialect matching the version reported by the node
+/// at `url`, falling back to [`DEFAULT_COMPAT_MODE`].
+async fn detect_compat_mode(url: &str, client: &HttpClient) -> CompatMode {
+    // NB: the status endpoint is the same across all dialects
+    let version = match client.status().await {
+        Ok(status) => status.node_info.version,
+        Err(err) => {
+            tracing::warn!(
+                url,
+                reason = err.to_string(),
+                compat_mode = %DEFAULT_COMPAT_MODE,
+                "Failed to query CometBFT version, using default compat mode"
+            );
+            return DEFAULT_COMPAT_MODE;
+        }
+    };
+
+    match CompatMode::from_version(version.clone()) {
+        Ok(compat_mode) => {
+            tracing::info!(url, %version, %compat_mode, "Detected compat mode");
+            compat_mode
+        }
+        Err(err) => {
+            tracing::warn!(
+                url,
+                %version,
+                reason = err.to_string(),
+                compat_mode = %DEFAULT_COMPAT_MODE,
+                "Unsupported CometBFT version, using default compat mode"
+            );
+            DEFAULT_COMPAT_MODE
+        }
+    }
+}
+
+/// Check if `err` was caused by a failure to reach the CometBFT node.
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<tendermint_rpc::Error>()
+            .is_some_and(|err| {
+                matches!(
+                    err.detail(),
+                    ErrorDetail::Io(_)
+                        | ErrorDetail::Http(_)
+                        | ErrorDetail::HttpRequestFailed(_)
+                        | ErrorDetail::Timeout(_)
+                )
+            })
+    })
+}
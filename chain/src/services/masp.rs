@@ -1,22 +1,28 @@
+use anyhow::Context;
 use namada_core::masp_primitives::ff::PrimeField;
 use namada_core::masp_primitives::sapling::Node;
 use namada_core::masp_primitives::transaction::Transaction;
-use namada_sdk::masp_primitives::merkle_tree::IncrementalWitness;
-use shared::indexed_tx::IndexedTx;
+use shared::height::BlockHeight;
 
 use crate::entity::commitment_tree::CommitmentTree;
-use crate::entity::tx_notes_index::TxNoteMap;
 use crate::entity::witness_map::WitnessMap;
 
+/// Return the note commitments of the shielded outputs of `stx_batch`.
+pub fn note_commitments(
+    stx_batch: &Transaction,
+) -> impl Iterator<Item = Node> + '_ {
+    stx_batch
+        .sapling_bundle()
+        .into_iter()
+        .flat_map(|bundle| &bundle.shielded_outputs)
+        .map(|so| Node::new(so.cmu.to_repr()))
+}
+
 pub fn update_commitment_tree(
     commitment_tree: &CommitmentTree,
     stx_batch: &Transaction,
 ) -> anyhow::Result<()> {
-    for so in stx_batch
-        .sapling_bundle()
-        .map_or(&vec![], |x| &x.shielded_outputs)
-    {
-        let node = Node::new(so.cmu.to_repr());
+    for node in note_commitments(stx_batch) {
         if !commitment_tree.append(node) {
             anyhow::bail!("Note commitment tree is full");
         }
@@ -25,36 +31,53 @@ pub fn update_commitment_tree(
     Ok(())
 }
 
-pub fn update_witness_map_and_note_index(
-    note_pos: &mut usize,
+/// Add the witnesses of the notes committed in a block to the witness
+/// map, and append these notes to the witnesses already in it.
+///
+/// The commitment tree must hold `note_commitments`, in the same order,
+/// on top of its last committed state.
+pub fn update_witness_map(
     commitment_tree: &CommitmentTree,
-    tx_notes_index: &mut TxNoteMap,
     witness_map: &WitnessMap,
-    indexed_tx: IndexedTx,
-    shielded: &Transaction,
+    note_commitments: &[Node],
 ) -> anyhow::Result<()> {
-    tx_notes_index.insert(indexed_tx, *note_pos);
+    let mut tree = commitment_tree.get_committed_tree();
 
-    for so in shielded
-        .sapling_bundle()
-        .map_or(&vec![], |x| &x.shielded_outputs)
-    {
-        // Create merkle tree leaf node from note commitment
-        let node = Node::new(so.cmu.to_repr());
-
-        // Update each merkle tree in the witness map with the latest
-        // addition
-        witness_map.update(node).map_err(|note_pos| {
-            anyhow::anyhow!("Witness map is full at note position {note_pos}")
-        })?;
-
-        // Finally, make it easier to construct merkle paths to this new
-        // note
-        let witness =
-            IncrementalWitness::<Node>::from_tree(&commitment_tree.get_tree());
-        witness_map.insert(*note_pos, witness);
-        *note_pos += 1;
+    witness_map.append_notes(&mut tree, note_commitments)?;
+
+    if tree.root() != commitment_tree.root() {
+        anyhow::bail!(
+            "Notes appended to the witness map do not match the commitment \
+             tree"
+        );
+    }
+
+    Ok(())
+}
+
+/// Re-apply the shielded txs committed at `block_height` to the
+/// commitment tree and witness map, in the same order they were
+/// originally processed in.
+pub fn replay_block(
+    commitment_tree: &CommitmentTree,
+    witness_map: &WitnessMap,
+    block_height: BlockHeight,
+    shielded_txs: &[Transaction],
+) -> anyhow::Result<()> {
+    let mut block_note_commitments = Vec::new();
+
+    for stx_batch in shielded_txs {
+        update_commitment_tree(commitment_tree, stx_batch)?;
+        block_note_commitments.extend(note_commitments(stx_batch));
     }
 
+    update_witness_map(commitment_tree, witness_map, &block_note_commitments)
+        .with_context(|| {
+        format!("Failed to update the witness map at height {block_height}")
+    })?;
+
+    commitment_tree.commit();
+    witness_map.commit();
+
     Ok(())
 }
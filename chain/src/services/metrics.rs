@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+
+use axum::Router;
+use axum::http::StatusCode;
+use axum::routing::get;
+use prometheus::{
+    Encoder, Gauge, Histogram, IntCounter, IntCounterVec, IntGauge,
+    TextEncoder, register_gauge, register_histogram, register_int_counter,
+    register_int_counter_vec, register_int_gauge,
+};
+use shared::height::BlockHeight;
+
+/// Number of recent commits used to estimate the indexing rate.
+const RATE_WINDOW: usize = 64;
+
+pub static LAST_SYNCED_HEIGHT: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "masp_indexer_last_synced_height",
+        "Last block height committed by the crawler"
+    )
+    .unwrap()
+});
+
+pub static CHAIN_TIP_HEIGHT: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "masp_indexer_chain_tip_height",
+        "Last block height committed by the chain"
+    )
+    .unwrap()
+});
+
+pub static LAG_BLOCKS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "masp_indexer_lag_blocks",
+        "Number of blocks the crawler is behind the tip of the chain"
+    )
+    .unwrap()
+});
+
+pub static BLOCKS_PER_SECOND: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!(
+        "masp_indexer_blocks_per_second",
+        "Rate at which blocks are committed by the crawler"
+    )
+    .unwrap()
+});
+
+pub static BUILD_AND_COMMIT_DURATION: LazyLock<Histogram> =
+    LazyLock::new(|| {
+        register_histogram!(
+            "masp_indexer_build_and_commit_duration_seconds",
+            "Time taken to build and commit the masp data of a block",
+            vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]
+        )
+        .unwrap()
+    });
+
+pub static COMMIT_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "masp_indexer_commit_duration_seconds",
+        "Time taken to commit a batch of blocks to the database",
+        vec![
+            0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0
+        ]
+    )
+    .unwrap()
+});
+
+pub static LAST_COMMIT_DURATION: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!(
+        "masp_indexer_last_commit_duration_seconds",
+        "Time taken by the last commit of a batch of blocks"
+    )
+    .unwrap()
+});
+
+pub static MASP_TXS_INDEXED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "masp_indexer_masp_txs_indexed_total",
+        "Number of masp txs committed by the crawler"
+    )
+    .unwrap()
+});
+
+pub static COMMITMENT_TREE_SIZE: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "masp_indexer_commitment_tree_size",
+        "Number of notes in the commitment tree"
+    )
+    .unwrap()
+});
+
+pub static RPC_ERRORS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "masp_indexer_rpc_errors_total",
+        "Number of failed requests to CometBFT"
+    )
+    .unwrap()
+});
+
+pub static RETRIES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "masp_indexer_retries_total",
+        "Number of retries of failed operations",
+        &["operation"]
+    )
+    .unwrap()
+});
+
+pub static REORGS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "masp_indexer_reorgs_total",
+        "Number of chain reorgs detected by the crawler"
+    )
+    .unwrap()
+});
+
+pub static ROLLED_BACK_BLOCKS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "masp_indexer_rolled_back_blocks_total",
+        "Number of committed blocks rolled back due to chain reorgs"
+    )
+    .unwrap()
+});
+
+static COMMIT_INSTANTS: LazyLock<Mutex<VecDeque<Instant>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(RATE_WINDOW)));
+
+/// Record the last block height of the chain, and the resulting lag
+/// behind the last synced height.
+pub fn observe_chain_tip(chain_tip: BlockHeight) {
+    CHAIN_TIP_HEIGHT.set(chain_tip.0 as i64);
+    update_lag();
+}
+
+/// Record a batch of blocks that was successfully committed, up to
+/// `block_height`.
+pub fn observe_committed_block(block_height: BlockHeight, num_txs: usize) {
+    LAST_SYNCED_HEIGHT.set(block_height.0 as i64);
+    MASP_TXS_INDEXED.inc_by(num_txs as u64);
+    update_lag();
+
+    let mut instants = COMMIT_INSTANTS.lock().unwrap();
+    if instants.len() == RATE_WINDOW {
+        instants.pop_front();
+    }
+    instants.push_back(Instant::now());
+
+    if let (Some(first), Some(last)) = (instants.front(), instants.back()) {
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        if elapsed > 0.0 {
+            BLOCKS_PER_SECOND.set((instants.len() - 1) as f64 / elapsed);
+        }
+    }
+}
+
+/// Record a chain reorg, detected at `block_height` and rolled back to
+/// `common_ancestor`.
+pub fn observe_reorg(
+    block_height: BlockHeight,
+    common_ancestor: Option<BlockHeight>,
+) {
+    let last_committed = block_height.0.saturating_sub(1);
+    let rolled_back =
+        last_committed.saturating_sub(common_ancestor.map_or(0, |h| h.0));
+
+    REORGS.inc();
+    ROLLED_BACK_BLOCKS.inc_by(rolled_back);
+}
+
+fn update_lag() {
+    let lag = CHAIN_TIP_HEIGHT.get() - LAST_SYNCED_HEIGHT.get();
+    LAG_BLOCKS.set(lag.max(0));
+}
+
+/// Serve the metrics of the crawler in the Prometheus text format, under
+/// the `/metrics` route.
+pub fn spawn_server(port: u16) {
+    // NB: register all metrics upfront, such that they
+    // are exported before any block gets processed
+    LazyLock::force(&LAST_SYNCED_HEIGHT);
+    LazyLock::force(&CHAIN_TIP_HEIGHT);
+    LazyLock::force(&LAG_BLOCKS);
+    LazyLock::force(&BLOCKS_PER_SECOND);
+    LazyLock::force(&BUILD_AND_COMMIT_DURATION);
+    LazyLock::force(&COMMIT_DURATION);
+    LazyLock::force(&LAST_COMMIT_DURATION);
+    LazyLock::force(&MASP_TXS_INDEXED);
+    LazyLock::force(&COMMITMENT_TREE_SIZE);
+    LazyLock::force(&RPC_ERRORS);
+    LazyLock::force(&RETRIES);
+    LazyLock::force(&REORGS);
+    LazyLock::force(&ROLLED_BACK_BLOCKS);
+
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+    let router = Router::new().route("/metrics", get(render));
+
+    tokio::spawn(async move {
+        tracing::info!(%addr, "Serving metrics");
+
+        if let Err(reason) = axum::Server::bind(&addr)
+            .serve(router.into_make_service())
+            .await
+        {
+            tracing::error!(?reason, "Metrics server shut down unexpectedly");
+        }
+    });
+}
+
+async fn render() -> Result<String, StatusCode> {
+    let mut buffer = Vec::new();
+
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|reason| {
+            tracing::error!(?reason, "Failed to encode metrics");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    String::from_utf8(buffer).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -1,4 +1,16 @@
+pub mod admin;
+pub mod audit;
+pub mod backfill;
+pub mod block_stats;
 pub mod cometbft;
 pub mod db;
+pub mod failover;
 pub mod masp;
+pub mod metrics;
+pub mod prefetch;
+pub mod retry;
 pub mod rpc;
+pub mod shutdown;
+pub mod sync_marker;
+pub mod webhook;
+pub mod witness_checkpoint;
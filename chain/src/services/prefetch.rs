@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use shared::block::Block;
+use shared::height::BlockHeight;
+use tokio::task::JoinHandle;
+
+use super::backfill::Backfiller;
+use super::cometbft as cometbft_service;
+use super::failover::FailoverClient;
+
+/// Source of the data of the blocks processed by the crawler.
+pub enum BlockSource {
+    /// Blocks are prefetched a few heights ahead of the one processed.
+    Prefetch(Prefetcher),
+    /// Blocks of a historical range are fetched by backfill workers.
+    Backfill(Backfiller),
+}
+
+impl BlockSource {
+    /// Return the data of the block at `block_height`.
+    pub async fn fetch(
+        &self,
+        block_height: BlockHeight,
+        chain_tip: BlockHeight,
+    ) -> anyhow::Result<Block> {
+        match self {
+            Self::Prefetch(prefetcher) => {
+                prefetcher.fetch(block_height, chain_tip).await
+            }
+            Self::Backfill(backfiller) => backfiller.fetch(block_height).await,
+        }
+    }
+}
+
+/// Fetches the data of upcoming blocks from CometBFT concurrently, while
+/// the blocks themselves are still processed in height order.
+///
+/// At most `depth` blocks are in flight (or fetched, but not yet taken)
+/// at any time, which bounds the memory spent on prefetched blocks.
+pub struct Prefetcher {
+    client: FailoverClient,
+    depth: u64,
+    pending: Mutex<VecDeque<(BlockHeight, JoinHandle<anyhow::Result<Block>>)>>,
+}
+
+impl Prefetcher {
+    pub fn new(client: FailoverClient, depth: u64) -> Self {
+        Self {
+            client,
+            depth,
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Return the data of the block at `block_height`, and schedule the
+    /// fetches of the blocks following it, up to `chain_tip`.
+    pub async fn fetch(
+        &self,
+        block_height: BlockHeight,
+        chain_tip: BlockHeight,
+    ) -> anyhow::Result<Block> {
+        let prefetched = self.take_and_schedule(block_height, chain_tip);
+
+        match prefetched {
+            Some(handle) => handle
+                .await
+                .context("Failed to join block prefetching task")?,
+            None => {
+                self.client
+                    .call(|client| {
+                        cometbft_service::query_masp_txs_in_block(
+                            client,
+                            block_height,
+                        )
+                    })
+                    .await
+            }
+        }
+    }
+
+    fn take_and_schedule(
+        &self,
+        block_height: BlockHeight,
+        chain_tip: BlockHeight,
+    ) -> Option<JoinHandle<anyhow::Result<Block>>> {
+        let mut pending = self.pending.lock().unwrap();
+
+        // NB: blocks below the requested height are no longer needed
+        while pending
+            .front()
+            .is_some_and(|(height, _)| *height < block_height)
+        {
+            if let Some((_, handle)) = pending.pop_front() {
+                handle.abort();
+            }
+        }
+
+        let prefetched = match pending.front() {
+            Some((height, _)) if *height == block_height => {
+                pending.pop_front().map(|(_, handle)| handle)
+            }
+            // NB: the crawler moved back (e.g. after a chain reorg),
+            // so whatever was prefetched is of no use. if it is only
+            // retrying the block it just took, keep the following ones
+            Some((height, _)) if height.0 > block_height.0 + 1 => {
+                for (_, handle) in pending.drain(..) {
+                    handle.abort();
+                }
+                None
+            }
+            _ => None,
+        };
+
+        let next_height = pending
+            .back()
+            .map_or(block_height.0, |(height, _)| height.0)
+            + 1;
+        let last_height = (block_height.0 + self.depth).min(chain_tip.0);
+
+        for height in (next_height..=last_height).map(BlockHeight) {
+            let client = self.client.clone();
+
+            let handle = tokio::spawn(async move {
+                client
+                    .call(|client| {
+                        cometbft_service::query_masp_txs_in_block(
+                            client, height,
+                        )
+                    })
+                    .await
+            });
+
+            pending.push_back((height, handle));
+        }
+
+        prefetched
+    }
+}
+
+impl Drop for Prefetcher {
+    fn drop(&mut self) {
+        for (_, handle) in self.pending.get_mut().unwrap().drain(..) {
+            handle.abort();
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use shared::error::MainError;
+use tokio_retry::strategy::{ExponentialBackoff, jitter};
+
+use super::metrics;
+use super::shutdown::ExitHandle;
+
+/// How the delay between retries evolves.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum RetryStrategy {
+    /// Wait the same interval before every retry.
+    Fixed,
+    /// Multiply the interval by the backoff base after every retry.
+    #[default]
+    Exponential,
+}
+
+/// Exponential backoff between retries, which gives up once a deadline
+/// or a maximum number of retries is exceeded.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    backoff: ExponentialBackoff,
+    max_elapsed: Option<Duration>,
+    max_retries: Option<usize>,
+}
+
+impl RetryPolicy {
+    /// Build a policy that waits `multiplier * base^n` before retry `n`,
+    /// with each of these delays capped at `max_interval`. The base is
+    /// ignored by the fixed strategy.
+    pub fn new(
+        strategy: RetryStrategy,
+        base: u64,
+        multiplier: Duration,
+        max_interval: Duration,
+        max_elapsed: Option<Duration>,
+        max_retries: Option<usize>,
+    ) -> Self {
+        let base = match strategy {
+            RetryStrategy::Fixed => 1,
+            RetryStrategy::Exponential => base,
+        };
+        let backoff = ExponentialBackoff::from_millis(base)
+            .factor(multiplier.as_millis() as u64)
+            .max_delay(max_interval);
+
+        Self {
+            backoff,
+            max_elapsed,
+            max_retries,
+        }
+    }
+
+    /// Delays between retries, starting from the initial interval.
+    pub fn strategy(&self) -> impl Iterator<Item = Duration> + use<> {
+        self.backoff.clone().map(jitter)
+    }
+
+    /// Retry condition that stops retrying once a shutdown is requested, or
+    /// when the max elapsed time since the condition was created or the
+    /// max number of retries is exceeded. Retries are counted under the
+    /// `operation` label.
+    pub fn condition(
+        &self,
+        exit_handle: &ExitHandle,
+        operation: &'static str,
+    ) -> impl FnMut(&MainError) -> bool {
+        let started_at = Instant::now();
+        let max_elapsed = self.max_elapsed;
+        let max_retries = self.max_retries;
+        let mut num_retries = 0;
+        let retries = metrics::RETRIES.with_label_values(&[operation]);
+
+        move |_| {
+            if exit_handle.must_exit() {
+                return false;
+            }
+
+            if let Some(max_elapsed) =
+                max_elapsed.filter(|max| started_at.elapsed() >= *max)
+            {
+                tracing::error!(
+                    operation,
+                    ?max_elapsed,
+                    num_retries,
+                    "Giving up retrying, aborting"
+                );
+                return false;
+            }
+
+            if max_retries.is_some_and(|max| num_retries >= max) {
+                tracing::error!(
+                    operation,
+                    num_retries,
+                    "Giving up retrying, aborting"
+                );
+                return false;
+            }
+
+            num_retries += 1;
+            retries.inc();
+            true
+        }
+    }
+}
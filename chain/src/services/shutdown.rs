@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::watch;
+
+/// Handle to the shutdown request of the crawler, triggered by an INT,
+/// TERM or QUIT signal. A second signal exits right away, without
+/// waiting for in-flight work to finish.
+#[derive(Clone)]
+pub struct ExitHandle(watch::Receiver<bool>);
+
+impl ExitHandle {
+    /// Install the signal handlers, and return a handle to them.
+    pub fn install() -> Self {
+        let (sender, receiver) = watch::channel(false);
+
+        tokio::spawn(async move {
+            let mut interrupt = signal(SignalKind::interrupt())
+                .expect("Failed to install INT signal handler");
+            let mut term = signal(SignalKind::terminate())
+                .expect("Failed to install TERM signal handler");
+            let mut quit = signal(SignalKind::quit())
+                .expect("Failed to install QUIT signal handler");
+
+            let signal_descriptor = tokio::select! {
+                _ = interrupt.recv() => "INT",
+                _ = term.recv() => "TERM",
+                _ = quit.recv() => "QUIT",
+            };
+            tracing::info!(which = signal_descriptor, "Signal received");
+
+            _ = sender.send(true);
+
+            let signal_descriptor = tokio::select! {
+                _ = interrupt.recv() => "INT",
+                _ = term.recv() => "TERM",
+                _ = quit.recv() => "QUIT",
+            };
+            tracing::warn!(
+                which = signal_descriptor,
+                "Signal received again, exiting immediately"
+            );
+
+            std::process::exit(1);
+        });
+
+        Self(receiver)
+    }
+
+    /// Exit the process if it is still running `timeout` after a shutdown
+    /// was requested, e.g. because a commit is stuck. The transaction of
+    /// an unfinished commit is rolled back by the database.
+    pub fn enforce_timeout(&self, timeout: Duration) {
+        let handle = self.clone();
+
+        tokio::spawn(async move {
+            handle.exited().await;
+            tokio::time::sleep(timeout).await;
+
+            tracing::error!(
+                ?timeout,
+                "Timed out waiting for in-flight work to finish, exiting"
+            );
+            std::process::exit(1);
+        });
+    }
+
+    /// Check if a shutdown was requested.
+    pub fn must_exit(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Wait until a shutdown is requested.
+    pub async fn exited(&self) {
+        if self.0.clone().wait_for(|&exit| exit).await.is_err() {
+            // NB: the signal handlers are gone, so no
+            // shutdown will ever be requested
+            std::future::pending::<()>().await;
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool};
+
+use shared::height::BlockHeight;
+
+/// Keeps track of whether the crawler has caught up with the tip of the
+/// chain, signaling transitions via a structured log line and an
+/// optional marker file.
+pub struct SyncMarker {
+    path: Option<PathBuf>,
+    lag_threshold: u64,
+    synced: AtomicBool,
+}
+
+impl SyncMarker {
+    pub fn new(path: Option<PathBuf>, lag_threshold: u64) -> Self {
+        if let Some(path) = path.as_ref() {
+            // NB: a marker left behind by a previous run is stale
+            remove_marker(path);
+        }
+
+        Self {
+            path,
+            lag_threshold,
+            synced: AtomicBool::new(false),
+        }
+    }
+
+    /// Update the sync status, given the last committed block height and
+    /// the last block height of the chain.
+    pub fn update(&self, last_committed: BlockHeight, chain_tip: BlockHeight) {
+        let lag = chain_tip.0.saturating_sub(last_committed.0);
+        let synced = lag <= self.lag_threshold;
+
+        if self.synced.swap(synced, atomic::Ordering::Relaxed) == synced {
+            return;
+        }
+
+        if synced {
+            tracing::info!(
+                synced = true,
+                %last_committed,
+                %chain_tip,
+                "Crawler has caught up with the tip of the chain"
+            );
+            if let Some(path) = self.path.as_ref() {
+                write_marker(path, last_committed);
+            }
+        } else {
+            tracing::info!(
+                synced = false,
+                %last_committed,
+                %chain_tip,
+                lag,
+                "Crawler has fallen behind the tip of the chain"
+            );
+            if let Some(path) = self.path.as_ref() {
+                remove_marker(path);
+            }
+        }
+    }
+}
+
+fn write_marker(path: &Path, last_committed: BlockHeight) {
+    if let Err(reason) = std::fs::write(path, format!("{}\n", last_committed.0))
+    {
+        tracing::warn!(?path, ?reason, "Failed to write sync marker file");
+    }
+}
+
+fn remove_marker(path: &Path) {
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(reason) => {
+            tracing::warn!(?path, ?reason, "Failed to remove sync marker file");
+        }
+    }
+}
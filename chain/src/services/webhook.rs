@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use shared::height::BlockHeight;
+use shared::id::Id;
+use tokio::sync::mpsc;
+use tokio_retry::Retry;
+use tokio_retry::strategy::{ExponentialBackoff, jitter};
+
+use super::block_stats::BlockCounts;
+
+/// Maximum number of notifications waiting to be delivered, before new
+/// notifications start being dropped.
+const BUFFER_CAPACITY: usize = 1024;
+
+/// Header carrying the hex encoded HMAC-SHA256 of the payload, keyed by
+/// the webhook secret.
+const SIGNATURE_HEADER: &str = "x-masp-indexer-signature";
+
+/// Time after which a delivery attempt is abandoned.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum delay between delivery attempts.
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Range of note positions, the end being exclusive.
+#[derive(Serialize)]
+struct NotePositions {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Serialize)]
+struct MaspActivityPayload {
+    block_height: u64,
+    /// Hash of the block, which tells apart the blocks committed at the
+    /// same height before and after a chain reorg.
+    block_hash: String,
+    num_transactions: usize,
+    note_positions: NotePositions,
+}
+
+#[derive(Serialize)]
+struct RollbackPayload {
+    /// Height of the last block kept, or 0 if all blocks were rolled
+    /// back. The masp activity of the blocks above it was orphaned.
+    block_height: u64,
+}
+
+/// Notification POSTed to the webhooks, tagged with its `event`.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Notification {
+    MaspActivity(MaspActivityPayload),
+    Rollback(RollbackPayload),
+}
+
+impl Notification {
+    fn block_height(&self) -> u64 {
+        match self {
+            Self::MaspActivity(payload) => payload.block_height,
+            Self::Rollback(payload) => payload.block_height,
+        }
+    }
+}
+
+/// Settings of the webhooks notified of new masp activity.
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    pub secret: String,
+    pub max_retries: usize,
+}
+
+/// POSTs a signed JSON payload to the configured webhooks for each
+/// committed block with masp txs. Notifications are delivered by a
+/// background task, in height order, such that an unreachable webhook
+/// never stalls the crawler.
+pub struct WebhookDispatcher {
+    sender: Option<mpsc::Sender<Notification>>,
+    staged: Vec<MaspActivityPayload>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: Option<WebhookConfig>) -> Self {
+        let sender = config.map(|config| {
+            let (sender, receiver) = mpsc::channel(BUFFER_CAPACITY);
+            tokio::spawn(deliver_notifications(config, receiver));
+            sender
+        });
+
+        Self {
+            sender,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Keep the masp activity of a block until it is committed. Blocks
+    /// without masp txs are ignored.
+    pub fn stage(
+        &mut self,
+        block_height: BlockHeight,
+        block_hash: &Id,
+        counts: BlockCounts,
+    ) {
+        if self.sender.is_none() || counts.num_transactions == 0 {
+            return;
+        }
+
+        self.staged.push(MaspActivityPayload {
+            block_height: block_height.0,
+            block_hash: block_hash.to_string(),
+            num_transactions: counts.num_transactions,
+            note_positions: NotePositions {
+                start: counts.first_note_pos,
+                end: counts.first_note_pos + counts.num_notes,
+            },
+        });
+    }
+
+    /// Forget the staged blocks, which will not be committed.
+    pub fn discard(&mut self) {
+        self.staged.clear();
+    }
+
+    /// Notify the webhooks of each staged block, after they were
+    /// committed.
+    pub fn flush(&mut self) {
+        let Some(sender) = self.sender.as_ref() else {
+            return;
+        };
+
+        for payload in self.staged.drain(..) {
+            send(sender, Notification::MaspActivity(payload));
+        }
+    }
+
+    /// Notify the webhooks that the committed blocks above
+    /// `last_block_height` were rolled back by a chain reorg, after
+    /// the masp activity of the blocks committed before.
+    pub fn rollback(&mut self, last_block_height: Option<BlockHeight>) {
+        let Some(sender) = self.sender.as_ref() else {
+            return;
+        };
+
+        send(
+            sender,
+            Notification::Rollback(RollbackPayload {
+                block_height: last_block_height.map_or(0, |height| height.0),
+            }),
+        );
+    }
+}
+
+fn send(sender: &mpsc::Sender<Notification>, notification: Notification) {
+    let block_height = notification.block_height();
+
+    if let Err(err) = sender.try_send(notification) {
+        tracing::warn!(
+            block_height,
+            reason = %err,
+            "Dropped webhook notification"
+        );
+    }
+}
+
+async fn deliver_notifications(
+    config: WebhookConfig,
+    mut receiver: mpsc::Receiver<Notification>,
+) {
+    let client =
+        match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(reason) => {
+                tracing::warn!(?reason, "Failed to build webhook HTTP client");
+                return;
+            }
+        };
+
+    while let Some(notification) = receiver.recv().await {
+        let body = serde_json::to_vec(&notification)
+            .expect("Serializing webhook payloads should not fail");
+        let signature = sign(&config.secret, &body);
+
+        futures::future::join_all(config.urls.iter().map(|url| {
+            deliver(
+                &client,
+                url,
+                &body,
+                &signature,
+                config.max_retries,
+                notification.block_height(),
+            )
+        }))
+        .await;
+    }
+}
+
+/// POST `body` to `url`, retrying with exponential backoff until the
+/// webhook responds with a success status, or `max_retries` is exceeded.
+async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    body: &[u8],
+    signature: &str,
+    max_retries: usize,
+    block_height: u64,
+) {
+    let strategy = ExponentialBackoff::from_millis(2)
+        .factor(500)
+        .max_delay(MAX_RETRY_INTERVAL)
+        .map(jitter)
+        .take(max_retries);
+
+    let result = Retry::spawn(strategy, || async {
+        client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .body(body.to_vec())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .inspect_err(|reason| {
+                tracing::debug!(
+                    url,
+                    block_height,
+                    %reason,
+                    "Webhook delivery attempt failed"
+                );
+            })
+    })
+    .await;
+
+    if let Err(reason) = result {
+        tracing::warn!(
+            url,
+            block_height,
+            %reason,
+            "Giving up delivering webhook notification"
+        );
+    }
+}
+
+/// Hex encoded HMAC-SHA256 of `body`, keyed by `secret`, prefixed with
+/// the name of the algorithm.
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+use shared::height::BlockHeight;
+
+/// Decides at which block heights the witness map is persisted. Without
+/// a block or time interval, it is persisted along with every block.
+pub struct WitnessCheckpoint {
+    every_blocks: Option<u64>,
+    every: Option<Duration>,
+    last_height: Option<BlockHeight>,
+    last_at: Instant,
+}
+
+impl WitnessCheckpoint {
+    pub fn new(
+        every_blocks: Option<u64>,
+        every: Option<Duration>,
+        last_height: Option<BlockHeight>,
+    ) -> Self {
+        Self {
+            every_blocks,
+            every,
+            last_height,
+            last_at: Instant::now(),
+        }
+    }
+
+    /// Check whether the witness map must be persisted along with the
+    /// block at `block_height`, in which case the next checkpoint is
+    /// counted from it.
+    pub fn is_due(&mut self, block_height: BlockHeight) -> bool {
+        let due = match (self.every_blocks, self.every) {
+            (None, None) => true,
+            (every_blocks, every) => {
+                let blocks_since = block_height
+                    .0
+                    .saturating_sub(self.last_height.map_or(0, |h| h.0));
+
+                every_blocks.is_some_and(|n| blocks_since >= n)
+                    || every.is_some_and(|t| self.last_at.elapsed() >= t)
+            }
+        };
+
+        if due {
+            self.last_height = Some(block_height);
+            self.last_at = Instant::now();
+        }
+
+        due
+    }
+}
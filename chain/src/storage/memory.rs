@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::Utc;
+use namada_sdk::borsh::BorshDeserialize;
+use namada_sdk::masp_primitives::merkle_tree::IncrementalWitness;
+use namada_sdk::masp_primitives::sapling::Node;
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::chain_state::ChainStateDb;
+use orm::compact_output::CompactOutputDb;
+use orm::failed_block::FailedBlockDb;
+use orm::memory::{MemoryDb, Tables};
+use orm::notes_index::{NotesIndexDb, NotesIndexInsertDb};
+use orm::nullifier::NullifierDb;
+use orm::tree::TreeDb;
+use orm::tx::TxDb;
+use orm::witness::WitnessDb;
+use shared::height::BlockHeight;
+
+use super::{Storage, missing_block_ranges};
+use crate::entity::commit_batch::CommitBatch;
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::witness_map::WitnessMap;
+
+/// Insert all the rows of `batch` into `tables`, except for its chain
+/// state.
+fn insert_batch(tables: &mut Tables, batch: &CommitBatch) {
+    for tree in &batch.commitment_trees {
+        let id = tables.next_id();
+        tables.commitment_tree.push(TreeDb {
+            id,
+            tree: tree.tree.clone(),
+            block_height: tree.block_height,
+        });
+    }
+
+    for witness in &batch.witnesses {
+        let id = tables.next_id();
+        tables.witness.push(WitnessDb {
+            id,
+            witness_idx: witness.witness_idx,
+            block_height: witness.block_height,
+            witness_bytes: witness.witness_bytes.clone(),
+        });
+    }
+
+    for note in &batch.notes_index {
+        // NB: same semantics as `ON CONFLICT DO NOTHING`
+        tables
+            .notes_index
+            .entry(note.note_position)
+            .or_insert_with(|| NotesIndexDb {
+                block_index: note.block_index,
+                note_position: note.note_position,
+                block_height: note.block_height,
+                masp_tx_index: note.masp_tx_index,
+                is_fee_unshielding: note.is_fee_unshielding,
+            });
+    }
+
+    for tx in &batch.shielded_txs {
+        let id = tables.next_id();
+        tables.tx.push(TxDb {
+            id,
+            block_index: tx.block_index,
+            tx_bytes: tx.tx_bytes.clone(),
+            block_height: tx.block_height,
+            masp_tx_index: tx.masp_tx_index,
+        });
+    }
+
+    for nullifier in &batch.nullifiers {
+        if tables.nullifiers.contains_key(&nullifier.nullifier) {
+            // NB: same semantics as `ON CONFLICT DO NOTHING`
+            continue;
+        }
+        let id = tables.next_id();
+        tables.nullifiers.insert(
+            nullifier.nullifier.clone(),
+            NullifierDb {
+                id,
+                nullifier: nullifier.nullifier.clone(),
+                block_height: nullifier.block_height,
+                block_index: nullifier.block_index,
+                masp_tx_index: nullifier.masp_tx_index,
+            },
+        );
+    }
+
+    for output in &batch.compact_outputs {
+        let key = (
+            output.block_height,
+            output.block_index,
+            output.masp_tx_index,
+            output.output_index,
+        );
+        if tables.compact_outputs.contains_key(&key) {
+            // NB: same semantics as `ON CONFLICT DO NOTHING`
+            continue;
+        }
+        let id = tables.next_id();
+        tables.compact_outputs.insert(
+            key,
+            CompactOutputDb {
+                id,
+                block_height: output.block_height,
+                block_index: output.block_index,
+                masp_tx_index: output.masp_tx_index,
+                output_index: output.output_index,
+                cmu: output.cmu.clone(),
+                ephemeral_key: output.ephemeral_key.clone(),
+                ciphertext: output.ciphertext.clone(),
+            },
+        );
+    }
+
+    for block_hash in &batch.block_hashes {
+        tables
+            .block_hash
+            .insert(block_hash.block_height, block_hash.clone());
+    }
+
+    for anchor in &batch.anchors {
+        tables.anchors.insert(anchor.block_height, anchor.clone());
+    }
+}
+
+/// Storage backend that keeps all indexed data in memory, for tests.
+/// All data is lost on exit.
+#[derive(Default, Clone)]
+pub struct InMemoryStorage {
+    db: MemoryDb,
+}
+
+impl InMemoryStorage {
+    /// Database url prefix selecting the in-memory backend.
+    pub const URL_PREFIX: &str = "memory://";
+
+    /// Tables written by the crawler, which the webserver can be pointed
+    /// at to query the indexed data.
+    pub fn db(&self) -> &MemoryDb {
+        &self.db
+    }
+}
+
+impl Storage for InMemoryStorage {
+    async fn get_last_synced_block(
+        &self,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        let tables = self.db.lock();
+        Ok(tables.last_synced_height().map(BlockHeight::from))
+    }
+
+    async fn get_last_commitment_tree(
+        &self,
+    ) -> anyhow::Result<Option<CommitmentTree>> {
+        let tables = self.db.lock();
+
+        let max_block_height = tables
+            .commitment_tree
+            .iter()
+            .map(|tree| tree.block_height)
+            .max();
+
+        tables
+            .commitment_tree
+            .iter()
+            .find(|tree| Some(tree.block_height) == max_block_height)
+            .cloned()
+            .map(|tree| {
+                tree.try_into().context(
+                    "Failed to deserialize commitment tree from memory",
+                )
+            })
+            .transpose()
+    }
+
+    async fn get_commitment_tree_at(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<CommitmentTree>> {
+        let tables = self.db.lock();
+
+        tables
+            .commitment_tree
+            .iter()
+            .filter(|tree| tree.block_height <= block_height.0 as i32)
+            .max_by_key(|tree| tree.block_height)
+            .cloned()
+            .map(|tree| {
+                tree.try_into().context(
+                    "Failed to deserialize commitment tree from memory",
+                )
+            })
+            .transpose()
+    }
+
+    async fn get_last_witness_map(
+        &self,
+    ) -> anyhow::Result<(Option<BlockHeight>, WitnessMap)> {
+        let tables = self.db.lock();
+
+        let max_block_height =
+            tables.witness.iter().map(|w| w.block_height).max();
+
+        let witnesses = tables
+            .witness
+            .iter()
+            .filter(|witness| Some(witness.block_height) == max_block_height)
+            .try_fold(HashMap::new(), |mut accum, witness| {
+                let witness_node = IncrementalWitness::<Node>::try_from_slice(
+                    &witness.witness_bytes,
+                )
+                .context("Failed to deserialize note witness from memory")?;
+                let note_index = usize::try_from(witness.witness_idx)
+                    .context("Failed to convert note index from memory")?;
+                accum.insert(note_index, witness_node);
+                anyhow::Ok(accum)
+            })?;
+
+        Ok((
+            max_block_height.map(BlockHeight::from),
+            WitnessMap::new(witnesses),
+        ))
+    }
+
+    async fn get_block_hash(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<String>> {
+        let tables = self.db.lock();
+        Ok(tables
+            .block_hash
+            .get(&(block_height.0 as i32))
+            .map(|block_hash| block_hash.hash.clone()))
+    }
+
+    async fn get_notes_index(&self) -> anyhow::Result<Vec<NotesIndexInsertDb>> {
+        let tables = self.db.lock();
+        Ok(tables
+            .notes_index
+            .values()
+            .map(|note| NotesIndexInsertDb {
+                block_index: note.block_index,
+                note_position: note.note_position,
+                block_height: note.block_height,
+                masp_tx_index: note.masp_tx_index,
+                is_fee_unshielding: note.is_fee_unshielding,
+            })
+            .collect())
+    }
+
+    async fn get_missing_block_ranges(
+        &self,
+    ) -> anyhow::Result<Vec<RangeInclusive<BlockHeight>>> {
+        let tables = self.db.lock();
+        Ok(missing_block_ranges(
+            tables.block_hash.keys().copied(),
+            tables.last_synced_height(),
+        ))
+    }
+
+    async fn replay_shielded_txs<F>(
+        &self,
+        after: Option<BlockHeight>,
+        mut replay_block: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(BlockHeight, Vec<Transaction>) -> anyhow::Result<()>
+            + Send
+            + 'static,
+    {
+        let after = after.map_or(-1, |h| h.0 as i32);
+        let mut txs = self.db.lock().tx.clone();
+        txs.retain(|tx| tx.block_height > after);
+
+        // NB: stable sort, to preserve the insertion order
+        // of txs with the same keys
+        txs.sort_by_key(|tx| (tx.block_height, tx.masp_tx_index));
+
+        let mut blocks: Vec<(BlockHeight, Vec<Transaction>)> = Vec::new();
+
+        for tx in txs {
+            let block_height = BlockHeight::from(tx.block_height);
+            let masp_tx = Transaction::try_from_slice(&tx.tx_bytes)
+                .context("Failed to deserialize shielded tx from memory")?;
+
+            match blocks.last_mut() {
+                Some((height, txs)) if *height == block_height => {
+                    txs.push(masp_tx);
+                }
+                _ => blocks.push((block_height, vec![masp_tx])),
+            }
+        }
+
+        for (block_height, txs) in blocks {
+            replay_block(block_height, txs)?;
+        }
+
+        Ok(())
+    }
+
+    async fn commit(&self, batch: Arc<CommitBatch>) -> anyhow::Result<()> {
+        let Some(chain_state) = batch.chain_state_into_db() else {
+            return Ok(());
+        };
+
+        // NB: hold the lock for the entire commit, such that
+        // readers never observe a partially committed batch
+        let mut tables = self.db.lock();
+
+        if tables
+            .last_synced_height()
+            .is_some_and(|h| h >= chain_state.block_height)
+            && batch.is_committed(|height| {
+                tables
+                    .block_hash
+                    .get(&height)
+                    .map(|block_hash| block_hash.hash.as_str())
+            })
+        {
+            tracing::info!(
+                block_height = chain_state.block_height,
+                "Batch already committed to memory"
+            );
+            return Ok(());
+        }
+
+        batch.check_follows(tables.last_synced_height())?;
+
+        insert_batch(&mut tables, &batch);
+
+        tables.chain_state = Some(ChainStateDb {
+            block_height: chain_state.block_height,
+            chain_tip: chain_state.chain_tip,
+            committed_at: Utc::now().naive_utc(),
+        });
+
+        tracing::info!(
+            block_height = chain_state.block_height,
+            "Committed new blocks to memory"
+        );
+
+        Ok(())
+    }
+
+    async fn replace_blocks(
+        &self,
+        batch: Arc<CommitBatch>,
+    ) -> anyhow::Result<()> {
+        let (Some(first_height), Some(block_height)) =
+            (batch.first_block_height(), batch.block_height())
+        else {
+            return Ok(());
+        };
+        let range = first_height.0 as i32..=block_height.0 as i32;
+
+        let mut tables = self.db.lock();
+
+        if tables.last_synced_height().is_none_or(|h| h < *range.end()) {
+            anyhow::bail!(
+                "Cannot replace blocks up to {block_height}, past the last \
+                 synced height {:?}",
+                tables.last_synced_height()
+            );
+        }
+
+        tables
+            .commitment_tree
+            .retain(|tree| !range.contains(&tree.block_height));
+        tables
+            .witness
+            .retain(|witness| !range.contains(&witness.block_height));
+        tables
+            .notes_index
+            .retain(|_, note| !range.contains(&note.block_height));
+        tables.tx.retain(|tx| !range.contains(&tx.block_height));
+        tables
+            .nullifiers
+            .retain(|_, nullifier| !range.contains(&nullifier.block_height));
+        tables
+            .compact_outputs
+            .retain(|_, output| !range.contains(&output.block_height));
+        tables.failed_blocks.retain(|h, _| !range.contains(h));
+
+        insert_batch(&mut tables, &batch);
+
+        tracing::info!(
+            %first_height,
+            %block_height,
+            "Replaced blocks in memory"
+        );
+
+        Ok(())
+    }
+
+    async fn prune_witness_checkpoints(&self, keep: u64) -> anyhow::Result<()> {
+        let mut tables = self.db.lock();
+
+        let mut checkpoint_heights = tables
+            .witness
+            .iter()
+            .map(|witness| witness.block_height)
+            .collect::<Vec<_>>();
+        checkpoint_heights.sort_unstable_by(|a, b| b.cmp(a));
+        checkpoint_heights.dedup();
+
+        if let Some(&oldest_kept) =
+            checkpoint_heights.get(keep.saturating_sub(1) as usize)
+        {
+            tables
+                .witness
+                .retain(|witness| witness.block_height >= oldest_kept);
+        }
+
+        Ok(())
+    }
+
+    async fn record_failed_block(
+        &self,
+        block_height: BlockHeight,
+        error: String,
+        skipped: bool,
+    ) -> anyhow::Result<()> {
+        let mut tables = self.db.lock();
+        let block_height = block_height.0 as i32;
+
+        tables.failed_blocks.insert(
+            block_height,
+            FailedBlockDb {
+                block_height,
+                error,
+                skipped,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn get_failed_blocks(&self) -> anyhow::Result<Vec<FailedBlockDb>> {
+        let tables = self.db.lock();
+        Ok(tables.failed_blocks.values().cloned().collect())
+    }
+
+    async fn get_start_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let tables = self.db.lock();
+        Ok(tables.start_height.map(BlockHeight::from))
+    }
+
+    async fn set_start_height(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<()> {
+        let mut tables = self.db.lock();
+        tables.start_height = Some(block_height.0 as i32);
+        Ok(())
+    }
+
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>> {
+        let tables = self.db.lock();
+        Ok(tables.chain_id.clone())
+    }
+
+    async fn set_chain_id(&self, chain_id: String) -> anyhow::Result<()> {
+        let mut tables = self.db.lock();
+        tables.chain_id = Some(chain_id);
+        Ok(())
+    }
+
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let tables = self.db.lock();
+        Ok(tables.pruned_height.map(BlockHeight::from))
+    }
+
+    async fn prune(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        let mut tables = self.db.lock();
+
+        let last_checkpoint =
+            tables.witness.iter().map(|w| w.block_height).max();
+        let pruned = tables.pruned_height;
+
+        let Some(cutoff) = last_checkpoint
+            .map(|h| h.min(block_height.0 as i32))
+            .filter(|&h| pruned.is_none_or(|p| h > p))
+        else {
+            return Ok(pruned.map(BlockHeight::from));
+        };
+
+        tables.tx.retain(|tx| tx.block_height > cutoff);
+        tables
+            .notes_index
+            .retain(|_, note| note.block_height > cutoff);
+        tables
+            .nullifiers
+            .retain(|_, nullifier| nullifier.block_height > cutoff);
+        tables
+            .compact_outputs
+            .retain(|_, output| output.block_height > cutoff);
+        tables.pruned_height = Some(cutoff);
+
+        Ok(Some(BlockHeight::from(cutoff)))
+    }
+
+    async fn rollback(
+        &self,
+        block_height: Option<BlockHeight>,
+    ) -> anyhow::Result<()> {
+        let mut tables = self.db.lock();
+        let height = block_height.map_or(-1, |h| h.0 as i32);
+
+        if let Some(pruned) = tables.pruned_height.filter(|_| height >= 0) {
+            let has_checkpoint = tables.witness.iter().any(|witness| {
+                (pruned..=height).contains(&witness.block_height)
+            });
+
+            if !has_checkpoint {
+                anyhow::bail!(
+                    "Cannot roll back to block height {height}, since the \
+                     shielded txs up to {pruned} were pruned"
+                );
+            }
+        }
+
+        tables
+            .commitment_tree
+            .retain(|tree| tree.block_height <= height);
+        tables
+            .witness
+            .retain(|witness| witness.block_height <= height);
+        tables
+            .notes_index
+            .retain(|_, note| note.block_height <= height);
+        tables.tx.retain(|tx| tx.block_height <= height);
+        tables
+            .nullifiers
+            .retain(|_, nullifier| nullifier.block_height <= height);
+        tables
+            .compact_outputs
+            .retain(|_, output| output.block_height <= height);
+        tables.block_hash.retain(|h, _| *h <= height);
+        tables.anchors.retain(|h, _| *h <= height);
+        tables.failed_blocks.retain(|h, _| *h <= height);
+        let chain_tip =
+            tables.chain_state.as_ref().map(|state| state.chain_tip);
+        tables.chain_state = block_height.map(|block_height| ChainStateDb {
+            block_height: block_height.0 as i32,
+            // NB: only used if no chain state is left, otherwise the
+            // last known tip is kept
+            chain_tip: chain_tip.unwrap_or(block_height.0 as i32),
+            committed_at: Utc::now().naive_utc(),
+        });
+        if block_height.is_none() {
+            tables.pruned_height = None;
+        }
+
+        tracing::info!(?block_height, "Rolled back blocks in memory");
+
+        Ok(())
+    }
+}
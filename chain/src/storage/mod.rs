@@ -0,0 +1,178 @@
+#[cfg(any(test, feature = "test-utils"))]
+pub mod memory;
+pub mod postgres;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb;
+#[cfg(test)]
+mod tests;
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::failed_block::FailedBlockDb;
+use orm::notes_index::NotesIndexInsertDb;
+use shared::height::BlockHeight;
+
+use crate::entity::commit_batch::CommitBatch;
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::witness_map::WitnessMap;
+
+/// Persistence backend of the crawler.
+///
+/// Implementations must commit all the data of a block atomically, and
+/// return it in the same order it was committed in.
+pub trait Storage: Clone {
+    async fn get_last_synced_block(
+        &self,
+    ) -> anyhow::Result<Option<BlockHeight>>;
+
+    async fn get_last_commitment_tree(
+        &self,
+    ) -> anyhow::Result<Option<CommitmentTree>>;
+
+    /// Commitment tree at the highest height at or below `block_height`.
+    async fn get_commitment_tree_at(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<CommitmentTree>>;
+
+    /// Last persisted witness map, along with the height it was
+    /// persisted at.
+    async fn get_last_witness_map(
+        &self,
+    ) -> anyhow::Result<(Option<BlockHeight>, WitnessMap)>;
+
+    /// Hash of the block committed at `block_height`, if any.
+    async fn get_block_hash(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<String>>;
+
+    /// All the notes committed so far, ordered by their position in the
+    /// commitment tree.
+    async fn get_notes_index(&self) -> anyhow::Result<Vec<NotesIndexInsertDb>>;
+
+    /// Ranges of block heights up to the last synced height that were
+    /// never committed, starting from the lowest committed block.
+    async fn get_missing_block_ranges(
+        &self,
+    ) -> anyhow::Result<Vec<RangeInclusive<BlockHeight>>>;
+
+    /// Feed all shielded txs committed after block height `after` to
+    /// `replay_block`, grouped by block height and sorted by their masp
+    /// tx index.
+    async fn replay_shielded_txs<F>(
+        &self,
+        after: Option<BlockHeight>,
+        replay_block: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(BlockHeight, Vec<Transaction>) -> anyhow::Result<()>
+            + Send
+            + 'static;
+
+    /// Atomically commit all the blocks in `batch`, along with a chain
+    /// state pointing at its highest block height. Committing a batch
+    /// whose blocks were all committed already is a no-op.
+    ///
+    /// Fails with [`NonContiguousCommit`] if the batch does not directly
+    /// follow the last synced height.
+    ///
+    /// [`NonContiguousCommit`]: crate::entity::commit_batch::NonContiguousCommit
+    async fn commit(&self, batch: Arc<CommitBatch>) -> anyhow::Result<()>;
+
+    /// Atomically replace the data committed for the blocks in `batch`
+    /// with the data in it, leaving the last synced height untouched.
+    /// The blocks in `batch` must all have been committed already.
+    async fn replace_blocks(
+        &self,
+        batch: Arc<CommitBatch>,
+    ) -> anyhow::Result<()>;
+
+    /// Delete all witness map checkpoints but the last `keep` ones.
+    async fn prune_witness_checkpoints(&self, keep: u64) -> anyhow::Result<()>;
+
+    /// Record that the block at `block_height` failed to be processed,
+    /// and whether it was skipped. Replaces any previous record of the
+    /// same block.
+    async fn record_failed_block(
+        &self,
+        block_height: BlockHeight,
+        error: String,
+        skipped: bool,
+    ) -> anyhow::Result<()>;
+
+    /// Blocks recorded as failed, sorted by height.
+    async fn get_failed_blocks(&self) -> anyhow::Result<Vec<FailedBlockDb>>;
+
+    /// Height the crawler was configured to start indexing after, as
+    /// recorded by [`Storage::set_start_height`].
+    async fn get_start_height(&self) -> anyhow::Result<Option<BlockHeight>>;
+
+    /// Record the height the crawler starts indexing after. Unlike the
+    /// rest of the data, it is kept across rollbacks.
+    async fn set_start_height(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<()>;
+
+    /// Chain id of the network the committed data was indexed from, as
+    /// recorded by [`Storage::set_chain_id`].
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>>;
+
+    /// Record the chain id of the network being indexed. Like the start
+    /// height, it is kept across rollbacks.
+    async fn set_chain_id(&self, chain_id: String) -> anyhow::Result<()>;
+
+    /// Highest block height whose shielded txs, notes, nullifiers and
+    /// compact outputs were pruned, as recorded by [`Storage::prune`].
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>>;
+
+    /// Atomically delete the shielded txs, notes, nullifiers and compact
+    /// outputs committed up to and including `block_height`. Blocks after
+    /// the last witness map checkpoint are never pruned, since their txs
+    /// are replayed on restart. Returns the resulting pruned height.
+    async fn prune(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<BlockHeight>>;
+
+    /// Atomically delete all the data committed after `block_height`,
+    /// or all committed data if `block_height` is `None`. Failed blocks
+    /// recorded after `block_height` are deleted as well.
+    ///
+    /// Fails if `block_height` is not preceded by a witness map
+    /// checkpoint taken at or after the pruned height, since the blocks
+    /// left could not be rebuilt otherwise.
+    async fn rollback(
+        &self,
+        block_height: Option<BlockHeight>,
+    ) -> anyhow::Result<()>;
+}
+
+/// Find the gaps in `committed_heights`, which must be sorted in
+/// ascending order, up to and including `last_synced_height`.
+fn missing_block_ranges(
+    committed_heights: impl IntoIterator<Item = i32>,
+    last_synced_height: Option<i32>,
+) -> Vec<RangeInclusive<BlockHeight>> {
+    let mut ranges = vec![];
+    let mut next_height = None;
+
+    for height in committed_heights {
+        if let Some(next) = next_height.filter(|&next| next < height) {
+            ranges
+                .push(BlockHeight::from(next)..=BlockHeight::from(height - 1));
+        }
+        next_height = Some(height + 1);
+    }
+
+    if let (Some(next), Some(last)) = (next_height, last_synced_height) {
+        if next <= last {
+            ranges.push(BlockHeight::from(next)..=BlockHeight::from(last));
+        }
+    }
+
+    ranges
+}
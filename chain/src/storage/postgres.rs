@@ -0,0 +1,212 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::failed_block::FailedBlockDb;
+use orm::notes_index::NotesIndexInsertDb;
+use shared::height::BlockHeight;
+
+use super::{Storage, missing_block_ranges};
+use crate::appstate::AppState;
+use crate::entity::commit_batch::CommitBatch;
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::witness_map::WitnessMap;
+use crate::services::db as db_service;
+
+#[derive(Clone)]
+pub struct PostgresStorage {
+    app_state: AppState,
+}
+
+impl PostgresStorage {
+    pub fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+}
+
+impl Storage for PostgresStorage {
+    async fn get_last_synced_block(
+        &self,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        db_service::get_last_synced_block(
+            self.app_state.get_db_connection().await?,
+        )
+        .await
+    }
+
+    async fn get_last_commitment_tree(
+        &self,
+    ) -> anyhow::Result<Option<CommitmentTree>> {
+        db_service::get_last_commitment_tree(
+            self.app_state.get_db_connection().await?,
+        )
+        .await
+    }
+
+    async fn get_commitment_tree_at(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<CommitmentTree>> {
+        db_service::get_commitment_tree_at(
+            self.app_state.get_db_connection().await?,
+            block_height,
+        )
+        .await
+    }
+
+    async fn get_last_witness_map(
+        &self,
+    ) -> anyhow::Result<(Option<BlockHeight>, WitnessMap)> {
+        db_service::get_last_witness_map(
+            self.app_state.get_db_connection().await?,
+        )
+        .await
+    }
+
+    async fn get_block_hash(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<String>> {
+        db_service::get_block_hash(
+            self.app_state.get_db_connection().await?,
+            block_height,
+        )
+        .await
+    }
+
+    async fn get_notes_index(&self) -> anyhow::Result<Vec<NotesIndexInsertDb>> {
+        db_service::get_notes_index(self.app_state.get_db_connection().await?)
+            .await
+    }
+
+    async fn get_missing_block_ranges(
+        &self,
+    ) -> anyhow::Result<Vec<RangeInclusive<BlockHeight>>> {
+        let (committed_heights, last_synced_height) =
+            db_service::get_committed_block_heights(
+                self.app_state.get_db_connection().await?,
+            )
+            .await?;
+
+        Ok(missing_block_ranges(committed_heights, last_synced_height))
+    }
+
+    async fn replay_shielded_txs<F>(
+        &self,
+        after: Option<BlockHeight>,
+        replay_block: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(BlockHeight, Vec<Transaction>) -> anyhow::Result<()>
+            + Send
+            + 'static,
+    {
+        db_service::replay_shielded_txs(
+            self.app_state.get_db_connection().await?,
+            after,
+            replay_block,
+        )
+        .await
+    }
+
+    async fn commit(&self, batch: Arc<CommitBatch>) -> anyhow::Result<()> {
+        let conn = self.app_state.get_db_connection().await?;
+
+        db_service::commit(&conn, batch).await
+    }
+
+    async fn replace_blocks(
+        &self,
+        batch: Arc<CommitBatch>,
+    ) -> anyhow::Result<()> {
+        let conn = self.app_state.get_db_connection().await?;
+
+        db_service::replace_blocks(&conn, batch).await
+    }
+
+    async fn prune_witness_checkpoints(&self, keep: u64) -> anyhow::Result<()> {
+        db_service::prune_witness_checkpoints(
+            self.app_state.get_db_connection().await?,
+            keep,
+        )
+        .await
+    }
+
+    async fn record_failed_block(
+        &self,
+        block_height: BlockHeight,
+        error: String,
+        skipped: bool,
+    ) -> anyhow::Result<()> {
+        db_service::record_failed_block(
+            self.app_state.get_db_connection().await?,
+            FailedBlockDb {
+                block_height: block_height.0 as i32,
+                error,
+                skipped,
+            },
+        )
+        .await
+    }
+
+    async fn get_failed_blocks(&self) -> anyhow::Result<Vec<FailedBlockDb>> {
+        db_service::get_failed_blocks(self.app_state.get_db_connection().await?)
+            .await
+    }
+
+    async fn get_start_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        db_service::get_start_height(self.app_state.get_db_connection().await?)
+            .await
+    }
+
+    async fn set_start_height(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<()> {
+        db_service::set_start_height(
+            self.app_state.get_db_connection().await?,
+            block_height,
+        )
+        .await
+    }
+
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>> {
+        db_service::get_chain_id(self.app_state.get_db_connection().await?)
+            .await
+    }
+
+    async fn set_chain_id(&self, chain_id: String) -> anyhow::Result<()> {
+        db_service::set_chain_id(
+            self.app_state.get_db_connection().await?,
+            chain_id,
+        )
+        .await
+    }
+
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        db_service::get_pruned_height(self.app_state.get_db_connection().await?)
+            .await
+    }
+
+    async fn prune(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        db_service::prune(
+            self.app_state.get_db_connection().await?,
+            block_height,
+        )
+        .await
+    }
+
+    async fn rollback(
+        &self,
+        block_height: Option<BlockHeight>,
+    ) -> anyhow::Result<()> {
+        db_service::rollback(
+            self.app_state.get_db_connection().await?,
+            block_height,
+        )
+        .await
+    }
+}
@@ -0,0 +1,755 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use chrono::DateTime;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_sdk::masp_primitives::merkle_tree::IncrementalWitness;
+use namada_sdk::masp_primitives::sapling::Node;
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::block_hash::BlockHashDb;
+use orm::failed_block::FailedBlockDb;
+use orm::notes_index::NotesIndexInsertDb;
+use orm::tree::TreeDb;
+use rocksdb::{DB, Direction, IteratorMode, Options, WriteBatch};
+use shared::height::BlockHeight;
+
+use super::{Storage, missing_block_ranges};
+use crate::entity::commit_batch::CommitBatch;
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::witness_map::WitnessMap;
+
+/// Single row values, such as the chain state, keyed by their name.
+const META: &str = "meta";
+const COMMITMENT_TREE: &str = "commitment_tree";
+const WITNESS: &str = "witness";
+const NOTES_INDEX: &str = "notes_index";
+const TX: &str = "tx";
+const NULLIFIERS: &str = "nullifiers";
+const COMPACT_OUTPUTS: &str = "compact_outputs";
+const BLOCK_HASH: &str = "block_hash";
+const ANCHORS: &str = "anchors";
+const FAILED_BLOCKS: &str = "failed_blocks";
+
+/// Column families of the database, one per table of the Postgres
+/// schema.
+const COLUMN_FAMILIES: [&str; 10] = [
+    META,
+    COMMITMENT_TREE,
+    WITNESS,
+    NOTES_INDEX,
+    TX,
+    NULLIFIERS,
+    COMPACT_OUTPUTS,
+    BLOCK_HASH,
+    ANCHORS,
+    FAILED_BLOCKS,
+];
+
+const CHAIN_STATE_KEY: &[u8] = b"chain_state";
+const START_HEIGHT_KEY: &[u8] = b"start_height";
+const CHAIN_ID_KEY: &[u8] = b"chain_id";
+const PRUNED_HEIGHT_KEY: &[u8] = b"pruned_height";
+
+type Row = (Box<[u8]>, Box<[u8]>);
+
+/// Encode a key component in big endian, such that keys sort like the
+/// values they start with. Heights and indices are never negative.
+fn key(value: i32) -> [u8; 4] {
+    (value as u32).to_be_bytes()
+}
+
+/// Value of the `index`-th component of a key, e.g. the block height a
+/// key starts with.
+fn key_component(key: &[u8], index: usize) -> i32 {
+    let component = &key[4 * index..4 * (index + 1)];
+    i32::from_be_bytes(component.try_into().unwrap())
+}
+
+fn decode<T: BorshDeserialize>(bytes: &[u8], table: &str) -> anyhow::Result<T> {
+    T::try_from_slice(bytes)
+        .with_context(|| format!("Failed to deserialize {table} from RocksDB"))
+}
+
+/// Storage backend that persists indexed data to an embedded RocksDB
+/// database, for deployments and tests without a Postgres instance.
+///
+/// Each table of the Postgres schema maps to a column family, whose keys
+/// start with the block height of the rows, such that the rows of a
+/// range of blocks can be deleted without scanning the whole table.
+#[derive(Clone)]
+pub struct RocksDbStorage {
+    db: Arc<DB>,
+    /// Held by writers, such that the checks preceding a write see the
+    /// state it is applied to.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl RocksDbStorage {
+    /// Database url prefix selecting the RocksDB backend, followed by the
+    /// path to the database directory.
+    pub const URL_PREFIX: &str = "rocksdb://";
+
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db = DB::open_cf(&options, path, COLUMN_FAMILIES).with_context(
+            || format!("Failed to open RocksDB database at {}", path.display()),
+        )?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            write_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn cf(&self, name: &str) -> &rocksdb::ColumnFamily {
+        self.db
+            .cf_handle(name)
+            .expect("Column families are created when opening the database")
+    }
+
+    /// Rows of column family `cf`, starting from `mode`.
+    fn rows<'a>(
+        &'a self,
+        cf: &'static str,
+        mode: IteratorMode,
+    ) -> impl Iterator<Item = anyhow::Result<Row>> + use<'a> {
+        self.db.iterator_cf(self.cf(cf), mode).map(move |row| {
+            row.with_context(|| format!("Failed to read {cf} from RocksDB"))
+        })
+    }
+
+    /// Row of column family `cf` with the highest key.
+    fn last_row(&self, cf: &'static str) -> anyhow::Result<Option<Row>> {
+        self.rows(cf, IteratorMode::End).next().transpose()
+    }
+
+    /// Rows of column family `cf` whose key starts with `block_height`.
+    fn rows_at_height(
+        &self,
+        cf: &'static str,
+        block_height: i32,
+    ) -> impl Iterator<Item = anyhow::Result<Row>> {
+        let start = key(block_height);
+
+        self.rows(cf, IteratorMode::From(&start, Direction::Forward))
+            .take_while(move |row| {
+                row.as_ref().map_or(true, |(key, _)| {
+                    key_component(key, 0) == block_height
+                })
+            })
+    }
+
+    fn get_meta<T: BorshDeserialize>(
+        &self,
+        meta_key: &[u8],
+    ) -> anyhow::Result<Option<T>> {
+        self.db
+            .get_cf(self.cf(META), meta_key)
+            .context("Failed to read metadata from RocksDB")?
+            .map(|value| decode(&value, META))
+            .transpose()
+    }
+
+    fn get_height(&self, meta_key: &[u8]) -> anyhow::Result<Option<i32>> {
+        self.get_meta(meta_key)
+    }
+
+    fn write(&self, batch: WriteBatch) -> anyhow::Result<()> {
+        self.db
+            .write(batch)
+            .context("Failed to write batch to RocksDB")
+    }
+
+    /// Queue the deletion of the rows of column family `cf` whose key
+    /// starts with a block height in `heights`.
+    fn delete_heights(
+        &self,
+        write: &mut WriteBatch,
+        cf: &'static str,
+        heights: RangeInclusive<i32>,
+    ) {
+        write.delete_range_cf(
+            self.cf(cf),
+            key(*heights.start()),
+            key(heights.end().saturating_add(1)),
+        );
+    }
+
+    /// Queue the insertion of all the rows of `batch`, except for its
+    /// chain state.
+    fn insert_batch(&self, write: &mut WriteBatch, batch: &CommitBatch) {
+        for tree in &batch.commitment_trees {
+            write.put_cf(
+                self.cf(COMMITMENT_TREE),
+                key(tree.block_height),
+                &tree.tree,
+            );
+        }
+
+        for witness in &batch.witnesses {
+            write.put_cf(
+                self.cf(WITNESS),
+                [key(witness.block_height), key(witness.witness_idx)].concat(),
+                &witness.witness_bytes,
+            );
+        }
+
+        for note in &batch.notes_index {
+            let row: NotesIndexRow = (
+                note.block_index,
+                note.masp_tx_index,
+                note.is_fee_unshielding,
+            );
+            write.put_cf(
+                self.cf(NOTES_INDEX),
+                [key(note.block_height), key(note.note_position)].concat(),
+                row.serialize_to_vec(),
+            );
+        }
+
+        // NB: txs are keyed by masp tx index first, the order they are
+        // replayed in
+        for tx in &batch.shielded_txs {
+            write.put_cf(
+                self.cf(TX),
+                [
+                    key(tx.block_height),
+                    key(tx.masp_tx_index),
+                    key(tx.block_index),
+                ]
+                .concat(),
+                &tx.tx_bytes,
+            );
+        }
+
+        for nullifier in &batch.nullifiers {
+            write.put_cf(
+                self.cf(NULLIFIERS),
+                [
+                    &key(nullifier.block_height)[..],
+                    &key(nullifier.block_index),
+                    &key(nullifier.masp_tx_index),
+                    &nullifier.nullifier,
+                ]
+                .concat(),
+                b"",
+            );
+        }
+
+        for output in &batch.compact_outputs {
+            write.put_cf(
+                self.cf(COMPACT_OUTPUTS),
+                [
+                    key(output.block_height),
+                    key(output.block_index),
+                    key(output.masp_tx_index),
+                    key(output.output_index),
+                ]
+                .concat(),
+                (&output.cmu, &output.ephemeral_key, &output.ciphertext)
+                    .serialize_to_vec(),
+            );
+        }
+
+        for block_hash in &batch.block_hashes {
+            let row: BlockHashRow = (
+                block_hash.hash.clone(),
+                block_hash
+                    .timestamp
+                    .map(|timestamp| timestamp.and_utc().timestamp_micros()),
+                block_hash.epoch,
+            );
+            write.put_cf(
+                self.cf(BLOCK_HASH),
+                key(block_hash.block_height),
+                row.serialize_to_vec(),
+            );
+        }
+
+        for anchor in &batch.anchors {
+            write.put_cf(
+                self.cf(ANCHORS),
+                key(anchor.block_height),
+                (&anchor.root, anchor.tree_size).serialize_to_vec(),
+            );
+        }
+    }
+
+    /// Row of the block hash committed at `block_height`.
+    fn get_block_hash_row(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<BlockHashDb>> {
+        let Some(value) = self
+            .db
+            .get_cf(self.cf(BLOCK_HASH), key(block_height))
+            .context("Failed to read block hash from RocksDB")?
+        else {
+            return Ok(None);
+        };
+        let (hash, timestamp, epoch) =
+            decode::<BlockHashRow>(&value, BLOCK_HASH)?;
+
+        Ok(Some(BlockHashDb {
+            block_height,
+            hash,
+            timestamp: timestamp
+                .and_then(DateTime::from_timestamp_micros)
+                .map(|timestamp| timestamp.naive_utc()),
+            epoch,
+        }))
+    }
+}
+
+/// Block index, masp tx index and fee unshielding flag of a note, keyed
+/// by its block height and position. Nullifiers are keyed by their block
+/// height, block index, masp tx index and bytes, with no value.
+type NotesIndexRow = (i32, i32, bool);
+
+/// Hash, timestamp (in microseconds since the unix epoch) and epoch of a
+/// block, keyed by its height.
+type BlockHashRow = (String, Option<i64>, Option<i32>);
+
+impl Storage for RocksDbStorage {
+    async fn get_last_synced_block(
+        &self,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(self.get_height(CHAIN_STATE_KEY)?.map(BlockHeight::from))
+    }
+
+    async fn get_last_commitment_tree(
+        &self,
+    ) -> anyhow::Result<Option<CommitmentTree>> {
+        self.last_row(COMMITMENT_TREE)?
+            .map(|(tree_key, tree)| {
+                CommitmentTree::try_from(TreeDb {
+                    id: 0,
+                    tree: tree.into_vec(),
+                    block_height: key_component(&tree_key, 0),
+                })
+                .context("Failed to deserialize commitment tree from RocksDB")
+            })
+            .transpose()
+    }
+
+    async fn get_commitment_tree_at(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<CommitmentTree>> {
+        let start = key(block_height.0 as i32);
+
+        // NB: seeks to the highest key at or below the start
+        self.rows(
+            COMMITMENT_TREE,
+            IteratorMode::From(&start, Direction::Reverse),
+        )
+        .next()
+        .transpose()?
+        .map(|(tree_key, tree)| {
+            CommitmentTree::try_from(TreeDb {
+                id: 0,
+                tree: tree.into_vec(),
+                block_height: key_component(&tree_key, 0),
+            })
+            .context("Failed to deserialize commitment tree from RocksDB")
+        })
+        .transpose()
+    }
+
+    async fn get_last_witness_map(
+        &self,
+    ) -> anyhow::Result<(Option<BlockHeight>, WitnessMap)> {
+        let Some((last_key, _)) = self.last_row(WITNESS)? else {
+            return Ok((None, WitnessMap::default()));
+        };
+        let block_height = key_component(&last_key, 0);
+
+        let witnesses = self.rows_at_height(WITNESS, block_height).try_fold(
+            HashMap::new(),
+            |mut accum, row| {
+                let (witness_key, witness_bytes) = row?;
+                let witness_node =
+                    IncrementalWitness::<Node>::try_from_slice(&witness_bytes)
+                        .context(
+                            "Failed to deserialize note witness from RocksDB",
+                        )?;
+                let note_index = key_component(&witness_key, 1) as usize;
+                accum.insert(note_index, witness_node);
+                anyhow::Ok(accum)
+            },
+        )?;
+
+        Ok((
+            Some(BlockHeight::from(block_height)),
+            WitnessMap::new(witnesses),
+        ))
+    }
+
+    async fn get_block_hash(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .get_block_hash_row(block_height.0 as i32)?
+            .map(|block_hash| block_hash.hash))
+    }
+
+    async fn get_notes_index(&self) -> anyhow::Result<Vec<NotesIndexInsertDb>> {
+        self.rows(NOTES_INDEX, IteratorMode::Start)
+            .map(|row| {
+                let (note_key, value) = row?;
+                let (block_index, masp_tx_index, is_fee_unshielding) =
+                    decode::<NotesIndexRow>(&value, NOTES_INDEX)?;
+
+                Ok(NotesIndexInsertDb {
+                    block_index,
+                    note_position: key_component(&note_key, 1),
+                    block_height: key_component(&note_key, 0),
+                    masp_tx_index,
+                    is_fee_unshielding,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_missing_block_ranges(
+        &self,
+    ) -> anyhow::Result<Vec<RangeInclusive<BlockHeight>>> {
+        let committed_heights = self
+            .rows(BLOCK_HASH, IteratorMode::Start)
+            .map(|row| row.map(|(hash_key, _)| key_component(&hash_key, 0)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(missing_block_ranges(
+            committed_heights,
+            self.get_height(CHAIN_STATE_KEY)?,
+        ))
+    }
+
+    async fn replay_shielded_txs<F>(
+        &self,
+        after: Option<BlockHeight>,
+        mut replay_block: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(BlockHeight, Vec<Transaction>) -> anyhow::Result<()>
+            + Send
+            + 'static,
+    {
+        let start = key(after.map_or(0, |h| h.0 as i32 + 1));
+        let mut block: Option<(BlockHeight, Vec<Transaction>)> = None;
+
+        for row in self.rows(TX, IteratorMode::From(&start, Direction::Forward))
+        {
+            let (tx_key, tx_bytes) = row?;
+            let block_height = BlockHeight::from(key_component(&tx_key, 0));
+            let masp_tx = Transaction::try_from_slice(&tx_bytes)
+                .context("Failed to deserialize shielded tx from RocksDB")?;
+
+            match block.as_mut() {
+                Some((height, txs)) if *height == block_height => {
+                    txs.push(masp_tx);
+                }
+                _ => {
+                    if let Some((height, txs)) =
+                        block.replace((block_height, vec![masp_tx]))
+                    {
+                        replay_block(height, txs)?;
+                    }
+                }
+            }
+        }
+
+        if let Some((height, txs)) = block {
+            replay_block(height, txs)?;
+        }
+
+        Ok(())
+    }
+
+    async fn commit(&self, batch: Arc<CommitBatch>) -> anyhow::Result<()> {
+        let Some(chain_state) = batch.chain_state_into_db() else {
+            return Ok(());
+        };
+
+        let _guard = self.write_lock.lock().unwrap();
+        let last_synced_height = self.get_height(CHAIN_STATE_KEY)?;
+
+        if last_synced_height.is_some_and(|h| h >= chain_state.block_height) {
+            let committed_hashes = batch
+                .block_hashes
+                .iter()
+                .map(|hash| {
+                    let committed_hash = self
+                        .get_block_hash_row(hash.block_height)?
+                        .map(|block_hash| block_hash.hash);
+                    anyhow::Ok((hash.block_height, committed_hash))
+                })
+                .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+            if batch.is_committed(|height| {
+                committed_hashes.get(&height)?.as_deref()
+            }) {
+                tracing::info!(
+                    block_height = chain_state.block_height,
+                    "Batch already committed to RocksDB"
+                );
+                return Ok(());
+            }
+        }
+
+        batch.check_follows(last_synced_height)?;
+
+        let mut write = WriteBatch::default();
+        self.insert_batch(&mut write, &batch);
+        write.put_cf(
+            self.cf(META),
+            CHAIN_STATE_KEY,
+            chain_state.block_height.serialize_to_vec(),
+        );
+        self.write(write)?;
+
+        tracing::info!(
+            block_height = chain_state.block_height,
+            "Committed new blocks to RocksDB"
+        );
+
+        Ok(())
+    }
+
+    async fn replace_blocks(
+        &self,
+        batch: Arc<CommitBatch>,
+    ) -> anyhow::Result<()> {
+        let (Some(first_height), Some(block_height)) =
+            (batch.first_block_height(), batch.block_height())
+        else {
+            return Ok(());
+        };
+        let range = first_height.0 as i32..=block_height.0 as i32;
+
+        let _guard = self.write_lock.lock().unwrap();
+        let last_synced_height = self.get_height(CHAIN_STATE_KEY)?;
+
+        if last_synced_height.is_none_or(|h| h < *range.end()) {
+            anyhow::bail!(
+                "Cannot replace blocks up to {block_height}, past the last \
+                 synced height {last_synced_height:?}"
+            );
+        }
+
+        let mut write = WriteBatch::default();
+        for cf in [
+            COMMITMENT_TREE,
+            WITNESS,
+            NOTES_INDEX,
+            TX,
+            NULLIFIERS,
+            COMPACT_OUTPUTS,
+            FAILED_BLOCKS,
+        ] {
+            self.delete_heights(&mut write, cf, range.clone());
+        }
+        self.insert_batch(&mut write, &batch);
+        self.write(write)?;
+
+        tracing::info!(
+            %first_height,
+            %block_height,
+            "Replaced blocks in RocksDB"
+        );
+
+        Ok(())
+    }
+
+    async fn prune_witness_checkpoints(&self, keep: u64) -> anyhow::Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+
+        let mut kept = 0;
+        let mut oldest_kept = None;
+
+        for row in self.rows(WITNESS, IteratorMode::End) {
+            let (witness_key, _) = row?;
+            let block_height = key_component(&witness_key, 0);
+
+            if oldest_kept == Some(block_height) {
+                continue;
+            }
+            if kept == keep {
+                break;
+            }
+            kept += 1;
+            oldest_kept = Some(block_height);
+        }
+
+        if let Some(oldest_kept) = oldest_kept.filter(|&h| h > 0) {
+            let mut write = WriteBatch::default();
+            self.delete_heights(&mut write, WITNESS, 0..=oldest_kept - 1);
+            self.write(write)?;
+        }
+
+        Ok(())
+    }
+
+    async fn record_failed_block(
+        &self,
+        block_height: BlockHeight,
+        error: String,
+        skipped: bool,
+    ) -> anyhow::Result<()> {
+        self.db
+            .put_cf(
+                self.cf(FAILED_BLOCKS),
+                key(block_height.0 as i32),
+                (error, skipped).serialize_to_vec(),
+            )
+            .context("Failed to record failed block in RocksDB")
+    }
+
+    async fn get_failed_blocks(&self) -> anyhow::Result<Vec<FailedBlockDb>> {
+        self.rows(FAILED_BLOCKS, IteratorMode::Start)
+            .map(|row| {
+                let (block_key, value) = row?;
+                let (error, skipped) =
+                    decode::<(String, bool)>(&value, FAILED_BLOCKS)?;
+
+                Ok(FailedBlockDb {
+                    block_height: key_component(&block_key, 0),
+                    error,
+                    skipped,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_start_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(self.get_height(START_HEIGHT_KEY)?.map(BlockHeight::from))
+    }
+
+    async fn set_start_height(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<()> {
+        self.db
+            .put_cf(
+                self.cf(META),
+                START_HEIGHT_KEY,
+                (block_height.0 as i32).serialize_to_vec(),
+            )
+            .context("Failed to record start height in RocksDB")
+    }
+
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>> {
+        self.get_meta(CHAIN_ID_KEY)
+    }
+
+    async fn set_chain_id(&self, chain_id: String) -> anyhow::Result<()> {
+        self.db
+            .put_cf(self.cf(META), CHAIN_ID_KEY, chain_id.serialize_to_vec())
+            .context("Failed to record chain id in RocksDB")
+    }
+
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(self.get_height(PRUNED_HEIGHT_KEY)?.map(BlockHeight::from))
+    }
+
+    async fn prune(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        let _guard = self.write_lock.lock().unwrap();
+
+        let last_checkpoint = self
+            .last_row(WITNESS)?
+            .map(|(witness_key, _)| key_component(&witness_key, 0));
+        let pruned = self.get_height(PRUNED_HEIGHT_KEY)?;
+
+        let Some(cutoff) = last_checkpoint
+            .map(|h| h.min(block_height.0 as i32))
+            .filter(|&h| pruned.is_none_or(|p| h > p))
+        else {
+            return Ok(pruned.map(BlockHeight::from));
+        };
+
+        let mut write = WriteBatch::default();
+        for cf in [NOTES_INDEX, TX, NULLIFIERS, COMPACT_OUTPUTS] {
+            self.delete_heights(&mut write, cf, 0..=cutoff);
+        }
+        write.put_cf(
+            self.cf(META),
+            PRUNED_HEIGHT_KEY,
+            cutoff.serialize_to_vec(),
+        );
+        self.write(write)?;
+
+        Ok(Some(BlockHeight::from(cutoff)))
+    }
+
+    async fn rollback(
+        &self,
+        block_height: Option<BlockHeight>,
+    ) -> anyhow::Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let height = block_height.map_or(-1, |h| h.0 as i32);
+
+        let pruned =
+            self.get_height(PRUNED_HEIGHT_KEY)?.filter(|_| height >= 0);
+
+        if let Some(pruned) = pruned {
+            let start = key(pruned);
+            let checkpoint = self
+                .rows(WITNESS, IteratorMode::From(&start, Direction::Forward))
+                .next()
+                .transpose()?
+                .map(|(witness_key, _)| key_component(&witness_key, 0));
+
+            if checkpoint.is_none_or(|h| h > height) {
+                anyhow::bail!(
+                    "Cannot roll back to block height {height}, since the \
+                     shielded txs up to {pruned} were pruned"
+                );
+            }
+        }
+
+        let mut write = WriteBatch::default();
+        for cf in [
+            COMMITMENT_TREE,
+            WITNESS,
+            NOTES_INDEX,
+            TX,
+            NULLIFIERS,
+            COMPACT_OUTPUTS,
+            BLOCK_HASH,
+            ANCHORS,
+            FAILED_BLOCKS,
+        ] {
+            self.delete_heights(&mut write, cf, height + 1..=i32::MAX);
+        }
+
+        match block_height {
+            Some(block_height) => write.put_cf(
+                self.cf(META),
+                CHAIN_STATE_KEY,
+                (block_height.0 as i32).serialize_to_vec(),
+            ),
+            None => {
+                write.delete_cf(self.cf(META), CHAIN_STATE_KEY);
+                write.delete_cf(self.cf(META), PRUNED_HEIGHT_KEY);
+            }
+        }
+        self.write(write)?;
+
+        tracing::info!(?block_height, "Rolled back blocks in RocksDB");
+
+        Ok(())
+    }
+}
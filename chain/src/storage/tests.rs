@@ -0,0 +1,356 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use shared::height::BlockHeight;
+
+use super::Storage;
+use super::memory::InMemoryStorage;
+use crate::entity::commit_batch::{CommitBatch, NonContiguousCommit};
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::witness_map::WitnessMap;
+use crate::tests::{block_hash, crawl_block, note_commitment};
+
+/// Chain whose blocks each hold a single note, crawled on top of the
+/// same commitment tree and witness map.
+#[derive(Default)]
+struct Chain {
+    commitment_tree: CommitmentTree,
+    witness_map: WitnessMap,
+}
+
+impl Chain {
+    /// Crawl the blocks at `heights` into a batch, checkpointing the
+    /// witness map at its last block.
+    fn crawl(&self, heights: RangeInclusive<u64>) -> CommitBatch {
+        let mut batch = CommitBatch::default();
+
+        for block_height in heights.map(BlockHeight) {
+            crawl_block(
+                &mut batch,
+                &self.commitment_tree,
+                &self.witness_map,
+                block_height,
+                &[note_commitment(block_height.0 as u8)],
+            );
+        }
+        batch.checkpoint_witnesses(&self.witness_map);
+
+        batch
+    }
+}
+
+/// Give the blocks of `batch` different hashes, as if they were forked.
+fn fork(mut batch: CommitBatch) -> CommitBatch {
+    for block_hash in &mut batch.block_hashes {
+        block_hash.hash = format!("{:064x}", block_hash.block_height + 1000);
+    }
+    batch
+}
+
+async fn note_positions<S: Storage>(storage: &S) -> Vec<(i32, i32)> {
+    storage
+        .get_notes_index()
+        .await
+        .unwrap()
+        .iter()
+        .map(|note| (note.block_height, note.note_position))
+        .collect()
+}
+
+async fn commit_and_read<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    storage.commit(Arc::new(chain.crawl(1..=3))).await.unwrap();
+
+    assert_eq!(
+        storage.get_last_synced_block().await.unwrap(),
+        Some(BlockHeight(3))
+    );
+    assert_eq!(
+        storage.get_block_hash(BlockHeight(2)).await.unwrap(),
+        Some(block_hash(BlockHeight(2)).to_string())
+    );
+    assert_eq!(storage.get_block_hash(BlockHeight(4)).await.unwrap(), None);
+    assert_eq!(note_positions(&storage).await, [(1, 0), (2, 1), (3, 2)]);
+    assert!(storage.get_missing_block_ranges().await.unwrap().is_empty());
+
+    let tree = storage.get_last_commitment_tree().await.unwrap().unwrap();
+    assert_eq!(tree.root(), chain.commitment_tree.root());
+    let tree = storage
+        .get_commitment_tree_at(BlockHeight(2))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tree.size(), 2);
+
+    let (checkpoint_height, witness_map) =
+        storage.get_last_witness_map().await.unwrap();
+    assert_eq!(checkpoint_height, Some(BlockHeight(3)));
+    assert_eq!(witness_map.size(), 3);
+}
+
+async fn recommit_is_idempotent<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    let batch = Arc::new(chain.crawl(1..=3));
+
+    storage.commit(batch.clone()).await.unwrap();
+    storage.commit(batch).await.unwrap();
+
+    assert_eq!(
+        storage.get_last_synced_block().await.unwrap(),
+        Some(BlockHeight(3))
+    );
+    assert_eq!(note_positions(&storage).await, [(1, 0), (2, 1), (3, 2)]);
+}
+
+async fn non_contiguous_commit_fails<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    storage.commit(Arc::new(chain.crawl(1..=2))).await.unwrap();
+
+    let non_contiguous = |err: anyhow::Error| {
+        let err = err.downcast::<NonContiguousCommit>().unwrap();
+        (err.expected, err.found)
+    };
+
+    // NB: leaves a gap at height 3
+    let err = storage
+        .commit(Arc::new(Chain::default().crawl(4..=4)))
+        .await
+        .unwrap_err();
+    assert_eq!(non_contiguous(err), (3, 4));
+
+    // NB: overwrites height 2 with a different block
+    let err = storage
+        .commit(Arc::new(fork(Chain::default().crawl(2..=3))))
+        .await
+        .unwrap_err();
+    assert_eq!(non_contiguous(err), (3, 2));
+
+    assert_eq!(
+        storage.get_last_synced_block().await.unwrap(),
+        Some(BlockHeight(2))
+    );
+}
+
+async fn rollback_deletes_later_blocks<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    storage.commit(Arc::new(chain.crawl(1..=2))).await.unwrap();
+    storage.commit(Arc::new(chain.crawl(3..=4))).await.unwrap();
+    storage
+        .record_failed_block(BlockHeight(1), "failed".to_string(), false)
+        .await
+        .unwrap();
+    storage
+        .record_failed_block(BlockHeight(4), "failed".to_string(), true)
+        .await
+        .unwrap();
+    storage.set_start_height(BlockHeight(0)).await.unwrap();
+    storage.set_chain_id("chain".to_string()).await.unwrap();
+
+    storage.rollback(Some(BlockHeight(2))).await.unwrap();
+
+    assert_eq!(
+        storage.get_last_synced_block().await.unwrap(),
+        Some(BlockHeight(2))
+    );
+    assert_eq!(storage.get_block_hash(BlockHeight(3)).await.unwrap(), None);
+    assert_eq!(note_positions(&storage).await, [(1, 0), (2, 1)]);
+    let tree = storage.get_last_commitment_tree().await.unwrap().unwrap();
+    assert_eq!(tree.size(), 2);
+    let (checkpoint_height, witness_map) =
+        storage.get_last_witness_map().await.unwrap();
+    assert_eq!(checkpoint_height, Some(BlockHeight(2)));
+    assert_eq!(witness_map.size(), 2);
+    let failed_blocks = storage.get_failed_blocks().await.unwrap();
+    assert_eq!(
+        failed_blocks
+            .iter()
+            .map(|block| block.block_height)
+            .collect::<Vec<_>>(),
+        [1]
+    );
+
+    // NB: the rolled back blocks can be committed again
+    let chain = Chain::default();
+    chain.crawl(1..=2);
+    storage.commit(Arc::new(chain.crawl(3..=3))).await.unwrap();
+
+    storage.rollback(None).await.unwrap();
+
+    assert_eq!(storage.get_last_synced_block().await.unwrap(), None);
+    assert!(storage.get_notes_index().await.unwrap().is_empty());
+    assert!(storage.get_last_commitment_tree().await.unwrap().is_none());
+    assert!(storage.get_failed_blocks().await.unwrap().is_empty());
+    assert_eq!(
+        storage.get_start_height().await.unwrap(),
+        Some(BlockHeight(0))
+    );
+    assert_eq!(
+        storage.get_chain_id().await.unwrap().as_deref(),
+        Some("chain")
+    );
+}
+
+async fn replace_blocks_keeps_last_synced_height<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    storage.commit(Arc::new(chain.crawl(1..=3))).await.unwrap();
+    storage
+        .record_failed_block(BlockHeight(2), "failed".to_string(), true)
+        .await
+        .unwrap();
+
+    let chain = Chain::default();
+    chain.crawl(1..=1);
+    storage
+        .replace_blocks(Arc::new(fork(chain.crawl(2..=3))))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        storage.get_last_synced_block().await.unwrap(),
+        Some(BlockHeight(3))
+    );
+    assert_eq!(
+        storage.get_block_hash(BlockHeight(2)).await.unwrap(),
+        Some(format!("{:064x}", 1002))
+    );
+    assert_eq!(note_positions(&storage).await, [(1, 0), (2, 1), (3, 2)]);
+    assert!(storage.get_failed_blocks().await.unwrap().is_empty());
+
+    // NB: blocks past the last synced height must be committed instead
+    storage
+        .replace_blocks(Arc::new(chain.crawl(4..=4)))
+        .await
+        .unwrap_err();
+}
+
+async fn rollback_stops_at_pruned_blocks<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    storage.commit(Arc::new(chain.crawl(1..=2))).await.unwrap();
+    storage.commit(Arc::new(chain.crawl(3..=4))).await.unwrap();
+    storage.commit(Arc::new(chain.crawl(5..=5))).await.unwrap();
+
+    assert_eq!(
+        storage.prune(BlockHeight(3)).await.unwrap(),
+        Some(BlockHeight(3))
+    );
+    assert_eq!(
+        storage.get_pruned_height().await.unwrap(),
+        Some(BlockHeight(3))
+    );
+    assert_eq!(note_positions(&storage).await, [(4, 3), (5, 4)]);
+
+    // NB: pruning never goes past the last witness map checkpoint, nor
+    // back below the pruned height
+    assert_eq!(
+        storage.prune(BlockHeight(10)).await.unwrap(),
+        Some(BlockHeight(5))
+    );
+    assert_eq!(
+        storage.prune(BlockHeight(1)).await.unwrap(),
+        Some(BlockHeight(5))
+    );
+
+    // NB: no checkpoint at or after the pruned height precedes height 4,
+    // so its state could not be rebuilt
+    storage.rollback(Some(BlockHeight(4))).await.unwrap_err();
+    assert_eq!(
+        storage.get_last_synced_block().await.unwrap(),
+        Some(BlockHeight(5))
+    );
+
+    storage.rollback(Some(BlockHeight(5))).await.unwrap();
+    storage.rollback(None).await.unwrap();
+    assert_eq!(storage.get_pruned_height().await.unwrap(), None);
+}
+
+async fn prune_witness_checkpoints_keeps_the_last_ones<S: Storage>(storage: S) {
+    let chain = Chain::default();
+    storage.commit(Arc::new(chain.crawl(1..=2))).await.unwrap();
+    storage.commit(Arc::new(chain.crawl(3..=4))).await.unwrap();
+
+    storage.prune_witness_checkpoints(1).await.unwrap();
+
+    let (checkpoint_height, witness_map) =
+        storage.get_last_witness_map().await.unwrap();
+    assert_eq!(checkpoint_height, Some(BlockHeight(4)));
+    assert_eq!(witness_map.size(), 4);
+
+    // NB: the checkpoint at height 2 is gone, so the state at
+    // height 3 can no longer be rebuilt once pruned
+    storage.prune(BlockHeight(4)).await.unwrap();
+    storage.rollback(Some(BlockHeight(3))).await.unwrap_err();
+}
+
+async fn failed_blocks_are_sorted_and_replaced<S: Storage>(storage: S) {
+    for (block_height, error, skipped) in
+        [(3, "first", false), (1, "second", true), (3, "third", true)]
+    {
+        storage
+            .record_failed_block(
+                BlockHeight(block_height),
+                error.to_string(),
+                skipped,
+            )
+            .await
+            .unwrap();
+    }
+
+    let failed_blocks = storage.get_failed_blocks().await.unwrap();
+    assert_eq!(
+        failed_blocks
+            .iter()
+            .map(|block| {
+                (block.block_height, block.error.as_str(), block.skipped)
+            })
+            .collect::<Vec<_>>(),
+        [(1, "second", true), (3, "third", true)]
+    );
+}
+
+#[cfg(feature = "rocksdb")]
+fn open_rocksdb(name: &str) -> super::rocksdb::RocksDbStorage {
+    let path = std::env::temp_dir()
+        .join(format!("masp-indexer-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+
+    super::rocksdb::RocksDbStorage::open(path).unwrap()
+}
+
+/// Run each of the storage tests against every backend.
+macro_rules! storage_tests {
+    ($($test:ident),* $(,)?) => {
+        mod memory {
+            use super::*;
+
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(InMemoryStorage::default()).await;
+                }
+            )*
+        }
+
+        #[cfg(feature = "rocksdb")]
+        mod rocksdb {
+            use super::*;
+
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(open_rocksdb(stringify!($test))).await;
+                }
+            )*
+        }
+    };
+}
+
+storage_tests!(
+    commit_and_read,
+    recommit_is_idempotent,
+    non_contiguous_commit_fails,
+    rollback_deletes_later_blocks,
+    replace_blocks_keeps_last_synced_height,
+    rollback_stops_at_pruned_blocks,
+    prune_witness_checkpoints_keeps_the_last_ones,
+    failed_blocks_are_sorted_and_replaced,
+);
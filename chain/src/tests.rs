@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use namada_sdk::borsh::BorshSerializeExt;
+use namada_sdk::masp_primitives::sapling::Node;
+use shared::height::BlockHeight;
+use shared::id::Id;
+use shared::indexed_tx::IndexedTx;
+use shared::tx_index::{MaspTxIndex, TxIndex};
+use webserver::appstate::AppState;
+use webserver::repository::block::BlockRepositoryTrait;
+use webserver::repository::memory::block::MemoryBlockRepository;
+use webserver::repository::memory::namada_state::MemoryNamadaStateRepository;
+use webserver::repository::memory::notes_index::MemoryNotesIndexRepository;
+use webserver::repository::memory::tree::MemoryTreeRepository;
+use webserver::repository::memory::witness_map::MemoryWitnessMapRepository;
+use webserver::repository::namada_state::NamadaStateRepositoryTrait;
+use webserver::repository::notes_index::NotesIndexRepositoryTrait;
+use webserver::repository::tree::TreeRepositoryTrait;
+use webserver::repository::witness_map::WitnessMapRepositoryTrait;
+
+use crate::entity::chain_state::ChainState;
+use crate::entity::commit_batch::CommitBatch;
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::tx_notes_index::TxNoteMap;
+use crate::entity::witness_map::WitnessMap;
+use crate::services::masp as masp_service;
+use crate::storage::Storage;
+use crate::storage::memory::InMemoryStorage;
+
+const CHAIN_TIP: BlockHeight = BlockHeight(3);
+
+/// Made up note commitment, standing in for a shielded output.
+pub(crate) fn note_commitment(seed: u8) -> Node {
+    let mut repr = [0; 32];
+    repr[0] = seed;
+    Node::new(repr)
+}
+
+pub(crate) fn block_hash(block_height: BlockHeight) -> Id {
+    Id::Hash(format!("{:064x}", block_height.0))
+}
+
+/// Process a block holding a single masp tx with the notes `notes` (or
+/// no masp tx at all, if there are none) the way the crawler does, and
+/// add it to `batch`.
+pub(crate) fn crawl_block(
+    batch: &mut CommitBatch,
+    commitment_tree: &CommitmentTree,
+    witness_map: &WitnessMap,
+    block_height: BlockHeight,
+    notes: &[Node],
+) {
+    let mut tx_notes_index = TxNoteMap::default();
+
+    if !notes.is_empty() {
+        let indexed_tx = IndexedTx {
+            block_height,
+            masp_tx_index: MaspTxIndex(0),
+            block_index: TxIndex(0),
+            batch_index: 0,
+        };
+        tx_notes_index.insert(indexed_tx, commitment_tree.size(), false);
+    }
+
+    for note in notes.iter().cloned() {
+        assert!(commitment_tree.append(note));
+    }
+    masp_service::update_witness_map(commitment_tree, witness_map, notes)
+        .unwrap();
+
+    batch.push(
+        ChainState::new(block_height, block_hash(block_height), CHAIN_TIP),
+        commitment_tree,
+        witness_map,
+        tx_notes_index,
+        vec![],
+        false,
+    );
+}
+
+#[tokio::test]
+async fn crawled_blocks_are_served_by_the_webserver() {
+    let storage = InMemoryStorage::default();
+    let commitment_tree = CommitmentTree::default();
+    let witness_map = WitnessMap::default();
+
+    let blocks = [
+        vec![note_commitment(1), note_commitment(2)],
+        vec![],
+        vec![note_commitment(3)],
+    ];
+    let mut batch = CommitBatch::default();
+    for (block_height, notes) in (1..).map(BlockHeight).zip(&blocks) {
+        crawl_block(
+            &mut batch,
+            &commitment_tree,
+            &witness_map,
+            block_height,
+            notes,
+        );
+    }
+    batch.checkpoint_witnesses(&witness_map);
+    storage.commit(Arc::new(batch)).await.unwrap();
+
+    let app_state = AppState::in_memory(storage.db().clone(), 1 << 20);
+
+    let latest_height = MemoryNamadaStateRepository::new(app_state.clone())
+        .get_latest_height()
+        .await
+        .unwrap();
+    assert_eq!(latest_height, Some(CHAIN_TIP));
+
+    let block = MemoryBlockRepository::new(app_state.clone())
+        .get_at_height(2)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(block.hash, block_hash(BlockHeight(2)).to_string());
+
+    // NB: the second block has no notes, so the tree of the first block
+    // is served for it
+    let tree_repo = MemoryTreeRepository::new(app_state.clone());
+    let tree = tree_repo.get_at_height(2).await.unwrap().unwrap();
+    assert_eq!(tree.block_height, 1);
+    let tree = tree_repo.get_at_height(3).await.unwrap().unwrap();
+    assert_eq!(tree.block_height, 3);
+    assert_eq!(tree.tree, commitment_tree.get_tree().serialize_to_vec());
+
+    let notes_index = MemoryNotesIndexRepository::new(app_state.clone())
+        .get_notes_index(None, 3, None, None, 0)
+        .await
+        .unwrap();
+    let notes_index = notes_index
+        .iter()
+        .map(|note| (note.block_height, note.note_position))
+        .collect::<Vec<_>>();
+    assert_eq!(notes_index, [(1, 0), (3, 2)]);
+
+    let (last_synced_height, witness, tree, anchor_height) =
+        MemoryWitnessMapRepository::new(app_state)
+            .get_note_witness(0, None)
+            .await
+            .unwrap();
+    assert_eq!(last_synced_height, Some(3));
+    assert_eq!(anchor_height, Some(3));
+    assert_eq!(tree.unwrap().block_height, 3);
+    assert_eq!(
+        witness.unwrap().witness_bytes,
+        witness_map.get_witnesses()[&0].serialize_to_vec()
+    );
+}
@@ -0,0 +1,64 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Request error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Indexer responded with {status}: {message}")]
+    Api {
+        status: StatusCode,
+        /// Machine readable code of the error (e.g. `HEIGHT_NOT_SYNCED`),
+        /// unless the response did not come from the webserver
+        code: Option<String>,
+        message: String,
+    },
+}
+
+/// Body of the error responses of the webserver.
+#[derive(Deserialize)]
+pub(crate) struct ApiErrorBody {
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
+
+impl ClientError {
+    /// Check if the requested blocks have not been synced yet.
+    pub fn is_not_synced(&self) -> bool {
+        // NB: fall back to the status if the response did not come from
+        // the webserver
+        match self.code() {
+            Some(code) => {
+                matches!(code, "HEIGHT_NOT_SYNCED" | "NOTHING_SYNCED")
+            }
+            None => self.status() == Some(StatusCode::NOT_FOUND),
+        }
+    }
+
+    /// Check if the requested blocks were pruned by the indexer.
+    pub fn is_pruned(&self) -> bool {
+        self.status() == Some(StatusCode::GONE)
+    }
+
+    /// Check if the request was rejected by the rate limiter.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    /// Machine readable code of the error returned by the webserver,
+    /// if any.
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => code.as_deref(),
+            ClientError::Http(_) => None,
+        }
+    }
+
+    fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(err) => err.status(),
+        }
+    }
+}
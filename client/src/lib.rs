@@ -0,0 +1,181 @@
+pub mod error;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+pub use shared::response::{
+    Note, NotesIndexResponse, SyncResponse, TreeResponse, Tx, TxResponse,
+    TxSlot, Witness, WitnessMapResponse,
+};
+
+use crate::error::{ApiErrorBody, ClientError};
+
+/// Header carrying the API key, if the webserver requires one.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Path under which the webserver serves its API.
+const API_PREFIX: &str = "/api/v1";
+
+/// Query of a page of the notes map.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NotesMapQuery {
+    /// Upper bound (inclusive) on the block height of the returned notes
+    pub height: u64,
+    /// Lower bound (inclusive) on the block height of the returned notes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_height: Option<u64>,
+    /// Only return notes of fee unshieldings if `true`, or only regular
+    /// notes if `false`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_fee_unshielding: Option<bool>,
+    /// Maximum number of notes to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Number of notes to skip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+/// Query of a page of the masp txs of a range of blocks.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TxsQuery {
+    /// Block height of the first block of the range
+    pub height: u64,
+    /// Number of blocks after `height` included in the range, at most 30
+    pub height_offset: u64,
+    /// Maximum number of batches of txs to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Number of batches of txs to skip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+/// Client of the HTTP API of the masp indexer.
+#[derive(Clone)]
+pub struct IndexerClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl IndexerClient {
+    /// Create a client of the webserver at `base_url` (e.g.
+    /// `http://localhost:5000`).
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+
+        Self {
+            http: reqwest::Client::new(),
+            base_url,
+            api_key: None,
+        }
+    }
+
+    /// Authenticate requests with `api_key`.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Send requests through `http`, e.g. to configure timeouts.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Commitment tree at block `height`.
+    pub async fn get_commitment_tree(
+        &self,
+        height: u64,
+    ) -> Result<TreeResponse, ClientError> {
+        self.get("/commitment-tree", &[("height", height)]).await
+    }
+
+    /// Witness map of the commitment tree returned by
+    /// [`Self::get_commitment_tree`] for block `height`, i.e. as of the
+    /// closest height at or below `height` where the tree changed. The
+    /// `block_height` of the response is that of the tree, so the two
+    /// always agree on the anchor.
+    pub async fn get_witnesses(
+        &self,
+        height: u64,
+    ) -> Result<WitnessMapResponse, ClientError> {
+        self.get("/witness-map", &[("height", height)]).await
+    }
+
+    /// Witness map as of exactly block `height`, whose `block_height` is
+    /// always `height`. Fails if `height` has not been synced yet, unlike
+    /// [`Self::get_witnesses`] which falls back to the last synced
+    /// height.
+    pub async fn get_witnesses_at(
+        &self,
+        height: u64,
+    ) -> Result<WitnessMapResponse, ClientError> {
+        self.get("/witness-map/historical", &[("height", height)])
+            .await
+    }
+
+    /// Page of the notes map matching `query`.
+    pub async fn get_notes_map(
+        &self,
+        query: &NotesMapQuery,
+    ) -> Result<NotesIndexResponse, ClientError> {
+        self.get("/notes-index", query).await
+    }
+
+    /// Page of the masp txs matching `query`.
+    pub async fn get_txs(
+        &self,
+        query: &TxsQuery,
+    ) -> Result<TxResponse, ClientError> {
+        self.get("/tx", query).await
+    }
+
+    /// Everything needed to sync the blocks between `from` and `to`
+    /// (inclusive), read from a consistent view of the database. At most
+    /// 1000 blocks can be synced at once.
+    pub async fn sync_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<SyncResponse, ClientError> {
+        self.get("/sync", &[("from", from), ("to", to)]).await
+    }
+
+    async fn get<Q, T>(&self, path: &str, query: &Q) -> Result<T, ClientError>
+    where
+        Q: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let mut request = self
+            .http
+            .get(format!("{}{API_PREFIX}{path}", self.base_url))
+            .query(query);
+
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+
+        // NB: fall back to the reason of the status code if the body is
+        // not an API error, e.g. if it was returned by a proxy
+        let body = response.json::<ApiErrorBody>().await.ok();
+        let code = body.as_ref().and_then(|body| body.code.clone());
+        let message = body
+            .and_then(|body| body.message)
+            .or_else(|| status.canonical_reason().map(str::to_string))
+            .unwrap_or_default();
+
+        Err(ClientError::Api {
+            status,
+            code,
+            message,
+        })
+    }
+}
@@ -0,0 +1,4 @@
+CREATE TABLE block_hash (
+  block_height INT PRIMARY KEY,
+  hash VARCHAR NOT NULL
+);
@@ -0,0 +1,3 @@
+ALTER TABLE chain_state
+  DROP COLUMN chain_tip,
+  DROP COLUMN committed_at;
@@ -0,0 +1,3 @@
+ALTER TABLE chain_state
+  ADD COLUMN chain_tip INT NOT NULL DEFAULT 0,
+  ADD COLUMN committed_at TIMESTAMP NOT NULL DEFAULT now();
@@ -0,0 +1,2 @@
+ALTER TABLE notes_index
+  DROP COLUMN is_fee_unshielding;
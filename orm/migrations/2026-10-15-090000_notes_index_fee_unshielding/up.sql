@@ -0,0 +1,4 @@
+-- NB: notes indexed before this migration are assumed
+-- not to stem from fee unshieldings
+ALTER TABLE notes_index
+  ADD COLUMN is_fee_unshielding BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,11 @@
+ALTER TABLE commitment_tree
+  DROP CONSTRAINT commitment_tree_unique_height;
+
+ALTER TABLE witness
+  DROP CONSTRAINT witness_unique_note_at_height;
+
+ALTER TABLE tx
+  DROP CONSTRAINT tx_unique_masp_tx;
+
+ALTER TABLE notes_index
+  DROP CONSTRAINT notes_index_unique_note;
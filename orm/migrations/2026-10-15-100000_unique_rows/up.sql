@@ -0,0 +1,35 @@
+ALTER TABLE notes_index
+  ADD CONSTRAINT notes_index_unique_note
+  UNIQUE (block_height, block_index, masp_tx_index, note_position);
+
+-- keep the first row of each tx, witness and commitment tree inserted
+-- more than once, e.g. by replaying a block
+DELETE FROM tx a
+  USING tx b
+  WHERE a.block_height = b.block_height
+    AND a.block_index = b.block_index
+    AND a.masp_tx_index = b.masp_tx_index
+    AND a.id > b.id;
+
+ALTER TABLE tx
+  ADD CONSTRAINT tx_unique_masp_tx
+  UNIQUE (block_height, block_index, masp_tx_index);
+
+DELETE FROM witness a
+  USING witness b
+  WHERE a.witness_idx = b.witness_idx
+    AND a.block_height = b.block_height
+    AND a.id > b.id;
+
+ALTER TABLE witness
+  ADD CONSTRAINT witness_unique_note_at_height
+  UNIQUE (witness_idx, block_height);
+
+DELETE FROM commitment_tree a
+  USING commitment_tree b
+  WHERE a.block_height = b.block_height
+    AND a.id > b.id;
+
+ALTER TABLE commitment_tree
+  ADD CONSTRAINT commitment_tree_unique_height
+  UNIQUE (block_height);
@@ -0,0 +1,9 @@
+CREATE TABLE nullifiers (
+  id SERIAL PRIMARY KEY,
+  nullifier BYTEA NOT NULL UNIQUE,
+  block_height INT NOT NULL,
+  block_index INT NOT NULL,
+  masp_tx_index INT NOT NULL
+);
+
+CREATE INDEX nullifiers_block_height ON nullifiers (block_height);
@@ -0,0 +1,6 @@
+CREATE TABLE failed_blocks (
+  block_height INT PRIMARY KEY,
+  error VARCHAR NOT NULL,
+  skipped BOOLEAN NOT NULL,
+  failed_at TIMESTAMP NOT NULL DEFAULT now()
+);
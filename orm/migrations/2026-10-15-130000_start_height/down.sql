@@ -0,0 +1 @@
+DROP TABLE start_height;
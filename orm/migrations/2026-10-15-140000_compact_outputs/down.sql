@@ -0,0 +1 @@
+DROP TABLE compact_outputs;
@@ -0,0 +1,11 @@
+CREATE TABLE compact_outputs (
+  id SERIAL PRIMARY KEY,
+  block_height INT NOT NULL,
+  block_index INT NOT NULL,
+  masp_tx_index INT NOT NULL,
+  output_index INT NOT NULL,
+  cmu BYTEA NOT NULL,
+  ephemeral_key BYTEA NOT NULL,
+  ciphertext BYTEA NOT NULL,
+  UNIQUE (block_height, block_index, masp_tx_index, output_index)
+);
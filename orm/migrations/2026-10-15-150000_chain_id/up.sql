@@ -0,0 +1,4 @@
+CREATE TABLE chain_id (
+  id INT PRIMARY KEY DEFAULT 0 CHECK (id = 0),
+  chain_id VARCHAR NOT NULL
+);
@@ -0,0 +1,4 @@
+CREATE TABLE pruned_height (
+  id INT PRIMARY KEY DEFAULT 0 CHECK (id = 0),
+  block_height INT NOT NULL
+);
@@ -0,0 +1,5 @@
+DROP INDEX block_hash_timestamp_asc;
+
+ALTER TABLE block_hash
+  DROP COLUMN timestamp,
+  DROP COLUMN epoch;
@@ -0,0 +1,5 @@
+ALTER TABLE block_hash
+  ADD COLUMN timestamp TIMESTAMP,
+  ADD COLUMN epoch INT;
+
+CREATE INDEX block_hash_timestamp_asc ON block_hash (timestamp ASC);
@@ -0,0 +1,5 @@
+CREATE TABLE anchors (
+  block_height INT PRIMARY KEY,
+  root BYTEA NOT NULL,
+  tree_size INT NOT NULL
+);
@@ -0,0 +1,15 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::anchors;
+
+#[derive(Serialize, Queryable, Selectable, Insertable, Clone)]
+#[diesel(table_name = anchors)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AnchorDb {
+    pub block_height: i32,
+    /// Borsh encoded root of the commitment tree after the block.
+    pub root: Vec<u8>,
+    /// Number of notes in the commitment tree after the block.
+    pub tree_size: i32,
+}
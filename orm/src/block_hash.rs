@@ -0,0 +1,17 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::block_hash;
+
+#[derive(Serialize, Queryable, Selectable, Insertable, Clone)]
+#[diesel(table_name = block_hash)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BlockHashDb {
+    pub block_height: i32,
+    pub hash: String,
+    /// Time the block was committed at, if known.
+    pub timestamp: Option<NaiveDateTime>,
+    /// Epoch of the block, if known.
+    pub epoch: Option<i32>,
+}
@@ -0,0 +1,11 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use crate::schema::chain_id;
+
+#[derive(Queryable, Selectable, Insertable, Clone)]
+#[diesel(table_name = chain_id)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ChainIdDb {
+    pub id: i32,
+    pub chain_id: String,
+}
@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use diesel::{Insertable, Queryable, Selectable};
 use serde::Serialize;
 
@@ -8,6 +9,8 @@ use crate::schema::chain_state;
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct ChainStateDb {
     pub block_height: i32,
+    pub chain_tip: i32,
+    pub committed_at: NaiveDateTime,
 }
 
 #[derive(Serialize, Insertable, Clone)]
@@ -16,4 +19,5 @@ pub struct ChainStateDb {
 pub struct ChainStateteInsertDb {
     pub id: i32,
     pub block_height: i32,
+    pub chain_tip: i32,
 }
@@ -0,0 +1,31 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::compact_outputs;
+
+#[derive(Serialize, Queryable, Selectable, Clone)]
+#[diesel(table_name = compact_outputs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CompactOutputDb {
+    pub id: i32,
+    pub block_height: i32,
+    pub block_index: i32,
+    pub masp_tx_index: i32,
+    pub output_index: i32,
+    pub cmu: Vec<u8>,
+    pub ephemeral_key: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Insertable, Clone)]
+#[diesel(table_name = compact_outputs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CompactOutputInsertDb {
+    pub block_height: i32,
+    pub block_index: i32,
+    pub masp_tx_index: i32,
+    pub output_index: i32,
+    pub cmu: Vec<u8>,
+    pub ephemeral_key: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
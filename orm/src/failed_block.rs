@@ -0,0 +1,13 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::failed_blocks;
+
+#[derive(Serialize, Queryable, Selectable, Insertable, Clone)]
+#[diesel(table_name = failed_blocks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FailedBlockDb {
+    pub block_height: i32,
+    pub error: String,
+    pub skipped: bool,
+}
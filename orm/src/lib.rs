@@ -1,7 +1,17 @@
+pub mod anchor;
+pub mod block_hash;
 pub mod block_index;
+pub mod chain_id;
 pub mod chain_state;
+pub mod compact_output;
+pub mod failed_block;
+#[cfg(feature = "test-utils")]
+pub mod memory;
 pub mod notes_index;
+pub mod nullifier;
+pub mod pruned_height;
 pub mod schema;
+pub mod start_height;
 pub mod tree;
 pub mod tx;
 pub mod witness;
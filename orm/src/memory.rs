@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::anchor::AnchorDb;
+use crate::block_hash::BlockHashDb;
+use crate::chain_state::ChainStateDb;
+use crate::compact_output::CompactOutputDb;
+use crate::failed_block::FailedBlockDb;
+use crate::notes_index::NotesIndexDb;
+use crate::nullifier::NullifierDb;
+use crate::tree::TreeDb;
+use crate::tx::TxDb;
+use crate::witness::WitnessDb;
+
+/// Rows of the in-memory tables, mirroring the Postgres schema.
+#[derive(Default)]
+pub struct Tables {
+    pub chain_state: Option<ChainStateDb>,
+    pub commitment_tree: Vec<TreeDb>,
+    pub witness: Vec<WitnessDb>,
+    /// Notes, keyed by their position in the commitment tree.
+    pub notes_index: BTreeMap<i32, NotesIndexDb>,
+    pub tx: Vec<TxDb>,
+    /// Nullifiers, keyed by their bytes.
+    pub nullifiers: BTreeMap<Vec<u8>, NullifierDb>,
+    /// Compact outputs, keyed by their block height, block index, masp
+    /// tx index and output index.
+    pub compact_outputs: BTreeMap<(i32, i32, i32, i32), CompactOutputDb>,
+    pub block_hash: BTreeMap<i32, BlockHashDb>,
+    pub anchors: BTreeMap<i32, AnchorDb>,
+    pub failed_blocks: BTreeMap<i32, FailedBlockDb>,
+    pub start_height: Option<i32>,
+    pub chain_id: Option<String>,
+    pub pruned_height: Option<i32>,
+    next_id: i32,
+}
+
+impl Tables {
+    /// Next value of the serial ids of the rows.
+    pub fn next_id(&mut self) -> i32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn last_synced_height(&self) -> Option<i32> {
+        self.chain_state.as_ref().map(|state| state.block_height)
+    }
+
+    /// Commitment tree at the highest height at or below `block_height`.
+    pub fn commitment_tree_at(&self, block_height: i32) -> Option<&TreeDb> {
+        self.commitment_tree
+            .iter()
+            .filter(|tree| tree.block_height <= block_height)
+            .max_by_key(|tree| tree.block_height)
+    }
+
+    /// Height of the last witness map checkpoint at or below
+    /// `block_height`.
+    pub fn witness_checkpoint_at(&self, block_height: i32) -> Option<i32> {
+        self.witness
+            .iter()
+            .map(|witness| witness.block_height)
+            .filter(|&height| height <= block_height)
+            .max()
+    }
+
+    /// Witnesses of the checkpoint taken at `block_height`.
+    pub fn witnesses_at(&self, block_height: i32) -> Vec<WitnessDb> {
+        self.witness
+            .iter()
+            .filter(|witness| witness.block_height == block_height)
+            .cloned()
+            .collect()
+    }
+}
+
+/// In-memory database shared by the crawler and the webserver in tests.
+/// Clones refer to the same tables.
+#[derive(Default, Clone)]
+pub struct MemoryDb(Arc<Mutex<Tables>>);
+
+impl MemoryDb {
+    /// Lock the tables. Reads and writes done while holding the lock
+    /// are atomic, like the transactions of the Postgres backend.
+    pub fn lock(&self) -> MutexGuard<'_, Tables> {
+        self.0.lock().unwrap()
+    }
+}
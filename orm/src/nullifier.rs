@@ -0,0 +1,25 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::nullifiers;
+
+#[derive(Serialize, Queryable, Selectable, Clone)]
+#[diesel(table_name = nullifiers)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NullifierDb {
+    pub id: i32,
+    pub nullifier: Vec<u8>,
+    pub block_height: i32,
+    pub block_index: i32,
+    pub masp_tx_index: i32,
+}
+
+#[derive(Serialize, Insertable, Clone)]
+#[diesel(table_name = nullifiers)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NullifierInsertDb {
+    pub nullifier: Vec<u8>,
+    pub block_height: i32,
+    pub block_index: i32,
+    pub masp_tx_index: i32,
+}
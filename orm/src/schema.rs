@@ -1,5 +1,22 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    anchors (block_height) {
+        block_height -> Int4,
+        root -> Bytea,
+        tree_size -> Int4,
+    }
+}
+
+diesel::table! {
+    block_hash (block_height) {
+        block_height -> Int4,
+        hash -> Varchar,
+        timestamp -> Nullable<Timestamp>,
+        epoch -> Nullable<Int4>,
+    }
+}
+
 diesel::table! {
     block_index (id) {
         id -> Int4,
@@ -8,10 +25,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    chain_id (id) {
+        id -> Int4,
+        chain_id -> Varchar,
+    }
+}
+
 diesel::table! {
     chain_state (id) {
         id -> Int4,
         block_height -> Int4,
+        chain_tip -> Int4,
+        committed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    compact_outputs (id) {
+        id -> Int4,
+        block_height -> Int4,
+        block_index -> Int4,
+        masp_tx_index -> Int4,
+        output_index -> Int4,
+        cmu -> Bytea,
+        ephemeral_key -> Bytea,
+        ciphertext -> Bytea,
     }
 }
 
@@ -23,12 +62,46 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    failed_blocks (block_height) {
+        block_height -> Int4,
+        error -> Varchar,
+        skipped -> Bool,
+        failed_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     notes_index (note_position) {
         note_position -> Int4,
         block_index -> Int4,
         block_height -> Int4,
         masp_tx_index -> Int4,
+        is_fee_unshielding -> Bool,
+    }
+}
+
+diesel::table! {
+    nullifiers (id) {
+        id -> Int4,
+        nullifier -> Bytea,
+        block_height -> Int4,
+        block_index -> Int4,
+        masp_tx_index -> Int4,
+    }
+}
+
+diesel::table! {
+    pruned_height (id) {
+        id -> Int4,
+        block_height -> Int4,
+    }
+}
+
+diesel::table! {
+    start_height (id) {
+        id -> Int4,
+        block_height -> Int4,
     }
 }
 
@@ -52,10 +125,18 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    anchors,
+    block_hash,
     block_index,
+    chain_id,
     chain_state,
+    compact_outputs,
     commitment_tree,
+    failed_blocks,
     notes_index,
+    nullifiers,
+    pruned_height,
+    start_height,
     tx,
     witness,
 );
@@ -0,0 +1,11 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use crate::schema::start_height;
+
+#[derive(Queryable, Selectable, Insertable, Clone)]
+#[diesel(table_name = start_height)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct StartHeightDb {
+    pub id: i32,
+    pub block_height: i32,
+}
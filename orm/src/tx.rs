@@ -3,6 +3,11 @@ use serde::Serialize;
 
 use crate::schema::tx;
 
+/// Postgres channel notified whenever blocks containing masp txs are
+/// committed. The payload holds the lowest and highest block heights of
+/// the committed txs, separated by a colon.
+pub const COMMITTED_TXS_CHANNEL: &str = "masp_txs_committed";
+
 #[derive(Serialize, Queryable, Selectable, Clone)]
 #[diesel(table_name = tx)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use criterion::{
+    BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main,
+};
+use namada_sdk::masp_primitives::merkle_tree::{
+    CommitmentTree, IncrementalWitness,
+};
+use namada_sdk::masp_primitives::sapling::Node;
+use shared::witness::append_notes;
+
+/// Number of notes committed before the benchmarked block, each of them
+/// with a witness to update.
+const NUM_WITNESSES: usize = 1000;
+
+fn node(value: u64) -> Node {
+    let mut repr = [0; 32];
+    repr[..8].copy_from_slice(&value.to_le_bytes());
+    Node::new(repr)
+}
+
+/// Commitment tree and witness map after `NUM_WITNESSES` notes.
+fn setup() -> (
+    CommitmentTree<Node>,
+    HashMap<usize, IncrementalWitness<Node>>,
+) {
+    let mut commitment_tree = CommitmentTree::empty();
+    let mut witnesses = HashMap::new();
+
+    for note_pos in 0..NUM_WITNESSES {
+        let node = node(note_pos as u64);
+        for witness in witnesses.values_mut() {
+            witness.append(node).unwrap();
+        }
+        commitment_tree.append(node).unwrap();
+        witnesses
+            .insert(note_pos, IncrementalWitness::from_tree(&commitment_tree));
+    }
+
+    (commitment_tree, witnesses)
+}
+
+/// Append the notes of a block one by one, updating every witness after
+/// each of them.
+fn append_notes_sequentially(
+    commitment_tree: &mut CommitmentTree<Node>,
+    witnesses: &mut HashMap<usize, IncrementalWitness<Node>>,
+    nodes: &[Node],
+) {
+    for node in nodes {
+        for witness in witnesses.values_mut() {
+            witness.append(*node).unwrap();
+        }
+        commitment_tree.append(*node).unwrap();
+        witnesses.insert(
+            commitment_tree.size() - 1,
+            IncrementalWitness::from_tree(commitment_tree),
+        );
+    }
+}
+
+fn bench_append_notes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append_notes");
+    group.sample_size(10);
+
+    let (commitment_tree, witnesses) = setup();
+
+    for block_size in [10, 100, 1000] {
+        let nodes = (0..block_size)
+            .map(|index| node((NUM_WITNESSES + index) as u64))
+            .collect::<Vec<_>>();
+
+        group.bench_with_input(
+            BenchmarkId::new("sequential", block_size),
+            &nodes,
+            |b, nodes| {
+                b.iter_batched(
+                    || (commitment_tree.clone(), witnesses.clone()),
+                    |(mut commitment_tree, mut witnesses)| {
+                        append_notes_sequentially(
+                            &mut commitment_tree,
+                            &mut witnesses,
+                            nodes,
+                        )
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("batched", block_size),
+            &nodes,
+            |b, nodes| {
+                b.iter_batched(
+                    || (commitment_tree.clone(), witnesses.clone()),
+                    |(mut commitment_tree, mut witnesses)| {
+                        append_notes(
+                            &mut commitment_tree,
+                            &mut witnesses,
+                            nodes,
+                        )
+                        .unwrap()
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_append_notes);
+criterion_main!(benches);
@@ -1,10 +1,12 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
+use namada_core::hash::Hash;
 use namada_core::masp_primitives::transaction::Transaction as NamadaMaspTransaction;
 use namada_sdk::events::extend::IndexedMaspData;
 use tendermint_rpc::endpoint::{block, block_results};
 
-use crate::block_results::locate_masp_txs;
+use crate::block_results::{TxOutcome, locate_masp_txs};
 use crate::header::BlockHeader;
 use crate::id::Id;
 use crate::indexed_tx::IndexedTx;
@@ -15,6 +17,8 @@ use crate::tx_index::{MaspTxIndex, TxIndex};
 pub struct Block {
     pub hash: Id,
     pub header: BlockHeader,
+    /// Epoch of the block, if it could be queried.
+    pub epoch: Option<u64>,
     pub transactions: Vec<(usize, Transaction)>,
 }
 
@@ -22,12 +26,15 @@ impl Block {
     pub fn new(
         raw_block: block::Response,
         raw_results: block_results::Response,
+        tx_outcomes: &HashMap<Hash, TxOutcome>,
+        epoch: Option<u64>,
     ) -> Result<Self, String> {
-        let indexed_masp_txs = locate_masp_txs(&raw_results);
+        let indexed_masp_txs = locate_masp_txs(&raw_results)?;
 
         let mut block = Block {
             hash: Id::from(raw_block.block_id.hash),
             header: BlockHeader::from(raw_block.block.header),
+            epoch,
             transactions: Vec::with_capacity(raw_block.block.data.len()),
         };
 
@@ -38,7 +45,21 @@ impl Block {
         {
             let block_index = tx_index.0 as usize;
             let tx_bytes = &raw_block.block.data[block_index];
-            let tx = Transaction::from_namada_tx(tx_bytes, &masp_refs.0)?;
+            let tx = Transaction::from_namada_tx(
+                tx_bytes,
+                &masp_refs.0,
+                tx_outcomes,
+            )?;
+
+            if tx.masp_txs.is_empty() {
+                tracing::warn!(
+                    block_height = %block.header.height,
+                    block_index,
+                    tx_hash = %tx.hash,
+                    "Skipping masp txs of tx that was rejected"
+                );
+                continue;
+            }
 
             block.transactions.push((block_index, tx));
         }
@@ -1,18 +1,232 @@
+use std::collections::HashMap;
+
+use either::Either;
+use namada_core::hash::Hash;
 use namada_sdk::events::extend::{
-    IndexedMaspData, MaspDataRefs, ReadFromEventAttributes,
+    IndexedMaspData, MaspDataRefs, ReadFromEventAttributes, TxHash,
 };
+use namada_tx::TxCommitments;
+use namada_tx::data::{ResultCode, TxResult};
+use namada_tx::event::{Batch, Code};
+use tendermint::abci::Event;
 use tendermint_rpc::endpoint::block_results;
 
+/// Result of a tx applied in a block, read from its events.
+#[derive(Debug, Clone)]
+pub struct TxOutcome {
+    pub code: ResultCode,
+    pub batch: Option<TxResult<String>>,
+}
+
+impl TxOutcome {
+    /// Whether the wrapper tx was applied, i.e. its fees were paid, even
+    /// if some of its inner txs were rejected.
+    pub fn is_wrapper_applied(&self) -> bool {
+        matches!(self.code, ResultCode::Ok | ResultCode::WasmRuntimeError)
+    }
+
+    /// Whether the inner tx with `commitments`, batched in the wrapper tx
+    /// with hash `wrapper_hash`, was accepted.
+    pub fn is_inner_tx_accepted(
+        &self,
+        wrapper_hash: &Hash,
+        commitments: &TxCommitments,
+    ) -> bool {
+        self.is_wrapper_applied()
+            && self
+                .batch
+                .as_ref()
+                .and_then(|batch| {
+                    batch.get_inner_tx_result(
+                        Some(wrapper_hash),
+                        Either::Right(commitments),
+                    )
+                })
+                .is_some_and(|result| {
+                    result.as_ref().is_ok_and(|result| result.is_accepted())
+                })
+    }
+}
+
+/// Events emitted by the txs of a block. CometBFT 0.38 nodes report them
+/// as `finalize_block_events`, while older nodes report them as
+/// `end_block_events`.
+///
+/// Fails if the block applied txs, but no events were found, since their
+/// masp txs would otherwise be dropped without notice.
+fn block_events(
+    raw_block_results: &block_results::Response,
+) -> Result<&[Event], String> {
+    let events = match raw_block_results.end_block_events.as_deref() {
+        Some(events) if !events.is_empty() => events,
+        _ => &raw_block_results.finalize_block_events,
+    };
+
+    let num_txs = raw_block_results.txs_results.as_ref().map_or(0, Vec::len);
+
+    if events.is_empty() && num_txs > 0 {
+        return Err(format!(
+            "Found no end block or finalize block events in the results of \
+             block {}, which applied {num_txs} txs",
+            raw_block_results.height
+        ));
+    }
+
+    Ok(events)
+}
+
 pub fn locate_masp_txs(
     raw_block_results: &block_results::Response,
-) -> Vec<IndexedMaspData> {
-    raw_block_results
-        .end_block_events
-        .as_ref()
-        .unwrap_or(&vec![])
+) -> Result<Vec<IndexedMaspData>, String> {
+    Ok(block_events(raw_block_results)?
         .iter()
         .filter_map(|event| {
             MaspDataRefs::read_from_event_attributes(&event.attributes).ok()
         })
-        .collect()
+        .collect())
+}
+
+/// Look up the results of the txs applied in a block, indexed by the
+/// hash of their wrapper tx.
+pub fn locate_tx_outcomes(
+    raw_block_results: &block_results::Response,
+) -> Result<HashMap<Hash, TxOutcome>, String> {
+    Ok(block_events(raw_block_results)?
+        .iter()
+        .filter_map(|event| {
+            let hash =
+                TxHash::read_from_event_attributes(&event.attributes).ok()?;
+            let code =
+                Code::read_from_event_attributes(&event.attributes).ok()?;
+            let batch =
+                Batch::read_from_event_attributes(&event.attributes).ok();
+
+            Some((hash, TxOutcome { code, batch }))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address::MASP;
+    use namada_tx::data::BatchedTxResult;
+    use tendermint::AppHash;
+    use tendermint::abci::types::ExecTxResult;
+
+    use super::*;
+
+    fn commitments(seed: u8) -> TxCommitments {
+        TxCommitments {
+            data_hash: Hash([seed; 32]),
+            ..Default::default()
+        }
+    }
+
+    /// Outcome of a wrapper tx with hash `wrapper_hash`, whose batch
+    /// holds the results of the inner txs with `commitments`.
+    fn outcome(
+        code: ResultCode,
+        wrapper_hash: &Hash,
+        inner_txs: &[(TxCommitments, Result<BatchedTxResult, String>)],
+    ) -> TxOutcome {
+        let mut batch = TxResult::default();
+        for (commitments, result) in inner_txs {
+            batch.insert_inner_tx_result(
+                Some(wrapper_hash),
+                Either::Right(commitments),
+                result.clone(),
+            );
+        }
+
+        TxOutcome {
+            code,
+            batch: Some(batch),
+        }
+    }
+
+    fn rejected() -> BatchedTxResult {
+        let mut result = BatchedTxResult::default();
+        result.vps_result.rejected_vps.insert(MASP);
+        result
+    }
+
+    fn block_results(
+        num_txs: usize,
+        end_block_events: Option<Vec<Event>>,
+        finalize_block_events: Vec<Event>,
+    ) -> block_results::Response {
+        block_results::Response {
+            height: 1_u32.into(),
+            txs_results: Some(vec![ExecTxResult::default(); num_txs]),
+            finalize_block_events,
+            begin_block_events: None,
+            end_block_events,
+            validator_updates: vec![],
+            consensus_param_updates: None,
+            app_hash: AppHash::default(),
+        }
+    }
+
+    fn event(kind: &str) -> Event {
+        Event::new(kind, [("height", "1")])
+    }
+
+    #[test]
+    fn inner_txs_of_a_rejected_wrapper_are_not_accepted() {
+        let wrapper_hash = Hash([1; 32]);
+        let outcome = outcome(
+            ResultCode::InvalidTx,
+            &wrapper_hash,
+            &[(commitments(1), Ok(BatchedTxResult::default()))],
+        );
+
+        assert!(!outcome.is_wrapper_applied());
+        assert!(!outcome.is_inner_tx_accepted(&wrapper_hash, &commitments(1)));
+    }
+
+    #[test]
+    fn rejected_inner_txs_of_an_applied_wrapper_are_not_accepted() {
+        let wrapper_hash = Hash([1; 32]);
+        let outcome = outcome(
+            ResultCode::WasmRuntimeError,
+            &wrapper_hash,
+            &[
+                (commitments(1), Ok(BatchedTxResult::default())),
+                (commitments(2), Ok(rejected())),
+                (commitments(3), Err("out of gas".to_string())),
+            ],
+        );
+
+        assert!(outcome.is_wrapper_applied());
+        assert!(outcome.is_inner_tx_accepted(&wrapper_hash, &commitments(1)));
+        assert!(!outcome.is_inner_tx_accepted(&wrapper_hash, &commitments(2)));
+        assert!(!outcome.is_inner_tx_accepted(&wrapper_hash, &commitments(3)));
+        // NB: inner txs missing from the batch were never executed
+        assert!(!outcome.is_inner_tx_accepted(&wrapper_hash, &commitments(4)));
+        // NB: results are keyed by the hash of their wrapper tx too
+        assert!(!outcome.is_inner_tx_accepted(&Hash([2; 32]), &commitments(1)));
+    }
+
+    #[test]
+    fn block_events_fall_back_to_finalize_block_events() {
+        // NB: CometBFT 0.37 nodes report end block events
+        let results = block_results(1, Some(vec![event("end")]), vec![]);
+        assert_eq!(block_events(&results).unwrap(), [event("end")]);
+
+        // NB: CometBFT 0.38 nodes report finalize block events, leaving
+        // end block events null or empty
+        let results = block_results(1, None, vec![event("finalize")]);
+        assert_eq!(block_events(&results).unwrap(), [event("finalize")]);
+        let results = block_results(1, Some(vec![]), vec![event("finalize")]);
+        assert_eq!(block_events(&results).unwrap(), [event("finalize")]);
+    }
+
+    #[test]
+    fn block_events_are_required_when_txs_were_applied() {
+        let results = block_results(0, None, vec![]);
+        assert!(block_events(&results).unwrap().is_empty());
+
+        let results = block_results(2, Some(vec![]), vec![]);
+        block_events(&results).unwrap_err();
+    }
 }
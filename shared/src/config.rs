@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::error::ErrorKind;
+use clap::{Arg, ArgAction, Command, FromArgMatches, Parser};
+
+/// Environment variable holding the path to the config file, if it is
+/// not passed with `--config`.
+const CONFIG_PATH_ENV: &str = "CONFIG_PATH";
+
+/// Settings of the pool of connections to the database.
+#[derive(clap::Args, Clone, Copy, Debug)]
+pub struct DbPoolConfig {
+    /// Maximum number of connections to the database
+    #[clap(long, env, default_value_t = 8)]
+    pub database_pool_size: usize,
+
+    /// Number of times building the pool of connections to the database
+    /// is retried, before giving up
+    #[clap(long, env, default_value_t = 5)]
+    pub database_max_conn_retries: u32,
+
+    /// Maximum time waited for a connection to be available in the
+    /// pool, in seconds. Waits indefinitely if unset.
+    #[clap(long, env)]
+    pub database_wait_timeout: Option<u64>,
+
+    /// Maximum time spent establishing a new connection to the
+    /// database, in seconds. Waits indefinitely if unset.
+    #[clap(long, env)]
+    pub database_connect_timeout: Option<u64>,
+}
+
+impl DbPoolConfig {
+    pub fn wait_timeout(&self) -> Option<Duration> {
+        self.database_wait_timeout.map(Duration::from_secs)
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.database_connect_timeout.map(Duration::from_secs)
+    }
+}
+
+/// Parse `T` like [`Parser::parse`], falling back on the settings of the
+/// TOML config file passed with `--config`, if any.
+///
+/// The command line takes precedence over environment variables, which
+/// take precedence over the config file. The keys of the config file are
+/// the names of the command line arguments, in snake case. Exits with an
+/// error message if the config file or the arguments are invalid.
+pub fn parse<T: Parser>() -> T {
+    let mut command = T::command().arg(
+        Arg::new("config")
+            .long("config")
+            .env(CONFIG_PATH_ENV)
+            .value_name("PATH")
+            .value_parser(clap::value_parser!(PathBuf))
+            .help(
+                "TOML file holding the settings that are neither passed as \
+                 arguments nor as environment variables",
+            ),
+    );
+
+    if let Some(path) = config_path() {
+        command = match apply_config_file(command.clone(), &path) {
+            Ok(command) => command,
+            Err(err) => command
+                .error(ErrorKind::InvalidValue, format!("{err:#}"))
+                .exit(),
+        };
+    }
+
+    let matches = command.get_matches_mut();
+    T::from_arg_matches(&matches)
+        .unwrap_or_else(|err| err.format(&mut command).exit())
+}
+
+/// Find the path to the config file, ahead of parsing the arguments
+/// whose defaults it holds.
+fn config_path() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) =
+            arg.to_str().and_then(|arg| arg.strip_prefix("--config="))
+        {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from)
+}
+
+/// Set the settings of the config file at `path` as the default values
+/// of the matching arguments of `command`.
+fn apply_config_file(
+    mut command: Command,
+    path: &Path,
+) -> anyhow::Result<Command> {
+    let contents = std::fs::read_to_string(path).with_context(|| {
+        format!("Failed to read config file {}", path.display())
+    })?;
+    let settings = contents.parse::<toml::Table>().with_context(|| {
+        format!("Failed to parse config file {}", path.display())
+    })?;
+
+    for (key, value) in settings {
+        let id = key.replace('-', "_");
+
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == id.as_str())
+        else {
+            anyhow::bail!(
+                "Unknown setting `{key}` in config file {}",
+                path.display()
+            );
+        };
+
+        let values = match value {
+            toml::Value::Array(values)
+                if matches!(arg.get_action(), ArgAction::Append) =>
+            {
+                values.into_iter().map(into_arg_value).collect()
+            }
+            value => into_arg_value(value).map(|value| vec![value]),
+        }
+        .with_context(|| {
+            format!(
+                "Invalid value of setting `{key}` in config file {}",
+                path.display()
+            )
+        })?;
+
+        // NB: default values come after environment variables and the
+        // command line, and satisfy required arguments
+        command = command
+            .mut_arg(id, |arg| arg.default_values(values).required(false));
+    }
+
+    Ok(command)
+}
+
+fn into_arg_value(value: toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Float(value) => Some(value.to_string()),
+        toml::Value::Boolean(value) => Some(value.to_string()),
+        toml::Value::Datetime(_)
+        | toml::Value::Array(_)
+        | toml::Value::Table(_) => None,
+    }
+}
@@ -9,6 +9,7 @@ pub struct BlockHeader {
     pub proposer_address: Id,
     pub timestamp: String,
     pub app_hash: Id,
+    pub last_block_hash: Option<Id>,
 }
 
 impl From<Header> for BlockHeader {
@@ -20,6 +21,7 @@ impl From<Header> for BlockHeader {
             ),
             timestamp: value.time.to_rfc3339(),
             app_hash: Id::from(value.app_hash),
+            last_block_hash: value.last_block_id.map(Id::from),
         }
     }
 }
@@ -1,12 +1,16 @@
 pub mod block;
 pub mod block_results;
 pub mod commitment_tree;
+pub mod config;
 pub mod error;
 pub mod extracted_masp_tx;
 pub mod header;
 pub mod height;
 pub mod id;
 pub mod indexed_tx;
+pub mod response;
+pub mod telemetry;
 pub mod transaction;
 pub mod transactional;
 pub mod tx_index;
+pub mod witness;
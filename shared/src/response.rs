@@ -0,0 +1,199 @@
+use std::io::{self, Write};
+
+use namada_core::borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+
+use crate::height::BlockHeight;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct TreeResponse {
+    pub commitment_tree: Vec<u8>,
+    pub block_height: u64,
+}
+
+/// Encodes the same fields as the JSON response, in declaration order.
+impl BorshSerialize for TreeResponse {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.commitment_tree.serialize(writer)?;
+        self.block_height.serialize(writer)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct WitnessMapResponse {
+    pub witnesses: Vec<Witness>,
+    pub block_height: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct Witness {
+    pub bytes: Vec<u8>,
+    pub index: u64,
+}
+
+impl WitnessMapResponse {
+    pub fn new(
+        block_height: BlockHeight,
+        witnesses: Vec<(Vec<u8>, u64)>,
+    ) -> Self {
+        Self {
+            witnesses: witnesses
+                .into_iter()
+                .map(|(bytes, index)| Witness { bytes, index })
+                .collect(),
+            block_height: block_height.0,
+        }
+    }
+}
+
+/// Encodes the same fields as the JSON response, in declaration order.
+impl BorshSerialize for WitnessMapResponse {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.witnesses.serialize(writer)?;
+        self.block_height.serialize(writer)
+    }
+}
+
+impl BorshSerialize for Witness {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.bytes.serialize(writer)?;
+        self.index.serialize(writer)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct NotesIndexResponse {
+    pub notes_index: Vec<Note>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct Note {
+    pub block_height: u64,
+    pub block_index: u64,
+    pub masp_tx_index: u64,
+    pub note_position: u64,
+    pub is_fee_unshielding: bool,
+}
+
+impl NotesIndexResponse {
+    pub fn new(
+        notes_index: Vec<(u64, u64, u64, u64, bool)>,
+        has_more: bool,
+    ) -> Self {
+        Self {
+            notes_index: notes_index.into_iter().map(Note::from).collect(),
+            has_more,
+        }
+    }
+}
+
+impl From<(u64, u64, u64, u64, bool)> for Note {
+    fn from(
+        (
+            block_height,
+            block_index,
+            masp_tx_index,
+            note_position,
+            is_fee_unshielding,
+        ): (u64, u64, u64, u64, bool),
+    ) -> Self {
+        Self {
+            block_height,
+            block_index,
+            masp_tx_index,
+            note_position,
+            is_fee_unshielding,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct TxResponse {
+    pub txs: Vec<Tx>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct Tx {
+    pub block_height: u64,
+    pub block_index: u64,
+    pub batch: Vec<TxSlot>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct TxSlot {
+    pub masp_tx_index: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl TxResponse {
+    pub fn new(
+        txs: impl IntoIterator<Item = (Vec<(u64, Vec<u8>)>, u64, u64)>,
+        has_more: bool,
+    ) -> Self {
+        Self {
+            txs: txs
+                .into_iter()
+                .map(|(batch, block_height, block_index)| Tx {
+                    batch: batch
+                        .into_iter()
+                        .map(|(masp_tx_index, bytes)| TxSlot {
+                            masp_tx_index,
+                            bytes,
+                        })
+                        .collect(),
+                    block_height,
+                    block_index,
+                })
+                .collect(),
+            has_more,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct SyncResponse {
+    pub from_block_height: u64,
+    pub to_block_height: u64,
+    pub commitment_tree: Option<TreeResponse>,
+    pub witness_map: Option<WitnessMapResponse>,
+    pub notes_index: Vec<Note>,
+    pub txs: Vec<Tx>,
+}
+
+/// Encodes the same fields as the JSON response, in declaration order.
+impl BorshSerialize for SyncResponse {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.from_block_height.serialize(writer)?;
+        self.to_block_height.serialize(writer)?;
+
+        self.commitment_tree.serialize(writer)?;
+        self.witness_map.serialize(writer)?;
+
+        (self.notes_index.len() as u64).serialize(writer)?;
+        for note in &self.notes_index {
+            (
+                note.block_height,
+                note.block_index,
+                note.masp_tx_index,
+                note.note_position,
+                note.is_fee_unshielding,
+            )
+                .serialize(writer)?;
+        }
+
+        (self.txs.len() as u64).serialize(writer)?;
+        for tx in &self.txs {
+            tx.block_height.serialize(writer)?;
+            tx.block_index.serialize(writer)?;
+
+            (tx.batch.len() as u64).serialize(writer)?;
+            for slot in &tx.batch {
+                (slot.masp_tx_index, &slot.bytes).serialize(writer)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::Context;
+use opentelemetry::KeyValue;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{Resource, runtime};
+use tracing::Subscriber;
+use tracing_subscriber::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Build a layer exporting spans over gRPC to the OTLP collector at
+/// `endpoint`, under the name `service_name`.
+///
+/// Also installs the W3C trace context propagator, such that traces
+/// started by other services can be continued. Must be called from
+/// within a Tokio runtime.
+pub fn otlp_layer<S>(
+    service_name: &'static str,
+    endpoint: &str,
+) -> anyhow::Result<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .context("Failed to build OTLP span exporter")?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            service_name,
+        )]))
+        .build();
+    let tracer = provider.tracer(service_name);
+
+    opentelemetry::global::set_tracer_provider(provider);
+    opentelemetry::global::set_text_map_propagator(
+        TraceContextPropagator::new(),
+    );
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Export the spans still buffered, if OTLP export was set up.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
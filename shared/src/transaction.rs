@@ -1,12 +1,17 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Display;
 
+use namada_core::borsh::BorshDeserialize;
 use namada_core::hash::Hash;
+use namada_core::masp::MaspTxId;
 use namada_core::masp_primitives::transaction::Transaction as NamadaMaspTransaction;
 use namada_sdk::events::extend::MaspTxRef;
+use namada_sdk::ibc::IbcMessage;
 use namada_sdk::token::Transfer;
-use namada_tx::{Data, Section, Tx as NamadaTx};
+use namada_tx::{Data, Section, Tx as NamadaTx, TxCommitments};
 
+use crate::block_results::TxOutcome;
 use crate::id::Id;
 
 #[derive(Debug, Clone)]
@@ -16,17 +21,41 @@ pub struct Transaction {
 }
 
 impl Transaction {
+    /// Decode the masp txs referenced by `masp_tx_refs` out of a Namada
+    /// tx, skipping the ones that were not applied according to
+    /// `tx_outcomes`.
     pub fn from_namada_tx(
         nam_tx_bytes: &[u8],
-        valid_masp_tx_refs: &[MaspTxRef],
+        masp_tx_refs: &[MaspTxRef],
+        tx_outcomes: &HashMap<Hash, TxOutcome>,
     ) -> Result<Self, String> {
         let transaction =
             NamadaTx::try_from(nam_tx_bytes).map_err(|e| e.to_string())?;
         let transaction_id = transaction.header_hash();
 
-        let masp_txs = valid_masp_tx_refs.iter().try_fold(
-            vec![],
-            |mut acc, masp_tx_ref| {
+        // NB: masp txs are referenced by the events of the txs that were
+        // applied, so a missing outcome means the events were misread,
+        // rather than the tx being rejected
+        let outcome = tx_outcomes.get(&transaction_id).ok_or_else(|| {
+            format!(
+                "Missing the outcome of tx {transaction_id} in the block \
+                 results"
+            )
+        })?;
+
+        let valid_masp_tx_refs = masp_tx_refs.iter().filter(|masp_tx_ref| {
+            // NB: masp txs not carried by any inner tx unshield the
+            // fees of the wrapper tx
+            match find_inner_tx(&transaction, masp_tx_ref) {
+                Some(commitments) => {
+                    outcome.is_inner_tx_accepted(&transaction_id, commitments)
+                }
+                None => outcome.is_wrapper_applied(),
+            }
+        });
+
+        let masp_txs =
+            valid_masp_tx_refs.try_fold(vec![], |mut acc, masp_tx_ref| {
                 let masp_tx = match &masp_tx_ref {
                     MaspTxRef::MaspSection(masp_tx_id) => {
                         let masp_tx = transaction
@@ -51,8 +80,7 @@ impl Transaction {
 
                 acc.push(masp_tx.into_owned());
                 Result::<_, String>::Ok(acc)
-            },
-        )?;
+            })?;
 
         Ok(Transaction {
             masp_txs,
@@ -67,18 +95,68 @@ impl Display for Transaction {
     }
 }
 
+/// Find the inner tx of `transaction` carrying the masp tx referenced by
+/// `masp_tx_ref`, if any.
+fn find_inner_tx<'tx>(
+    transaction: &'tx NamadaTx,
+    masp_tx_ref: &MaspTxRef,
+) -> Option<&'tx TxCommitments> {
+    transaction
+        .commitments()
+        .iter()
+        .find(|commitments| match masp_tx_ref {
+            MaspTxRef::MaspSection(masp_tx_id) => transaction
+                .data(commitments)
+                .and_then(|data| shielded_section_hash(&data))
+                .is_some_and(|hash| hash == *masp_tx_id),
+            MaspTxRef::IbcData(sechash) => {
+                commitments.data_sechash() == sechash
+            }
+        })
+}
+
+/// Hash of the masp section shielding or unshielding the funds of an
+/// inner tx, given its data. Besides native transfers, IBC transfers may
+/// carry a [`Transfer`] with a masp section, e.g. when unshielding to
+/// another chain.
+fn shielded_section_hash(tx_data: &[u8]) -> Option<MaspTxId> {
+    if let Ok(transfer) = Transfer::try_from_slice(tx_data) {
+        return transfer.shielded_section_hash;
+    }
+
+    let transfer =
+        match namada_sdk::ibc::decode_message::<Transfer>(tx_data).ok()? {
+            IbcMessage::Transfer(msg) => msg.transfer,
+            IbcMessage::NftTransfer(msg) => msg.transfer,
+            IbcMessage::Envelope(_) => None,
+        };
+
+    transfer?.shielded_section_hash
+}
+
+/// Decode the masp tx carried by the IBC message in the data section
+/// with hash `data_sechash`. Shielding deposits from other chains embed
+/// the masp tx in the packets they receive, while IBC transfers of
+/// shielded funds reference a masp section of `transaction`.
 fn get_masp_tx_from_ibc_data(
     transaction: &NamadaTx,
     data_sechash: &Hash,
 ) -> Option<NamadaMaspTransaction> {
     let tx_data = get_namada_tx_data(transaction, data_sechash)?;
 
-    let ibc_msg = namada_sdk::ibc::decode_message::<Transfer>(tx_data).ok()?;
-    let namada_sdk::ibc::IbcMessage::Envelope(envelope) = ibc_msg else {
-        return None;
-    };
-
-    namada_sdk::ibc::extract_masp_tx_from_envelope(&envelope)
+    match namada_sdk::ibc::decode_message::<Transfer>(tx_data).ok()? {
+        IbcMessage::Envelope(envelope) => {
+            namada_sdk::ibc::extract_masp_tx_from_envelope(&envelope)
+        }
+        IbcMessage::Transfer(msg) => {
+            let masp_tx_id = msg.transfer?.shielded_section_hash?;
+            transaction.get_masp_section(&masp_tx_id).cloned()
+        }
+        IbcMessage::NftTransfer(msg) => {
+            let masp_tx_id = msg.transfer?.shielded_section_hash?;
+            transaction.get_masp_section(&masp_tx_id).cloned()
+        }
+    }
 }
 
 fn get_namada_tx_data<'tx>(
@@ -93,3 +171,100 @@ fn get_namada_tx_data<'tx>(
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use namada_core::borsh::BorshSerializeExt;
+    use namada_core::masp_primitives::transaction::TxId;
+    use namada_tx::data::{ResultCode, TxType};
+
+    use super::*;
+
+    fn masp_tx_id(seed: u8) -> MaspTxId {
+        MaspTxId::from(TxId::from_bytes([seed; 32]))
+    }
+
+    /// Tx batching a transfer shielded by the masp tx with id
+    /// `masp_tx_id`, followed by an IBC tx with data `ibc_data`.
+    fn batched_tx(masp_tx_id: MaspTxId, ibc_data: Vec<u8>) -> NamadaTx {
+        let mut transaction = NamadaTx::from_type(TxType::Raw);
+        transaction.add_serialized_data(
+            Transfer {
+                shielded_section_hash: Some(masp_tx_id),
+                ..Default::default()
+            }
+            .serialize_to_vec(),
+        );
+
+        let mut ibc_tx = NamadaTx::from_type(TxType::Raw);
+        ibc_tx.add_serialized_data(ibc_data);
+        let commitments = ibc_tx.first_commitments().unwrap().clone();
+        assert!(transaction.add_inner_tx(ibc_tx, commitments));
+
+        transaction
+    }
+
+    fn ibc_commitments(transaction: &NamadaTx) -> &TxCommitments {
+        transaction
+            .commitments()
+            .iter()
+            .find(|commitments| {
+                transaction.data(commitments) == Some(vec![1, 2, 3])
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn masp_tx_refs_are_matched_to_their_inner_tx() {
+        let transaction = batched_tx(masp_tx_id(1), vec![1, 2, 3]);
+        let ibc_commitments = ibc_commitments(&transaction);
+
+        let commitments =
+            find_inner_tx(&transaction, &MaspTxRef::MaspSection(masp_tx_id(1)))
+                .unwrap();
+        assert_ne!(commitments, ibc_commitments);
+
+        let commitments = find_inner_tx(
+            &transaction,
+            &MaspTxRef::IbcData(*ibc_commitments.data_sechash()),
+        )
+        .unwrap();
+        assert_eq!(commitments, ibc_commitments);
+    }
+
+    #[test]
+    fn fee_unshielding_refs_have_no_inner_tx() {
+        let transaction = batched_tx(masp_tx_id(1), vec![1, 2, 3]);
+
+        // NB: the masp tx unshielding the fees of the wrapper tx is not
+        // referenced by the data of any of its inner txs
+        assert!(
+            find_inner_tx(&transaction, &MaspTxRef::MaspSection(masp_tx_id(2)))
+                .is_none()
+        );
+        assert!(
+            find_inner_tx(&transaction, &MaspTxRef::IbcData(Hash([2; 32])))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn fee_unshielding_is_skipped_if_the_wrapper_was_rejected() {
+        let transaction = batched_tx(masp_tx_id(1), vec![1, 2, 3]);
+        let tx_outcomes = HashMap::from([(
+            transaction.header_hash(),
+            TxOutcome {
+                code: ResultCode::InvalidTx,
+                batch: None,
+            },
+        )]);
+
+        let transaction = Transaction::from_namada_tx(
+            &transaction.to_bytes(),
+            &[MaspTxRef::MaspSection(masp_tx_id(2))],
+            &tx_outcomes,
+        )
+        .unwrap();
+        assert!(transaction.masp_txs.is_empty());
+    }
+}
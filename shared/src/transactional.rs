@@ -38,6 +38,11 @@ impl<T> Transactional<T> {
         true
     }
 
+    /// Return the value as of the last commit.
+    pub fn committed(&self) -> &T {
+        &self.committed
+    }
+
     pub fn rollback(&mut self) {
         self.working_copy = None;
     }
@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+use namada_sdk::masp_primitives::merkle_tree::{
+    CommitmentTree, IncrementalWitness,
+};
+use namada_sdk::masp_primitives::sapling::Node;
+use rayon::prelude::*;
+
+/// Append the note commitments `nodes` of a block to `commitment_tree`,
+/// and to the `witnesses` of the notes committed before them, indexed
+/// by note position.
+///
+/// Returns the witnesses of the appended notes, in the same order. The
+/// witnesses are updated in parallel, since every witness has to absorb
+/// every note committed after it.
+pub fn append_notes<'a, P>(
+    commitment_tree: &mut CommitmentTree<Node>,
+    witnesses: impl IntoParallelIterator<
+        Item = (P, &'a mut IncrementalWitness<Node>),
+    >,
+    nodes: &[Node],
+) -> anyhow::Result<Vec<IncrementalWitness<Node>>>
+where
+    P: Display + Send,
+{
+    let first_note_pos = commitment_tree.size();
+
+    // NB: the witness of a note is built from the tree right after
+    // the note was appended, and then catches up with the rest
+    let mut new_witnesses = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        commitment_tree
+            .append(*node)
+            .map_err(|()| anyhow::anyhow!("Note commitment tree is full"))?;
+        new_witnesses.push(IncrementalWitness::from_tree(commitment_tree));
+    }
+
+    witnesses
+        .into_par_iter()
+        .try_for_each(|(note_pos, witness)| {
+            append_all(witness, nodes, note_pos)
+        })?;
+
+    new_witnesses.par_iter_mut().enumerate().try_for_each(
+        |(index, witness)| {
+            append_all(witness, &nodes[index + 1..], first_note_pos + index)
+        },
+    )?;
+
+    Ok(new_witnesses)
+}
+
+fn append_all(
+    witness: &mut IncrementalWitness<Node>,
+    nodes: &[Node],
+    note_pos: impl Display,
+) -> anyhow::Result<()> {
+    for node in nodes {
+        witness.append(*node).map_err(|()| {
+            anyhow::anyhow!("Witness of note {note_pos} is full")
+        })?;
+    }
+    Ok(())
+}
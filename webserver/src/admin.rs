@@ -0,0 +1,83 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::{Request, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::post;
+use subtle::ConstantTimeEq;
+
+use crate::appstate::AppState;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+#[derive(Clone)]
+struct AdminState {
+    data: AppState,
+    token: Arc<str>,
+}
+
+/// Serve the admin API on `addr`, separately from the public API, and
+/// authenticate its requests with the bearer `token`.
+pub fn spawn_server(addr: SocketAddr, token: String, data: AppState) {
+    let state = AdminState {
+        data,
+        token: token.into(),
+    };
+
+    let router = Router::new()
+        .route("/cache/invalidate", post(invalidate_cache))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_token,
+        ))
+        .with_state(state);
+
+    tokio::spawn(async move {
+        tracing::info!(%addr, "Serving admin API");
+
+        if let Err(reason) = axum::Server::bind(&addr)
+            .serve(router.into_make_service())
+            .await
+        {
+            tracing::error!(?reason, "Admin server shut down unexpectedly");
+        }
+    });
+}
+
+/// Middleware rejecting requests without the admin token.
+async fn require_token<B>(
+    State(state): State<AdminState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match token {
+        // NB: compared in constant time, such that the token cannot be
+        // guessed from response timings
+        Some(token)
+            if token.as_bytes().ct_eq(state.token.as_bytes()).into() =>
+        {
+            next.run(req).await
+        }
+        Some(_) => {
+            ApiErrorResponse::send(ErrorCode::Unauthorized, "Invalid token")
+        }
+        None => ApiErrorResponse::send(
+            ErrorCode::Unauthorized,
+            "Missing bearer token in the Authorization header",
+        ),
+    }
+}
+
+async fn invalidate_cache(State(state): State<AdminState>) -> StatusCode {
+    tracing::info!("Invalidating the response cache");
+    state.data.cache().invalidate();
+    StatusCode::NO_CONTENT
+}
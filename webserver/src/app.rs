@@ -4,23 +4,30 @@ use std::time::Duration;
 
 use anyhow::Context;
 use axum::error_handling::HandleErrorLayer;
-use axum::http::{HeaderValue, StatusCode};
-use axum::response::IntoResponse;
+use axum::http::HeaderValue;
+use axum::response::Response;
 use axum::routing::get;
-use axum::{BoxError, Json, Router};
+use axum::{BoxError, Router, middleware};
 use axum_trace_id::SetTraceIdLayer;
 use lazy_static::lazy_static;
 use serde_json::json;
+use tendermint_rpc::HttpClient;
 use tower::ServiceBuilder;
 use tower::buffer::BufferLayer;
 use tower::limit::RateLimitLayer;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
 use crate::appstate::AppState;
+use crate::auth::ApiKeys;
 use crate::config::AppConfig;
-use crate::handler;
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::response::api::{ApiErrorResponse, ErrorCode};
 use crate::state::common::CommonState;
+use crate::{
+    admin, auth, conditional, handler, metrics, openapi, rate_limit, telemetry,
+};
 
 lazy_static! {
     static ref HTTP_TIMEOUT: u64 = 60;
@@ -32,35 +39,125 @@ pub struct ApplicationServer;
 impl ApplicationServer {
     pub async fn serve(config: Arc<AppConfig>) -> anyhow::Result<()> {
         let rps = config.rps.unwrap_or_else(|| *REQ_PER_SEC);
-        let db_url = config.database_url.clone();
-
-        let app_state = AppState::new(db_url).await?;
-
-        let routes = {
-            let common_state = CommonState::new(app_state.clone());
-
-            Router::new()
-                .route(
-                    "/commitment-tree",
-                    get(handler::tree::get_commitment_tree),
-                )
-                .route(
-                    "/witness-map",
-                    get(handler::witness_map::get_witness_map),
-                )
-                .route(
-                    "/notes-index",
-                    get(handler::notes_index::get_notes_index),
-                )
-                .route("/tx", get(handler::tx::get_tx))
-                .route("/height", get(handler::namada_state::get_latest_height))
-                .route(
-                    "/block-index",
-                    get(handler::namada_state::get_block_index),
-                )
-                .with_state(common_state)
+        // NB: the webserver never writes to the database, so all of
+        // its queries can be served by a read replica
+        let db_url = config
+            .database_read_url
+            .clone()
+            .unwrap_or_else(|| config.database_url.clone());
+
+        if let Some(port) = config.metrics_port {
+            metrics::spawn_server(port);
+        }
+
+        let app_state =
+            AppState::new(db_url, config.db_pool, config.cache_max_bytes)
+                .await?;
+
+        if let (Some(addr), Some(token)) =
+            (config.admin_addr, config.admin_token.clone())
+        {
+            admin::spawn_server(addr, token, app_state.clone());
+        }
+
+        let cometbft_client = config
+            .cometbft_url
+            .as_deref()
+            .map(HttpClient::new)
+            .transpose()
+            .context("Failed to build CometBFT client")?;
+
+        let common_state = CommonState::new(
+            app_state.clone(),
+            config.notes_map_only,
+            Duration::from_millis(config.notes_stream_poll_interval),
+            config.caught_up_threshold,
+            Duration::from_secs(config.max_commit_age),
+            cometbft_client,
+        );
+
+        let revalidate = || {
+            middleware::from_fn_with_state(
+                common_state.clone(),
+                conditional::revalidate,
+            )
         };
 
+        let routes = Router::new()
+            .route("/anchor", get(handler::anchor::get_anchor))
+            .route("/anchor/latest", get(handler::anchor::get_latest_anchor))
+            .route(
+                "/anchor/:height",
+                get(handler::anchor::get_anchor_at_height),
+            )
+            .route("/anchors", get(handler::anchor::get_anchors))
+            .route(
+                "/witness",
+                get(handler::witness_map::get_witness)
+                    .route_layer(revalidate()),
+            )
+            .route(
+                "/notes-index",
+                get(handler::notes_index::get_notes_index)
+                    .route_layer(revalidate()),
+            )
+            .route(
+                "/notes-index/stream",
+                get(handler::notes_index::stream_notes_index),
+            )
+            .route("/nullifiers", get(handler::nullifier::get_nullifiers))
+            .route("/tx", get(handler::tx::get_tx))
+            .route("/stream/txs", get(handler::tx::stream_txs))
+            .route("/height", get(handler::namada_state::get_latest_height))
+            .route("/info", get(handler::namada_state::get_info))
+            .route("/block-index", get(handler::namada_state::get_block_index))
+            .route("/block/:height", get(handler::block::get_block))
+            .route(
+                "/height-at-time/:timestamp",
+                get(handler::block::get_height_at_time),
+            );
+
+        let heavy_routes = Router::new()
+            .route(
+                "/commitment-tree",
+                get(handler::tree::get_commitment_tree)
+                    .route_layer(revalidate()),
+            )
+            .route(
+                "/witness-map",
+                get(handler::witness_map::get_witness_map)
+                    .route_layer(revalidate()),
+            )
+            .route(
+                "/witness-map/historical",
+                get(handler::witness_map::get_historical_witness_map)
+                    .route_layer(revalidate()),
+            )
+            .route("/sync", get(handler::sync::get_sync))
+            .route(
+                "/compact-blocks",
+                get(handler::compact_block::get_compact_blocks),
+            );
+
+        let mut routes = Self::limit_rate(
+            routes,
+            config.rate_limit,
+            config.trust_forwarded_for,
+        )
+        .merge(Self::limit_rate(
+            heavy_routes,
+            config.heavy_rate_limit.or(config.rate_limit),
+            config.trust_forwarded_for,
+        ))
+        .with_state(common_state.clone());
+
+        if !config.api_keys.is_empty() {
+            routes = routes.route_layer(middleware::from_fn_with_state(
+                ApiKeys::new(config.api_keys.iter().cloned()),
+                auth::require_api_key,
+            ));
+        }
+
         let cors = CorsLayer::new()
             .allow_origin("*".parse::<HeaderValue>().unwrap())
             .allow_methods(Any)
@@ -68,17 +165,25 @@ impl ApplicationServer {
 
         let router = Router::new()
             .nest("/api/v1", routes)
-            .merge(Router::new().route(
-                "/health",
-                get(|| async { json!({"commit": env!("VERGEN_GIT_SHA").to_string(), "version": env!("CARGO_PKG_VERSION") }).to_string() }),
-            ))
+            .merge(
+                Router::new()
+                    .route("/health", get(handler::namada_state::get_health))
+                    .route("/ready", get(handler::namada_state::get_readiness))
+                    // NB: served without an API key, such that clients
+                    // can be generated before getting one
+                    .route("/api/v1/openapi.json", get(openapi::get_openapi))
+                    .with_state(common_state),
+            )
             .with_state(app_state)
+            .route_layer(middleware::from_fn(metrics::track_requests))
+            .route_layer(middleware::from_fn(telemetry::trace_requests))
             .layer(
                 ServiceBuilder::new()
                     .layer(TraceLayer::new_for_http())
                     .layer(HandleErrorLayer::new(Self::handle_timeout_error))
                     .timeout(Duration::from_secs(*HTTP_TIMEOUT))
                     .layer(cors)
+                    .layer(CompressionLayer::new().no_br())
                     .layer(BufferLayer::new(4096))
                     .layer(RateLimitLayer::new(rps, Duration::from_secs(1)))
                     .layer(SetTraceIdLayer::<String>::new()),
@@ -92,7 +197,7 @@ impl ApplicationServer {
         tracing::info!("🚀 Server has launched on https://{addr}");
 
         axum::Server::bind(&addr)
-            .serve(router.into_make_service())
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
             .with_graceful_shutdown(Self::shutdown_signal())
             .await
             .context("The server shutdown unexpectedly")?;
@@ -100,27 +205,40 @@ impl ApplicationServer {
         Ok(())
     }
 
+    /// Rate limit each client of the routes in `router` to `rate_limit`,
+    /// if any.
+    fn limit_rate<S>(
+        router: Router<S>,
+        rate_limit: Option<RateLimit>,
+        trust_forwarded_for: bool,
+    ) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        match rate_limit {
+            Some(limit) => router.route_layer(middleware::from_fn_with_state(
+                RateLimiter::new(limit, trust_forwarded_for),
+                rate_limit::limit_requests,
+            )),
+            None => router,
+        }
+    }
+
     /// Adds a custom handler for tower's `TimeoutLayer`, see https://docs.rs/axum/latest/axum/middleware/index.html#commonly-used-middleware.
-    async fn handle_timeout_error(
-        err: BoxError,
-    ) -> (StatusCode, Json<serde_json::Value>) {
+    async fn handle_timeout_error(err: BoxError) -> Response {
         if err.is::<tower::timeout::error::Elapsed>() {
-            (
-                StatusCode::REQUEST_TIMEOUT,
-                Json(json!({
-                    "error":
-                        format!(
-                            "request took longer than the configured {} second timeout",
-                            *HTTP_TIMEOUT
-                        )
-                })),
+            ApiErrorResponse::send_with_details(
+                ErrorCode::Timeout,
+                format!(
+                    "Request took longer than the configured {} second timeout",
+                    *HTTP_TIMEOUT
+                ),
+                json!({ "timeout": *HTTP_TIMEOUT }),
             )
         } else {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": format!("unhandled internal error: {}", err)
-                })),
+            ApiErrorResponse::send(
+                ErrorCode::Internal,
+                format!("Unhandled internal error: {err}"),
             )
         }
     }
@@ -134,16 +252,10 @@ impl ApplicationServer {
         tracing::info!("Interrupt signal received, shutting down server");
     }
 
-    async fn handle_404() -> impl IntoResponse {
-        (
-            StatusCode::NOT_FOUND,
-            axum::response::Json(serde_json::json!(
-                {
-                    "error": {
-                        "message": "The requested resource does not exist on this server!",
-                    }
-                }
-            )),
+    async fn handle_404() -> Response {
+        ApiErrorResponse::send(
+            ErrorCode::NotFound,
+            "The requested resource does not exist on this server",
         )
     }
 }
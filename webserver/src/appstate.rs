@@ -1,25 +1,41 @@
-use std::env;
 use std::time::Duration;
 
 use anyhow::Context;
 use deadpool_diesel::postgres::{Object, Pool as DbPool};
+#[cfg(feature = "test-utils")]
+use orm::memory::MemoryDb;
+use shared::config::DbPoolConfig;
+
+use crate::cache::ResponseCache;
+
+#[derive(Clone)]
+enum Db {
+    Postgres(DbPool),
+    /// Tables written by the in-memory storage backend of the crawler.
+    #[cfg(feature = "test-utils")]
+    Memory(MemoryDb),
+}
 
 #[derive(Clone)]
 pub struct AppState {
-    db: DbPool,
+    db: Db,
+    cache: ResponseCache,
 }
 
 impl AppState {
-    pub async fn new(db_url: String) -> anyhow::Result<Self> {
-        let max_pool_size = env::var("DATABASE_POOL_SIZE")
-            .unwrap_or_else(|_| 8.to_string())
-            .parse::<usize>()
-            .unwrap_or(8_usize);
-
-        let max_conn_retries = env::var("DATABASE_MAX_CONN_RETRIES")
-            .unwrap_or_else(|_| 5.to_string())
-            .parse::<u32>()
-            .unwrap_or(5);
+    pub async fn new(
+        db_url: String,
+        db_pool: DbPoolConfig,
+        cache_max_bytes: usize,
+    ) -> anyhow::Result<Self> {
+        // NB: the crawler can index to RocksDB, but only Postgres
+        // databases can be served
+        if db_url.starts_with("rocksdb://") {
+            anyhow::bail!(
+                "The webserver cannot read from RocksDB, index to Postgres \
+                 instead"
+            );
+        }
 
         let pool = tryhard::retry_fn(|| async {
             let pool_manager = deadpool_diesel::Manager::from_config(
@@ -30,23 +46,58 @@ impl AppState {
                         deadpool_diesel::RecyclingMethod::Verified,
                 },
             );
+            // NB: timeouts are only enforced with a runtime set
             DbPool::builder(pool_manager)
-                .max_size(max_pool_size)
+                .max_size(db_pool.database_pool_size)
+                .runtime(deadpool_diesel::Runtime::Tokio1)
+                .wait_timeout(db_pool.wait_timeout())
+                .create_timeout(db_pool.connect_timeout())
                 .build()
                 .context("Failed to build Postgres db pool")
         })
-        .retries(max_conn_retries)
+        .retries(db_pool.database_max_conn_retries)
         .exponential_backoff(Duration::from_millis(100))
         .max_delay(Duration::from_secs(5))
         .await?;
 
-        Ok(Self { db: pool })
+        Ok(Self {
+            db: Db::Postgres(pool),
+            cache: ResponseCache::new(cache_max_bytes),
+        })
+    }
+
+    /// Serve the data indexed in `db` instead of a Postgres database.
+    #[cfg(feature = "test-utils")]
+    pub fn in_memory(db: MemoryDb, cache_max_bytes: usize) -> Self {
+        Self {
+            db: Db::Memory(db),
+            cache: ResponseCache::new(cache_max_bytes),
+        }
     }
 
     pub async fn get_db_connection(&self) -> anyhow::Result<Object> {
-        self.db
-            .get()
-            .await
-            .context("Failed to get db connection handle from deadpool")
+        match &self.db {
+            Db::Postgres(pool) => pool
+                .get()
+                .await
+                .context("Failed to get db connection handle from deadpool"),
+            #[cfg(feature = "test-utils")]
+            Db::Memory(_) => {
+                anyhow::bail!("The in-memory database has no connections")
+            }
+        }
+    }
+
+    /// In-memory database to query instead of Postgres, if any.
+    #[cfg(feature = "test-utils")]
+    pub fn memory_db(&self) -> Option<&MemoryDb> {
+        match &self.db {
+            Db::Memory(db) => Some(db),
+            Db::Postgres(_) => None,
+        }
+    }
+
+    pub fn cache(&self) -> &ResponseCache {
+        &self.cache
     }
 }
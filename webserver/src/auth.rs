@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+/// Header carrying the API key of a request.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API keys accepted by the webserver.
+#[derive(Clone)]
+pub struct ApiKeys(Arc<HashSet<String>>);
+
+impl ApiKeys {
+    pub fn new(keys: impl IntoIterator<Item = String>) -> Self {
+        Self(Arc::new(keys.into_iter().collect()))
+    }
+}
+
+/// API key of a request, inserted in its extensions once validated.
+#[derive(Clone, Debug)]
+pub struct ApiKey(pub Arc<str>);
+
+/// Middleware rejecting requests without one of the accepted API keys.
+pub async fn require_api_key<B>(
+    State(api_keys): State<ApiKeys>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let api_key = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .map(|value| value.to_str().unwrap_or_default().to_owned());
+
+    match api_key {
+        Some(api_key) if api_keys.0.contains(&api_key) => {
+            req.extensions_mut().insert(ApiKey(api_key.into()));
+            next.run(req).await
+        }
+        Some(_) => {
+            ApiErrorResponse::send(ErrorCode::Unauthorized, "Invalid API key")
+        }
+        None => ApiErrorResponse::send(
+            ErrorCode::Unauthorized,
+            format!("Missing API key in the {API_KEY_HEADER} header"),
+        ),
+    }
+}
@@ -0,0 +1,289 @@
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::compression::ContentCoding;
+use crate::encoding::Encoding;
+use crate::metrics;
+
+/// Key of a cached response, along with the query parameters it was
+/// computed from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CacheKey {
+    Tree(u64),
+    WitnessMap(u64),
+    HistoricalWitnessMap(u64),
+    NotesIndex {
+        from_block_height: Option<u64>,
+        to_block_height: u64,
+        is_fee_unshielding: Option<bool>,
+        limit: Option<u64>,
+        offset: u64,
+    },
+    /// Response body to the request cached under `key`, encoded with
+    /// `encoding` and compressed with `coding`.
+    Encoded {
+        key: Box<CacheKey>,
+        encoding: Encoding,
+        coding: ContentCoding,
+    },
+}
+
+impl CacheKey {
+    /// Key of the response body to this request, encoded with `encoding`
+    /// and compressed with `coding`.
+    pub fn encoded(self, encoding: Encoding, coding: ContentCoding) -> Self {
+        CacheKey::Encoded {
+            key: Box::new(self),
+            encoding,
+            coding,
+        }
+    }
+
+    /// Highest block height the cached data depends on.
+    fn block_height(&self) -> u64 {
+        match self {
+            CacheKey::Tree(block_height)
+            | CacheKey::WitnessMap(block_height)
+            | CacheKey::HistoricalWitnessMap(block_height) => *block_height,
+            CacheKey::NotesIndex {
+                to_block_height, ..
+            } => *to_block_height,
+            CacheKey::Encoded { key, .. } => key.block_height(),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            CacheKey::Tree(_) => "commitment_tree",
+            CacheKey::WitnessMap(_) => "witness_map",
+            CacheKey::HistoricalWitnessMap(_) => "historical_witness_map",
+            CacheKey::NotesIndex { .. } => "notes_index",
+            CacheKey::Encoded { key, .. } => key.kind(),
+        }
+    }
+}
+
+/// Approximate number of bytes taken up by a cached value.
+pub trait CacheWeight {
+    fn weight(&self) -> usize;
+}
+
+impl CacheWeight for u8 {
+    fn weight(&self) -> usize {
+        1
+    }
+}
+
+impl CacheWeight for u64 {
+    fn weight(&self) -> usize {
+        size_of::<u64>()
+    }
+}
+
+impl CacheWeight for bool {
+    fn weight(&self) -> usize {
+        size_of::<bool>()
+    }
+}
+
+impl<T: CacheWeight> CacheWeight for Vec<T> {
+    fn weight(&self) -> usize {
+        self.iter().map(CacheWeight::weight).sum()
+    }
+}
+
+impl<T: CacheWeight> CacheWeight for Option<T> {
+    fn weight(&self) -> usize {
+        self.as_ref().map_or(0, CacheWeight::weight)
+    }
+}
+
+impl<A: CacheWeight, B: CacheWeight> CacheWeight for (A, B) {
+    fn weight(&self) -> usize {
+        self.0.weight() + self.1.weight()
+    }
+}
+
+impl CacheWeight for (u64, u64, u64, u64, bool) {
+    fn weight(&self) -> usize {
+        size_of::<Self>()
+    }
+}
+
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    weight: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Last synced height observed in the database. Nothing is cached
+    /// until it is known.
+    synced_height: Option<u64>,
+    /// Bumped every time the cache is invalidated, such that values
+    /// loaded before then are not inserted.
+    generation: u64,
+    entries: HashMap<CacheKey, Entry>,
+    /// Keys of the entries, from the least to the most recently used.
+    lru: BTreeMap<u64, CacheKey>,
+    next_use: u64,
+    used_bytes: usize,
+}
+
+impl Inner {
+    fn touch(&mut self, key: &CacheKey) -> Option<Arc<dyn Any + Send + Sync>> {
+        let next_use = self.next_use;
+        let entry = self.entries.get_mut(key)?;
+
+        self.lru.remove(&entry.last_used);
+        self.lru.insert(next_use, key.clone());
+        entry.last_used = next_use;
+        self.next_use += 1;
+
+        Some(entry.value.clone())
+    }
+
+    fn insert(
+        &mut self,
+        key: CacheKey,
+        value: Arc<dyn Any + Send + Sync>,
+        weight: usize,
+        max_bytes: usize,
+    ) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.lru.remove(&entry.last_used);
+            self.used_bytes -= entry.weight;
+        }
+
+        while self.used_bytes + weight > max_bytes {
+            let Some((_, evicted)) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&evicted) {
+                self.used_bytes -= entry.weight;
+            }
+        }
+
+        self.lru.insert(self.next_use, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                weight,
+                last_used: self.next_use,
+            },
+        );
+        self.next_use += 1;
+        self.used_bytes += weight;
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+        self.used_bytes = 0;
+        self.generation += 1;
+    }
+}
+
+/// In-process cache of the data served by the commitment tree, witness
+/// map and notes map endpoints, bounded by the bytes it takes up.
+///
+/// Only data up to the last synced height is cached, and the whole cache
+/// is invalidated whenever the last synced height changes.
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Arc<Mutex<Inner>>,
+    max_bytes: usize,
+}
+
+impl ResponseCache {
+    /// Create a new cache, holding up to `max_bytes` worth of data. A
+    /// cache with no room disables caching.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            max_bytes,
+        }
+    }
+
+    /// Record the last synced height, invalidating all cached data if it
+    /// differs from the previous one.
+    pub fn set_synced_height(&self, block_height: u64) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.synced_height == Some(block_height) {
+            return;
+        }
+
+        inner.clear();
+        inner.synced_height = Some(block_height);
+        metrics::CACHE_SIZE_BYTES.set(0);
+    }
+
+    /// Drop all cached data, e.g. after the indexed data was modified
+    /// without the last synced height changing.
+    pub fn invalidate(&self) {
+        self.inner.lock().unwrap().clear();
+        metrics::CACHE_SIZE_BYTES.set(0);
+    }
+
+    /// Return the value cached under `key`, or load it with `load` and
+    /// cache it.
+    pub async fn get_or_load<T, F, Fut>(
+        &self,
+        key: CacheKey,
+        load: F,
+    ) -> anyhow::Result<T>
+    where
+        T: CacheWeight + Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        if self.max_bytes == 0 {
+            return load().await;
+        }
+
+        let generation = {
+            let mut inner = self.inner.lock().unwrap();
+
+            let cached = inner
+                .touch(&key)
+                .and_then(|value| value.downcast::<T>().ok());
+            if let Some(value) = cached {
+                metrics::CACHE_REQUESTS
+                    .with_label_values(&[key.kind(), "hit"])
+                    .inc();
+                return Ok(T::clone(&value));
+            }
+
+            inner.generation
+        };
+
+        metrics::CACHE_REQUESTS
+            .with_label_values(&[key.kind(), "miss"])
+            .inc();
+
+        let value = load().await?;
+        let weight = value.weight();
+
+        let mut inner = self.inner.lock().unwrap();
+
+        // NB: the data may have changed while it was being loaded, if
+        // the cache got invalidated in the meantime
+        let cacheable = inner.generation == generation
+            && weight <= self.max_bytes
+            && inner
+                .synced_height
+                .is_some_and(|height| key.block_height() <= height);
+
+        if cacheable {
+            inner.insert(key, Arc::new(value.clone()), weight, self.max_bytes);
+            metrics::CACHE_SIZE_BYTES.set(inner.used_bytes as i64);
+        }
+
+        Ok(value)
+    }
+}
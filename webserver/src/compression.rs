@@ -0,0 +1,185 @@
+use std::convert::Infallible;
+use std::io::{self, Write};
+
+use anyhow::Context;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, header};
+use axum::response::{IntoResponse, Response};
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use namada_core::borsh::BorshSerialize;
+use serde::Serialize;
+
+use crate::cache::CacheWeight;
+use crate::encoding::{Encoding, NEGOTIATED_HEADERS};
+
+/// Compression level of zstd compressed bodies. Bodies are compressed
+/// once and then served from the cache, so a higher level than the
+/// default pays off.
+const ZSTD_LEVEL: i32 = 9;
+
+/// Content coding of a response body, negotiated from the
+/// `Accept-Encoding` header. zstd is preferred over gzip, and gzip over
+/// deflate, among the codings accepted by the client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ContentCoding {
+    Zstd,
+    Gzip,
+    Deflate,
+    #[default]
+    Identity,
+}
+
+impl ContentCoding {
+    /// Codings in order of preference.
+    const PREFERRED: [ContentCoding; 3] = [
+        ContentCoding::Zstd,
+        ContentCoding::Gzip,
+        ContentCoding::Deflate,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ContentCoding::Zstd => "zstd",
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Deflate => "deflate",
+            ContentCoding::Identity => "identity",
+        }
+    }
+
+    /// Compress `body` with this coding.
+    pub fn compress(self, body: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            ContentCoding::Zstd => zstd::encode_all(body, ZSTD_LEVEL),
+            ContentCoding::Gzip => {
+                let mut encoder =
+                    GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            // NB: the deflate content coding is the zlib format
+            ContentCoding::Deflate => {
+                let mut encoder =
+                    ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            ContentCoding::Identity => Ok(body.to_vec()),
+        }
+    }
+
+    fn negotiate(headers: &HeaderMap) -> Self {
+        // NB: quality values only tell apart the accepted codings from
+        // the refused ones, the server preference decides among them
+        let accepted = headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|coding| {
+                let mut params = coding.split(';');
+                let name = params.next()?.trim().to_ascii_lowercase();
+                let quality = params
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .next()
+                    .map_or(1.0, |q| q.trim().parse::<f32>().unwrap_or(0.0));
+                Some((name, quality))
+            })
+            .collect::<Vec<_>>();
+
+        let quality_of = |name: &str| {
+            accepted
+                .iter()
+                .find(|(coding, _)| coding == name)
+                .or_else(|| accepted.iter().find(|(coding, _)| coding == "*"))
+                .map_or(0.0, |(_, quality)| *quality)
+        };
+
+        Self::PREFERRED
+            .into_iter()
+            .find(|coding| quality_of(coding.name()) > 0.0)
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ContentCoding
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self::negotiate(&parts.headers))
+    }
+}
+
+/// Response body encoded and compressed ahead of time, such that it can
+/// be cached and served as is.
+#[derive(Clone)]
+pub struct EncodedBody {
+    encoding: Encoding,
+    coding: ContentCoding,
+    bytes: Bytes,
+}
+
+impl EncodedBody {
+    /// Encode `body` with `encoding`, and compress it with `coding`.
+    pub fn new<T>(
+        body: &T,
+        encoding: Encoding,
+        coding: ContentCoding,
+    ) -> anyhow::Result<Self>
+    where
+        T: Serialize + BorshSerialize,
+    {
+        let bytes = tokio::task::block_in_place(|| {
+            let encoded = encoding.encode(body)?;
+            coding
+                .compress(&encoded)
+                .context("Failed to compress response body")
+        })?;
+
+        Ok(Self {
+            encoding,
+            coding,
+            bytes: Bytes::from(bytes),
+        })
+    }
+}
+
+impl CacheWeight for EncodedBody {
+    fn weight(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl IntoResponse for EncodedBody {
+    fn into_response(self) -> Response {
+        let mut response = (
+            [
+                (header::CONTENT_TYPE, self.encoding.content_type()),
+                (header::VARY, NEGOTIATED_HEADERS),
+            ],
+            self.bytes,
+        )
+            .into_response();
+
+        // NB: the compression layer leaves alone bodies which already
+        // have a content coding
+        if self.coding != ContentCoding::Identity {
+            response.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                header::HeaderValue::from_static(self.coding.name()),
+            );
+        }
+
+        response
+    }
+}
@@ -0,0 +1,187 @@
+use axum::extract::{FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, NaiveDateTime};
+use namada_core::hash::Hash;
+
+use crate::compression::ContentCoding;
+use crate::encoding::{Encoding, NEGOTIATED_HEADERS};
+use crate::state::common::CommonState;
+
+/// Format of HTTP dates, in GMT.
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Validators of the responses of the state endpoints, derived from the
+/// last committed block.
+struct Validators {
+    /// Weak entity tag, hashing the last synced height and the time it
+    /// was committed at, such that blocks committed again after a
+    /// rollback get a new tag. The request path and query, along with
+    /// the negotiated encoding and content coding, are hashed as well,
+    /// since they all change the response body.
+    etag: String,
+    /// Time at which the last synced height was committed, served as
+    /// the `Last-Modified` date. The timestamp of the block itself is
+    /// not used, since the responses change when blocks get committed
+    /// rather than produced, and a block committed again after a reorg
+    /// may be older than one the client already holds, which would have
+    /// it keep a stale response.
+    committed_at: NaiveDateTime,
+}
+
+impl Validators {
+    fn new(
+        block_height: u64,
+        committed_at: NaiveDateTime,
+        representation: &Representation,
+    ) -> Self {
+        let Representation {
+            path_and_query,
+            encoding,
+            coding,
+        } = representation;
+
+        let tagged = format!(
+            "{block_height}:{}:{path_and_query}:{encoding:?}:{coding:?}",
+            committed_at.and_utc().timestamp_micros()
+        );
+        let etag = format!("W/\"{}\"", Hash::sha256(tagged.as_bytes()));
+
+        Self { etag, committed_at }
+    }
+
+    /// Whether the client already holds the current response, according
+    /// to the conditional headers in `headers`.
+    fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        // NB: `If-Modified-Since` is ignored if `If-None-Match` is
+        // present, as the latter is more precise
+        if headers.contains_key(header::IF_NONE_MATCH) {
+            return headers
+                .get_all(header::IF_NONE_MATCH)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .any(|etag| etag == "*" || weak_eq(etag, &self.etag));
+        }
+
+        headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .is_some_and(|modified_since| {
+                self.committed_at.and_utc().timestamp()
+                    <= modified_since.timestamp()
+            })
+    }
+
+    fn insert_into(&self, headers: &mut HeaderMap) {
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+
+        let last_modified =
+            self.committed_at.format(HTTP_DATE_FORMAT).to_string();
+        if let Ok(last_modified) = HeaderValue::from_str(&last_modified) {
+            headers.insert(header::LAST_MODIFIED, last_modified);
+        }
+    }
+}
+
+/// Request details selecting the body of a response.
+struct Representation {
+    path_and_query: String,
+    encoding: Encoding,
+    coding: ContentCoding,
+}
+
+impl Representation {
+    /// Read the representation requested in `parts`. Returns `None` if
+    /// the requested encoding is invalid, which the handler rejects.
+    async fn negotiate(parts: &mut Parts, state: &CommonState) -> Option<Self> {
+        let encoding = Encoding::from_request_parts(parts, state).await.ok()?;
+        let Ok(coding) = ContentCoding::from_request_parts(parts, state).await;
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map_or_else(|| parts.uri.path().to_owned(), ToString::to_string);
+
+        Some(Self {
+            path_and_query,
+            encoding,
+            coding,
+        })
+    }
+}
+
+/// Compare two entity tags, ignoring whether they are weak.
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// Middleware answering conditional requests to the endpoints whose
+/// responses only change once a new block gets committed.
+///
+/// Successful responses are tagged with an `ETag` and a `Last-Modified`
+/// date derived from the last committed block, and requests already
+/// holding them are answered with a 304, without running the handler.
+pub async fn revalidate<B>(
+    State(state): State<CommonState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let representation = Representation::negotiate(&mut parts, &state).await;
+    let req = Request::from_parts(parts, body);
+
+    let Some(representation) = representation else {
+        return next.run(req).await;
+    };
+
+    let validators = match state.namada_state_service.get_sync_progress().await
+    {
+        Ok(sync_progress) => {
+            sync_progress.map(|(block_height, _, committed_at)| {
+                Validators::new(block_height.0, committed_at, &representation)
+            })
+        }
+        Err(err) => {
+            tracing::warn!(
+                reason = err.to_string(),
+                "Failed to read the last committed block, skipping \
+                 conditional request handling"
+            );
+            None
+        }
+    };
+
+    // NB: nothing can be cached until a block is committed
+    let Some(validators) = validators else {
+        return next.run(req).await;
+    };
+
+    // NB: the validators are read before running the handler, such that
+    // a block committed in between only causes a needless refetch
+    if validators.is_fresh(req.headers()) {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        validators.insert_into(response.headers_mut());
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static(NEGOTIATED_HEADERS));
+        return response;
+    }
+
+    let mut response = next.run(req).await;
+
+    if response.status().is_success() {
+        validators.insert_into(response.headers_mut());
+    }
+
+    response
+}
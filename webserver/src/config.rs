@@ -1,3 +1,10 @@
+use std::net::SocketAddr;
+
+use shared::config::DbPoolConfig;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::rate_limit::RateLimit;
+
 #[derive(clap::Parser)]
 pub struct AppConfig {
     #[clap(long, env, default_value = "5000")]
@@ -6,6 +13,93 @@ pub struct AppConfig {
     #[clap(long, env)]
     pub database_url: String,
 
+    /// Link to a read replica of the Postgres database. If set, all
+    /// queries are sent to the replica rather than to `database_url`,
+    /// which the crawler writes to.
+    #[clap(long, env)]
+    pub database_read_url: Option<String>,
+
+    #[command(flatten)]
+    pub db_pool: DbPoolConfig,
+
     #[clap(long, env)]
     pub rps: Option<u64>,
+
+    /// API keys accepted in the `X-API-Key` header. If any are given,
+    /// requests to the API without one of them are rejected.
+    #[clap(long, env, value_delimiter = ',')]
+    pub api_keys: Vec<String>,
+
+    /// Rate limit applied to each API key, or to each client IP if no
+    /// key was sent, formatted as `<requests per second>[:<burst>]`
+    #[clap(long, env)]
+    pub rate_limit: Option<RateLimit>,
+
+    /// Rate limit of the commitment tree, witness map and sync routes,
+    /// which are the most expensive to serve. Defaults to `rate_limit`.
+    #[clap(long, env)]
+    pub heavy_rate_limit: Option<RateLimit>,
+
+    /// Identify clients by the first address in the `X-Forwarded-For`
+    /// header, when the webserver sits behind a trusted reverse proxy
+    #[clap(long, env)]
+    pub trust_forwarded_for: bool,
+
+    /// Port to serve prometheus metrics on, if any
+    #[clap(long, env)]
+    pub metrics_port: Option<u16>,
+
+    /// Address on which the admin API is served (e.g. `127.0.0.1:5001`),
+    /// separately from the public API
+    #[clap(long, env, requires = "admin_token")]
+    pub admin_addr: Option<SocketAddr>,
+
+    /// Bearer token required in the `Authorization` header of every
+    /// request to the admin API
+    #[clap(long, env)]
+    pub admin_token: Option<String>,
+
+    /// Maximum level of the logged events (e.g. `info`), or `off`
+    #[clap(long, env, default_value_t = LevelFilter::DEBUG)]
+    pub log_level: LevelFilter,
+
+    /// OTLP collector endpoint (e.g. `http://localhost:4317`) to which
+    /// tracing spans are exported over gRPC, if any
+    #[clap(long, env)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Maximum number of bytes of commitment trees, witness maps and
+    /// notes maps kept in memory, to serve repeated queries without
+    /// hitting the database. Zero disables caching.
+    #[clap(long, env, default_value_t = 256 * 1024 * 1024)]
+    pub cache_max_bytes: usize,
+
+    /// The crawler only persists the notes map, so commitment tree
+    /// and witness map queries cannot be served
+    #[clap(long, env)]
+    pub notes_map_only: bool,
+
+    /// Interval at which notifications of new notes and masp txs are
+    /// checked for, to push them to subscribers of the streaming
+    /// endpoints, and at which the last synced height is polled to
+    /// invalidate the response cache, in milliseconds
+    #[clap(long, env, default_value_t = 1000)]
+    pub notes_stream_poll_interval: u64,
+
+    /// Maximum number of blocks the indexer may lag behind the chain tip
+    /// while still being reported as ready by the readiness endpoint
+    #[clap(long, env, default_value_t = 10)]
+    pub caught_up_threshold: u64,
+
+    /// Maximum time since the crawler last committed a block, in
+    /// seconds, past which the readiness endpoint reports the index as
+    /// stale, e.g. because the crawler halted
+    #[clap(long, env, default_value_t = 300)]
+    pub max_commit_age: u64,
+
+    /// CometBFT RPC endpoint queried for the chain tip by the readiness
+    /// endpoint. If absent, the chain tip last observed by the crawler
+    /// is used instead.
+    #[clap(long, env)]
+    pub cometbft_url: Option<String>,
 }
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct AnchorQueryParams {
+    #[validate(range(min = 1))]
+    pub height: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct AnchorsQueryParams {
+    /// Block height (inclusive) of the first anchor to return
+    #[validate(range(min = 1))]
+    pub from: u64,
+    /// Block height (inclusive) of the last anchor to return
+    #[validate(range(min = 1))]
+    pub to: u64,
+}
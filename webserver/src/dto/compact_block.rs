@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct CompactBlocksQueryParams {
+    /// Block height (inclusive) of the first compact block to return
+    #[validate(range(min = 1))]
+    pub from: u64,
+    /// Block height (inclusive) of the last compact block to return
+    #[validate(range(min = 1))]
+    pub to: u64,
+}
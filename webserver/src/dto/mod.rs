@@ -1,4 +1,8 @@
+pub mod anchor;
+pub mod compact_block;
 pub mod notes_index;
+pub mod nullifier;
+pub mod sync;
 pub mod tree;
 pub mod txs;
 pub mod witness;
@@ -3,6 +3,24 @@ use validator::Validate;
 
 #[derive(Clone, Serialize, Deserialize, Validate)]
 pub struct NotesIndexQueryParams {
+    /// Upper bound (inclusive) on the block height of the returned notes
     #[validate(range(min = 1))]
     pub height: u64,
+    /// Lower bound (inclusive) on the block height of the returned notes
+    pub from_height: Option<u64>,
+    /// Only return notes of fee unshieldings if `true`, or only regular
+    /// notes if `false`
+    pub is_fee_unshielding: Option<bool>,
+    /// Maximum number of notes to return
+    #[validate(range(min = 1))]
+    pub limit: Option<u64>,
+    /// Number of notes to skip
+    pub offset: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct NotesStreamQueryParams {
+    /// Block height (inclusive) of the first notes to push. If absent,
+    /// only notes committed after subscribing are pushed.
+    pub from_height: Option<u64>,
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct NullifiersQueryParams {
+    /// Lower bound (inclusive) on the block height of the returned
+    /// nullifiers
+    pub from: u64,
+    /// Upper bound (inclusive) on the block height of the returned
+    /// nullifiers. Defaults to the last synced height.
+    pub to: Option<u64>,
+    /// Maximum number of nullifiers to return
+    #[validate(range(min = 1))]
+    pub limit: Option<u64>,
+    /// Number of nullifiers to skip
+    pub offset: Option<u64>,
+}
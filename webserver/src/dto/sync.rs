@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::encoding::Encoding;
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct SyncQueryParams {
+    /// Block height (inclusive) of the first block to sync
+    #[validate(range(min = 1))]
+    pub from: u64,
+    /// Block height (inclusive) of the last block to sync
+    #[validate(range(min = 1))]
+    pub to: u64,
+    /// Encoding of the response body. Takes precedence over the
+    /// negotiated encoding, if present.
+    pub encoding: Option<Encoding>,
+}
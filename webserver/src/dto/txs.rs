@@ -7,4 +7,9 @@ pub struct TxQueryParams {
     pub height: u64,
     #[validate(range(min = 0, max = 30))]
     pub height_offset: u64,
+    /// Maximum number of batches of txs to return
+    #[validate(range(min = 1))]
+    pub limit: Option<u64>,
+    /// Number of batches of txs to skip
+    pub offset: Option<u64>,
 }
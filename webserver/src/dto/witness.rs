@@ -6,3 +6,13 @@ pub struct WitnessMapQueryParams {
     #[validate(range(min = 1))]
     pub height: u64,
 }
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct WitnessQueryParams {
+    /// Position of the note in the commitment tree
+    pub note_position: u64,
+    /// Block height of the anchor to build the witness against. If
+    /// absent, the last synced height is used.
+    #[validate(range(min = 1))]
+    pub height: Option<u64>,
+}
@@ -0,0 +1,129 @@
+use anyhow::Context;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::{Json, async_trait};
+use namada_core::borsh::{BorshSerialize, BorshSerializeExt};
+use serde::{Deserialize, Serialize};
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+/// Media type of JSON encoded response bodies.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Media type of borsh encoded response bodies.
+pub const BORSH_CONTENT_TYPE: &str = "application/x-borsh";
+
+/// Request headers which negotiated response bodies vary with, to be
+/// sent in the `Vary` header, such that shared caches never serve a body
+/// in another encoding or content coding than the requested one.
+pub const NEGOTIATED_HEADERS: &str = "Accept, Accept-Encoding";
+
+/// Encoding of a response body. Negotiated from the `format` query
+/// param if present, or else from the `Accept` header, and defaults to
+/// JSON.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Json,
+    Borsh,
+}
+
+#[derive(Deserialize)]
+struct FormatQueryParams {
+    format: Option<Encoding>,
+}
+
+impl Encoding {
+    /// Media type of response bodies in this encoding.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => JSON_CONTENT_TYPE,
+            Encoding::Borsh => BORSH_CONTENT_TYPE,
+        }
+    }
+
+    /// Encode `body` in this encoding.
+    pub fn encode<T>(self, body: &T) -> anyhow::Result<Vec<u8>>
+    where
+        T: Serialize + BorshSerialize,
+    {
+        match self {
+            Encoding::Json => serde_json::to_vec(body)
+                .context("Failed to serialize response body to JSON"),
+            Encoding::Borsh => Ok(body.serialize_to_vec()),
+        }
+    }
+
+    /// Build a response out of `body`, in this encoding.
+    ///
+    /// The response varies with the `Accept` header, which the encoding
+    /// is negotiated from, and with the `Accept-Encoding` header, which
+    /// the compression layer picks a content coding from.
+    pub fn respond<T>(self, body: T) -> Response
+    where
+        T: Serialize + BorshSerialize,
+    {
+        let vary = [(header::VARY, NEGOTIATED_HEADERS)];
+
+        match self {
+            Encoding::Json => (vary, Json(body)).into_response(),
+            Encoding::Borsh => (
+                vary,
+                [(header::CONTENT_TYPE, BORSH_CONTENT_TYPE)],
+                body.serialize_to_vec(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Encoding
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(FormatQueryParams { format }) =
+            Query::from_request_parts(parts, state)
+                .await
+                .map_err(|err| {
+                    ApiErrorResponse::send(
+                        ErrorCode::InvalidRequest,
+                        err.body_text(),
+                    )
+                })?;
+
+        if let Some(format) = format {
+            return Ok(format);
+        }
+
+        // NB: quality values are ignored, borsh is picked as long as
+        // the client accepts it at all
+        let accepts_borsh = parts
+            .headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|media_range| media_range.split(';').next())
+            .any(|media_type| {
+                media_type.trim().eq_ignore_ascii_case(BORSH_CONTENT_TYPE)
+            });
+
+        Ok(if accepts_borsh {
+            Encoding::Borsh
+        } else {
+            Encoding::Json
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+#[derive(Error, Debug)]
+pub enum AnchorError {
+    #[error("Commitment trees are not indexed in notes map only mode")]
+    Unavailable,
+    #[error("Invalid range {0} -- {1}")]
+    InvalidRange(u64, u64),
+    #[error("Range {0} -- {1} spans more than {2} blocks")]
+    RangeTooLarge(u64, u64, u64),
+    #[error("Block height {0} has not been synced yet")]
+    HeightNotSynced(u64),
+    #[error("No blocks have been synced yet")]
+    NothingSynced,
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for AnchorError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+
+        match self {
+            AnchorError::Unavailable => {
+                ApiErrorResponse::send(ErrorCode::Unavailable, message)
+            }
+            AnchorError::InvalidRange(from, to) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::InvalidRange,
+                    message,
+                    json!({ "from": from, "to": to }),
+                )
+            }
+            AnchorError::RangeTooLarge(from, to, max_blocks) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::RangeTooLarge,
+                    message,
+                    json!({ "from": from, "to": to, "max_blocks": max_blocks }),
+                )
+            }
+            AnchorError::HeightNotSynced(height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::HeightNotSynced,
+                    message,
+                    json!({ "height": height }),
+                )
+            }
+            AnchorError::NothingSynced => {
+                ApiErrorResponse::send(ErrorCode::NothingSynced, message)
+            }
+            AnchorError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
+    }
+}
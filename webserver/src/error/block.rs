@@ -0,0 +1,53 @@
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+#[derive(Error, Debug)]
+pub enum BlockError {
+    #[error("Block height {0} has not been indexed")]
+    HeightNotIndexed(u64),
+    #[error("No indexed block was committed at or before {0}")]
+    NoBlockAtTime(String),
+    #[error(
+        "Invalid timestamp {0}, expected seconds since the unix epoch or an \
+         RFC 3339 date"
+    )]
+    InvalidTimestamp(String),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for BlockError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+
+        match self {
+            BlockError::HeightNotIndexed(height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::HeightNotIndexed,
+                    message,
+                    json!({ "height": height }),
+                )
+            }
+            BlockError::NoBlockAtTime(timestamp) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::NoBlockAtTime,
+                    message,
+                    json!({ "timestamp": timestamp }),
+                )
+            }
+            BlockError::InvalidTimestamp(timestamp) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::InvalidTimestamp,
+                    message,
+                    json!({ "timestamp": timestamp }),
+                )
+            }
+            BlockError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+#[derive(Error, Debug)]
+pub enum CompactBlockError {
+    #[error("Invalid range {0} -- {1}")]
+    InvalidRange(u64, u64),
+    #[error("Range {0} -- {1} spans more than {2} blocks")]
+    RangeTooLarge(u64, u64, u64),
+    #[error("Block height {0} has not been synced yet")]
+    HeightNotSynced(u64),
+    #[error("Block height {0} was pruned, only blocks above {1} are kept")]
+    Pruned(u64, u64),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for CompactBlockError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+
+        match self {
+            CompactBlockError::InvalidRange(from, to) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::InvalidRange,
+                    message,
+                    json!({ "from": from, "to": to }),
+                )
+            }
+            CompactBlockError::RangeTooLarge(from, to, max_blocks) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::RangeTooLarge,
+                    message,
+                    json!({ "from": from, "to": to, "max_blocks": max_blocks }),
+                )
+            }
+            CompactBlockError::HeightNotSynced(height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::HeightNotSynced,
+                    message,
+                    json!({ "height": height }),
+                )
+            }
+            CompactBlockError::Pruned(height, pruned_height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::Pruned,
+                    message,
+                    json!({ "height": height, "pruned_height": pruned_height }),
+                )
+            }
+            CompactBlockError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
+    }
+}
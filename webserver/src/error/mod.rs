@@ -1,6 +1,11 @@
+pub mod anchor;
 pub mod api;
+pub mod block;
+pub mod compact_block;
 pub mod namada_state;
 pub mod notes_index;
+pub mod nullifier;
+pub mod sync;
 pub mod tree;
 pub mod tx;
 pub mod witness_map;
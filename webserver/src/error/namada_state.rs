@@ -1,8 +1,7 @@
-use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 
-use crate::response::api::ApiErrorResponse;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
 
 #[derive(Error, Debug)]
 pub enum NamadaStateError {
@@ -14,11 +13,15 @@ pub enum NamadaStateError {
 
 impl IntoResponse for NamadaStateError {
     fn into_response(self) -> Response {
-        let status_code = match self {
-            NamadaStateError::BlockIndexNotFound => StatusCode::NOT_FOUND,
-            NamadaStateError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let message = self.to_string();
 
-        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+        match self {
+            NamadaStateError::BlockIndexNotFound => {
+                ApiErrorResponse::send(ErrorCode::NotFound, message)
+            }
+            NamadaStateError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
     }
 }
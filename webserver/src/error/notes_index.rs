@@ -1,24 +1,37 @@
-use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use serde_json::json;
 use thiserror::Error;
 
-use crate::response::api::ApiErrorResponse;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
 
 #[derive(Error, Debug)]
 pub enum NotesIndexError {
     #[error("NotesIndex not found")]
     NotFound,
+    #[error("Block height {0} was pruned, only blocks above {1} are kept")]
+    Pruned(u64, u64),
     #[error("Database error: {0}")]
     Database(String),
 }
 
 impl IntoResponse for NotesIndexError {
     fn into_response(self) -> Response {
-        let status_code = match self {
-            NotesIndexError::NotFound => StatusCode::NOT_FOUND,
-            NotesIndexError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let message = self.to_string();
 
-        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+        match self {
+            NotesIndexError::NotFound => {
+                ApiErrorResponse::send(ErrorCode::NotFound, message)
+            }
+            NotesIndexError::Pruned(height, pruned_height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::Pruned,
+                    message,
+                    json!({ "height": height, "pruned_height": pruned_height }),
+                )
+            }
+            NotesIndexError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
     }
 }
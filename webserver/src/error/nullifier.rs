@@ -0,0 +1,32 @@
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+#[derive(Error, Debug)]
+pub enum NullifierError {
+    #[error("Block height {0} was pruned, only blocks above {1} are kept")]
+    Pruned(u64, u64),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for NullifierError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+
+        match self {
+            NullifierError::Pruned(height, pruned_height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::Pruned,
+                    message,
+                    json!({ "height": height, "pruned_height": pruned_height }),
+                )
+            }
+            NullifierError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
+    }
+}
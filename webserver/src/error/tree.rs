@@ -1,21 +1,27 @@
-use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 
-use crate::response::api::ApiErrorResponse;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
 
 #[derive(Error, Debug)]
 pub enum TreeError {
+    #[error("Commitment trees are not indexed in notes map only mode")]
+    Unavailable,
     #[error("Database error: {0}")]
     Database(String),
 }
 
 impl IntoResponse for TreeError {
     fn into_response(self) -> Response {
-        let status_code = match self {
-            TreeError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let message = self.to_string();
 
-        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+        match self {
+            TreeError::Unavailable => {
+                ApiErrorResponse::send(ErrorCode::Unavailable, message)
+            }
+            TreeError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
     }
 }
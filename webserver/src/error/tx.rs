@@ -1,20 +1,32 @@
-use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use serde_json::json;
 use thiserror::Error;
 
-use crate::response::api::ApiErrorResponse;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
 
 #[derive(Error, Debug)]
 pub enum TxError {
+    #[error("Block height {0} was pruned, only blocks above {1} are kept")]
+    Pruned(u64, u64),
     #[error("Database error: {0}")]
     Database(String),
 }
 
 impl IntoResponse for TxError {
     fn into_response(self) -> Response {
-        let status_code = match &self {
-            TxError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+        let message = self.to_string();
+
+        match self {
+            TxError::Pruned(height, pruned_height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::Pruned,
+                    message,
+                    json!({ "height": height, "pruned_height": pruned_height }),
+                )
+            }
+            TxError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
     }
 }
@@ -1,21 +1,51 @@
-use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use serde_json::json;
 use thiserror::Error;
 
-use crate::response::api::ApiErrorResponse;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
 
 #[derive(Error, Debug)]
 pub enum WitnessMapError {
+    #[error("Witness maps are not indexed in notes map only mode")]
+    Unavailable,
+    #[error("Block height {0} has not been synced yet")]
+    HeightNotSynced(u64),
+    #[error("No blocks have been synced yet")]
+    NothingSynced,
+    #[error("No witness of note {0} is tracked at the given height")]
+    NoteNotTracked(u64),
     #[error("Database error: {0}")]
     Database(String),
 }
 
 impl IntoResponse for WitnessMapError {
     fn into_response(self) -> Response {
-        let status_code = match self {
-            WitnessMapError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let message = self.to_string();
 
-        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+        match self {
+            WitnessMapError::Unavailable => {
+                ApiErrorResponse::send(ErrorCode::Unavailable, message)
+            }
+            WitnessMapError::HeightNotSynced(height) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::HeightNotSynced,
+                    message,
+                    json!({ "height": height }),
+                )
+            }
+            WitnessMapError::NothingSynced => {
+                ApiErrorResponse::send(ErrorCode::NothingSynced, message)
+            }
+            WitnessMapError::NoteNotTracked(note_position) => {
+                ApiErrorResponse::send_with_details(
+                    ErrorCode::NoteNotTracked,
+                    message,
+                    json!({ "note_position": note_position }),
+                )
+            }
+            WitnessMapError::Database(_) => {
+                ApiErrorResponse::send(ErrorCode::Internal, message)
+            }
+        }
     }
 }
@@ -0,0 +1,108 @@
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::anchor::{AnchorQueryParams, AnchorsQueryParams};
+use crate::error::anchor::AnchorError;
+use crate::response::anchor::{AnchorResponse, AnchorsResponse};
+use crate::state::common::CommonState;
+
+/// Maximum number of anchors returned in a single request.
+const MAX_ANCHORS: u64 = 1000;
+
+#[debug_handler]
+pub async fn get_anchor(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<AnchorQueryParams>,
+) -> Result<Json<AnchorResponse>, AnchorError> {
+    if state.notes_map_only {
+        return Err(AnchorError::Unavailable);
+    }
+
+    let anchor = state
+        .anchor_service
+        .get_at_height(query_params.height)
+        .await
+        .inspect_wrap("get_anchor", |err| {
+            AnchorError::Database(err.to_string())
+        })?
+        .ok_or(AnchorError::HeightNotSynced(query_params.height))?;
+
+    Ok(Json(anchor.into()))
+}
+
+#[debug_handler]
+pub async fn get_anchor_at_height(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Path(height): Path<u64>,
+) -> Result<Json<AnchorResponse>, AnchorError> {
+    if state.notes_map_only {
+        return Err(AnchorError::Unavailable);
+    }
+
+    let anchor = state
+        .anchor_service
+        .get_at_height(height)
+        .await
+        .inspect_wrap("get_anchor_at_height", |err| {
+            AnchorError::Database(err.to_string())
+        })?
+        .ok_or(AnchorError::HeightNotSynced(height))?;
+
+    Ok(Json(anchor.into()))
+}
+
+#[debug_handler]
+pub async fn get_anchors(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<AnchorsQueryParams>,
+) -> Result<Json<AnchorsResponse>, AnchorError> {
+    let AnchorsQueryParams { from, to } = query_params;
+
+    if state.notes_map_only {
+        return Err(AnchorError::Unavailable);
+    }
+    if from > to {
+        return Err(AnchorError::InvalidRange(from, to));
+    }
+    if to - from >= MAX_ANCHORS {
+        return Err(AnchorError::RangeTooLarge(from, to, MAX_ANCHORS));
+    }
+
+    let anchors = state
+        .anchor_service
+        .get_range(from, to)
+        .await
+        .inspect_wrap("get_anchors", |err| {
+            AnchorError::Database(err.to_string())
+        })?
+        .ok_or(AnchorError::HeightNotSynced(to))?;
+
+    Ok(Json(AnchorsResponse::new(anchors)))
+}
+
+#[debug_handler]
+pub async fn get_latest_anchor(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+) -> Result<Json<AnchorResponse>, AnchorError> {
+    if state.notes_map_only {
+        return Err(AnchorError::Unavailable);
+    }
+
+    let anchor = state
+        .anchor_service
+        .get_latest()
+        .await
+        .inspect_wrap("get_latest_anchor", |err| {
+            AnchorError::Database(err.to_string())
+        })?
+        .ok_or(AnchorError::NothingSynced)?;
+
+    Ok(Json(anchor.into()))
+}
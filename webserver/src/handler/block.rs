@@ -0,0 +1,59 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use chrono::{DateTime, NaiveDateTime};
+use shared::error::InspectWrap;
+
+use crate::error::block::BlockError;
+use crate::response::block::BlockResponse;
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_block(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Path(block_height): Path<u64>,
+) -> Result<Json<BlockResponse>, BlockError> {
+    let block = state
+        .block_service
+        .get_at_height(block_height)
+        .await
+        .inspect_wrap("get_block", |err| BlockError::Database(err.to_string()))?
+        .ok_or(BlockError::HeightNotIndexed(block_height))?;
+
+    Ok(Json(block.into()))
+}
+
+/// Returns the last indexed block committed at or before the given time,
+/// either in seconds since the unix epoch or as an RFC 3339 date.
+#[debug_handler]
+pub async fn get_height_at_time(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Path(timestamp): Path<String>,
+) -> Result<Json<BlockResponse>, BlockError> {
+    let time = parse_timestamp(&timestamp)
+        .ok_or_else(|| BlockError::InvalidTimestamp(timestamp.clone()))?;
+
+    let block = state
+        .block_service
+        .get_at_time(time)
+        .await
+        .inspect_wrap("get_height_at_time", |err| {
+            BlockError::Database(err.to_string())
+        })?
+        .ok_or(BlockError::NoBlockAtTime(timestamp))?;
+
+    Ok(Json(block.into()))
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    match timestamp.parse::<i64>() {
+        Ok(seconds) => DateTime::from_timestamp(seconds, 0),
+        Err(_) => DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|time| time.to_utc()),
+    }
+    .map(|time| time.naive_utc())
+}
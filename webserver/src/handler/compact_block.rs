@@ -0,0 +1,65 @@
+use axum::extract::{Query, State};
+use axum::response::Response;
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::compact_block::CompactBlocksQueryParams;
+use crate::encoding::Encoding;
+use crate::error::compact_block::CompactBlockError;
+use crate::response::compact_block::CompactBlocksResponse;
+use crate::service::compact_block::CompactBlocksLookup;
+use crate::state::common::CommonState;
+
+/// Maximum number of compact blocks returned in a single request.
+const MAX_COMPACT_BLOCKS: u64 = 1000;
+
+#[debug_handler]
+pub async fn get_compact_blocks(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    Query(query_params): Query<CompactBlocksQueryParams>,
+) -> Result<Response, CompactBlockError> {
+    let CompactBlocksQueryParams { from, to } = query_params;
+
+    if from > to {
+        return Err(CompactBlockError::InvalidRange(from, to));
+    }
+    if to - from >= MAX_COMPACT_BLOCKS {
+        return Err(CompactBlockError::RangeTooLarge(
+            from,
+            to,
+            MAX_COMPACT_BLOCKS,
+        ));
+    }
+
+    let pruned_height = state
+        .namada_state_service
+        .get_pruned_height()
+        .await
+        .inspect_wrap("get_compact_blocks", |err| {
+            CompactBlockError::Database(err.to_string())
+        })?;
+
+    if let Some(pruned_height) = pruned_height.filter(|h| from <= h.0) {
+        return Err(CompactBlockError::Pruned(from, pruned_height.0));
+    }
+
+    let lookup = state
+        .compact_block_service
+        .get_compact_blocks(from, to)
+        .await
+        .inspect_wrap("get_compact_blocks", |err| {
+            CompactBlockError::Database(err.to_string())
+        })?;
+
+    match lookup {
+        CompactBlocksLookup::Found(blocks) => {
+            Ok(encoding.respond(CompactBlocksResponse::new(from, to, blocks)))
+        }
+        CompactBlocksLookup::NotSynced => {
+            Err(CompactBlockError::HeightNotSynced(to))
+        }
+    }
+}
@@ -1,11 +1,16 @@
 use axum::Json;
 use axum::extract::State;
+use axum::http::StatusCode;
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
+use chrono::Utc;
 use shared::error::InspectWrap;
 
 use crate::error::namada_state::NamadaStateError;
-use crate::response::namada_state::{BlockIndexResponse, LatestHeightResponse};
+use crate::response::namada_state::{
+    BlockIndexResponse, HealthResponse, InfoResponse, LatestHeightResponse,
+    ReadinessResponse,
+};
 use crate::state::common::CommonState;
 
 #[debug_handler]
@@ -48,3 +53,136 @@ pub async fn get_block_index(
         Err(NamadaStateError::BlockIndexNotFound)
     }
 }
+
+/// Reports the chain id of the indexed network, such that clients can
+/// check that they query an indexer of the right chain.
+#[debug_handler]
+pub async fn get_info(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+) -> Result<Json<InfoResponse>, NamadaStateError> {
+    let chain_id = state
+        .namada_state_service
+        .get_chain_id()
+        .await
+        .inspect_wrap("get_info", |err| {
+            NamadaStateError::Database(err.to_string())
+        })?;
+    let pruned_height = state
+        .namada_state_service
+        .get_pruned_height()
+        .await
+        .inspect_wrap("get_info", |err| {
+            NamadaStateError::Database(err.to_string())
+        })?;
+
+    Ok(Json(InfoResponse {
+        chain_id,
+        pruned_height: pruned_height.map(|h| h.0),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: env!("VERGEN_GIT_SHA").to_string(),
+        notes_map_only: state.notes_map_only,
+    }))
+}
+
+/// Reports whether the database is reachable. Responds with
+/// `503 Service Unavailable` otherwise, such that it can be used as a
+/// liveness probe.
+#[debug_handler]
+pub async fn get_health(
+    State(state): State<CommonState>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let database = state
+        .namada_state_service
+        .check_connection()
+        .await
+        .inspect_wrap("get_health", |err| err)
+        .is_ok();
+    let status_code = if database {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status_code,
+        Json(HealthResponse {
+            commit: env!("VERGEN_GIT_SHA").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            database,
+        }),
+    )
+}
+
+/// Reports the sync status of the indexer. Responds with
+/// `503 Service Unavailable` while the database or the configured
+/// CometBFT node is unreachable, the indexer lags behind the chain tip by
+/// more than the configured threshold, or nothing was committed for
+/// longer than the configured bound, such that it can be used as a
+/// readiness probe.
+#[debug_handler]
+pub async fn get_readiness(
+    State(state): State<CommonState>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let sync_progress = async {
+        state.namada_state_service.check_connection().await?;
+        state.namada_state_service.get_sync_progress().await
+    }
+    .await
+    .inspect_wrap("get_readiness", |err| err);
+    let database = sync_progress.is_ok();
+    let sync_progress = sync_progress.ok().flatten();
+
+    let live_chain_tip = state
+        .namada_state_service
+        .get_chain_tip()
+        .await
+        .inspect_wrap("get_readiness", |err| err);
+    let cometbft = match &live_chain_tip {
+        Ok(None) => None,
+        result => Some(result.is_ok()),
+    };
+
+    // NB: fall back to the chain tip observed by the crawler, if
+    // CometBFT is not configured or unreachable. the crawler's tip is
+    // only as recent as its last commit, hence the staleness bound
+    let chain_tip = live_chain_tip
+        .ok()
+        .flatten()
+        .or(sync_progress.map(|(_, chain_tip, _)| chain_tip));
+    let stale = sync_progress.is_none_or(|(_, _, committed_at)| {
+        (Utc::now().naive_utc() - committed_at)
+            .to_std()
+            .is_ok_and(|age| age > state.max_commit_age)
+    });
+
+    let lag_blocks =
+        sync_progress
+            .zip(chain_tip)
+            .map(|((height, _, _), chain_tip)| {
+                chain_tip.0.saturating_sub(height.0)
+            });
+    let caught_up =
+        lag_blocks.is_some_and(|lag| lag <= state.caught_up_threshold);
+    let status_code =
+        if database && cometbft != Some(false) && caught_up && !stale {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+
+    (
+        status_code,
+        Json(ReadinessResponse {
+            database,
+            cometbft,
+            last_synced_height: sync_progress.map(|(height, _, _)| height.0),
+            chain_tip: chain_tip.map(|h| h.0),
+            lag_blocks,
+            caught_up,
+            last_commit_at: sync_progress
+                .map(|(_, _, committed_at)| committed_at),
+            stale,
+        }),
+    )
+}
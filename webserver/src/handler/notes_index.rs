@@ -1,29 +1,206 @@
+use std::convert::Infallible;
+
 use axum::Json;
 use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt};
 use shared::error::InspectWrap;
+use tokio::sync::broadcast::error::RecvError;
 
-use crate::dto::notes_index::NotesIndexQueryParams;
+use crate::dto::notes_index::{NotesIndexQueryParams, NotesStreamQueryParams};
 use crate::error::notes_index::NotesIndexError;
-use crate::response::notes_index::NotesIndexResponse;
+use crate::response::notes_index::{NotesIndexEvent, NotesIndexResponse};
+use crate::service::notes_stream::NotesStreamService;
 use crate::state::common::CommonState;
 
+/// Maximum number of notes pushed in a single event, when catching up
+/// with the last synced height.
+const STREAM_PAGE_SIZE: u64 = 1000;
+
+/// Maximum number of notes returned in a single page.
+const MAX_NOTES_LIMIT: u64 = 10_000;
+
 #[debug_handler]
 pub async fn get_notes_index(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
     Query(query_params): Query<NotesIndexQueryParams>,
 ) -> Result<Json<NotesIndexResponse>, NotesIndexError> {
-    let from_block_height = query_params.height;
+    let from_block_height = query_params.from_height.unwrap_or_default();
+
+    let pruned_height = state
+        .namada_state_service
+        .get_pruned_height()
+        .await
+        .inspect_wrap("get_notes_index", |err| {
+            NotesIndexError::Database(err.to_string())
+        })?;
+
+    if let Some(pruned_height) =
+        pruned_height.filter(|h| from_block_height <= h.0)
+    {
+        return Err(NotesIndexError::Pruned(
+            from_block_height,
+            pruned_height.0,
+        ));
+    }
 
-    let notes_index = state
+    let limit = query_params
+        .limit
+        .map_or(MAX_NOTES_LIMIT, |limit| limit.min(MAX_NOTES_LIMIT));
+
+    let (notes_index, has_more) = state
         .notes_index_service
-        .get_notes_index(from_block_height)
+        .get_notes_index(
+            query_params.from_height,
+            query_params.height,
+            query_params.is_fee_unshielding,
+            Some(limit),
+            query_params.offset.unwrap_or_default(),
+        )
         .await
         .inspect_wrap("get_notes_index", |err| {
             NotesIndexError::Database(err.to_string())
         })?;
 
-    Ok(Json(NotesIndexResponse::new(notes_index)))
+    Ok(Json(NotesIndexResponse::new(notes_index, has_more)))
+}
+
+#[debug_handler]
+pub async fn stream_notes_index(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<NotesStreamQueryParams>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (sender, receiver) = mpsc::channel(1);
+
+    tokio::spawn(push_notes(
+        state.notes_stream_service,
+        query_params.from_height,
+        sender,
+    ));
+
+    Sse::new(receiver.map(Ok)).keep_alive(KeepAlive::default())
+}
+
+/// Push the notes committed since `from_height` to `sender`, followed by
+/// all notes committed from now on. Returns once the client disconnects,
+/// or if it lags behind the crawler.
+async fn push_notes(
+    service: NotesStreamService,
+    from_height: Option<u64>,
+    mut sender: mpsc::Sender<Event>,
+) {
+    // NB: subscribe before catching up, such that no
+    // notes are missed in between
+    let mut updates = service.subscribe();
+
+    let synced_height = match service.get_latest_height().await {
+        Ok(synced_height) => synced_height,
+        Err(err) => {
+            _ = sender.send(error_event(err.to_string())).await;
+            return;
+        }
+    };
+
+    if let Some(from_height) = from_height {
+        let pruned_height = match service.get_pruned_height().await {
+            Ok(pruned_height) => pruned_height,
+            Err(err) => {
+                _ = sender.send(error_event(err.to_string())).await;
+                return;
+            }
+        };
+
+        if let Some(pruned_height) = pruned_height.filter(|&h| from_height <= h)
+        {
+            _ = sender
+                .send(error_event(
+                    NotesIndexError::Pruned(from_height, pruned_height)
+                        .to_string(),
+                ))
+                .await;
+            return;
+        }
+
+        let mut offset = 0;
+
+        loop {
+            let notes_index = match service
+                .get_notes_index(
+                    from_height,
+                    synced_height,
+                    STREAM_PAGE_SIZE,
+                    offset,
+                )
+                .await
+            {
+                Ok(notes_index) => notes_index,
+                Err(err) => {
+                    _ = sender.send(error_event(err.to_string())).await;
+                    return;
+                }
+            };
+
+            if notes_index.is_empty() {
+                break;
+            }
+            offset += notes_index.len() as u64;
+
+            if sender
+                .send(notes_event(NotesIndexEvent::new(&notes_index)))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    // NB: notes up to the synced height were already pushed
+    let min_height = from_height.unwrap_or_default().max(synced_height + 1);
+
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(RecvError::Lagged(_)) => {
+                _ = sender
+                    .send(error_event(
+                        "Subscriber lagged behind, resubscribe from the last \
+                         received height"
+                            .to_string(),
+                    ))
+                    .await;
+                return;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let event = NotesIndexEvent::new(
+            update
+                .notes_index
+                .iter()
+                .filter(|(block_height, ..)| *block_height >= min_height),
+        );
+
+        if !event.notes_index.is_empty()
+            && sender.send(notes_event(event)).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn notes_event(event: NotesIndexEvent) -> Event {
+    Event::default()
+        .event("notes")
+        .json_data(event)
+        .expect("Notes should serialize to JSON")
+}
+
+fn error_event(message: String) -> Event {
+    Event::default().event("error").data(message)
 }
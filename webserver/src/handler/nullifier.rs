@@ -0,0 +1,46 @@
+use axum::Json;
+use axum::extract::{Query, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::nullifier::NullifiersQueryParams;
+use crate::error::nullifier::NullifierError;
+use crate::response::nullifier::NullifiersResponse;
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_nullifiers(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<NullifiersQueryParams>,
+) -> Result<Json<NullifiersResponse>, NullifierError> {
+    let pruned_height = state
+        .namada_state_service
+        .get_pruned_height()
+        .await
+        .inspect_wrap("get_nullifiers", |err| {
+            NullifierError::Database(err.to_string())
+        })?;
+
+    if let Some(pruned_height) =
+        pruned_height.filter(|h| query_params.from <= h.0)
+    {
+        return Err(NullifierError::Pruned(query_params.from, pruned_height.0));
+    }
+
+    let (nullifiers, has_more) = state
+        .nullifier_service
+        .get_nullifiers(
+            query_params.from,
+            query_params.to,
+            query_params.limit,
+            query_params.offset.unwrap_or_default(),
+        )
+        .await
+        .inspect_wrap("get_nullifiers", |err| {
+            NullifierError::Database(err.to_string())
+        })?;
+
+    Ok(Json(NullifiersResponse::new(nullifiers, has_more)))
+}
@@ -0,0 +1,56 @@
+use axum::extract::{Query, State};
+use axum::response::Response;
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::sync::SyncQueryParams;
+use crate::encoding::Encoding;
+use crate::error::sync::SyncError;
+use crate::response::sync::sync_response;
+use crate::service::sync::SyncLookup;
+use crate::state::common::CommonState;
+
+/// Maximum number of blocks that can be synced in a single request.
+const MAX_SYNC_BLOCKS: u64 = 1000;
+
+#[debug_handler]
+pub async fn get_sync(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    negotiated_encoding: Encoding,
+    Query(query_params): Query<SyncQueryParams>,
+) -> Result<Response, SyncError> {
+    let SyncQueryParams { from, to, encoding } = query_params;
+
+    if from > to {
+        return Err(SyncError::InvalidRange(from, to));
+    }
+    if to - from >= MAX_SYNC_BLOCKS {
+        return Err(SyncError::RangeTooLarge(from, to, MAX_SYNC_BLOCKS));
+    }
+
+    let pruned_height = state
+        .namada_state_service
+        .get_pruned_height()
+        .await
+        .inspect_wrap("get_sync", |err| SyncError::Database(err.to_string()))?;
+
+    if let Some(pruned_height) = pruned_height.filter(|h| from <= h.0) {
+        return Err(SyncError::Pruned(from, pruned_height.0));
+    }
+
+    let lookup = state
+        .sync_service
+        .get_sync_data(from, to, !state.notes_map_only)
+        .await
+        .inspect_wrap("get_sync", |err| SyncError::Database(err.to_string()))?;
+
+    let sync_data = match lookup {
+        SyncLookup::Found(sync_data) => sync_data,
+        SyncLookup::NotSynced => return Err(SyncError::HeightNotSynced(to)),
+    };
+    let response = sync_response(from, to, sync_data);
+
+    Ok(encoding.unwrap_or(negotiated_encoding).respond(response))
+}
@@ -1,34 +1,34 @@
-use axum::Json;
 use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
-use shared::commitment_tree::empty as empty_tree;
 use shared::error::InspectWrap;
 
+use crate::compression::ContentCoding;
 use crate::dto::tree::TreeQueryParams;
+use crate::encoding::Encoding;
 use crate::error::tree::TreeError;
-use crate::response::tree::TreeResponse;
 use crate::state::common::CommonState;
 
 #[debug_handler]
 pub async fn get_commitment_tree(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
+    encoding: Encoding,
+    coding: ContentCoding,
     Query(query_params): Query<TreeQueryParams>,
-) -> Result<Json<TreeResponse>, TreeError> {
-    let maybe_commitment_tree = state
+) -> Result<Response, TreeError> {
+    if state.notes_map_only {
+        return Err(TreeError::Unavailable);
+    }
+
+    let body = state
         .tree_service
-        .get_at_height(query_params.height)
+        .get_at_height(query_params.height, encoding, coding)
         .await
         .inspect_wrap("get_commitment_tree", |err| {
             TreeError::Database(err.to_string())
         })?;
 
-    let (commitment_tree, block_height) = maybe_commitment_tree
-        .unwrap_or_else(|| (empty_tree(), query_params.height));
-
-    Ok(Json(TreeResponse {
-        commitment_tree,
-        block_height,
-    }))
+    Ok(body.into_response())
 }
@@ -1,14 +1,24 @@
+use std::convert::Infallible;
+
 use axum::Json;
 use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt};
 use shared::error::InspectWrap;
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::dto::txs::TxQueryParams;
 use crate::error::tx::TxError;
-use crate::response::tx::TxResponse;
+use crate::response::tx::{TxResponse, TxsEvent};
+use crate::service::tx_stream::TxStreamService;
 use crate::state::common::CommonState;
 
+/// Maximum number of batches of txs returned in a single page.
+const MAX_TXS_LIMIT: u64 = 1000;
+
 #[debug_handler]
 pub async fn get_tx(
     _trace_id: TraceId<String>,
@@ -18,11 +28,75 @@ pub async fn get_tx(
     let from_block_height = query_params.height;
     let to_block_height = from_block_height + query_params.height_offset;
 
-    let txs = state
+    let pruned_height = state
+        .namada_state_service
+        .get_pruned_height()
+        .await
+        .inspect_wrap("get_tx", |err| TxError::Database(err.to_string()))?;
+
+    if let Some(pruned_height) =
+        pruned_height.filter(|h| from_block_height <= h.0)
+    {
+        return Err(TxError::Pruned(from_block_height, pruned_height.0));
+    }
+
+    let limit = query_params
+        .limit
+        .map_or(MAX_TXS_LIMIT, |limit| limit.min(MAX_TXS_LIMIT));
+
+    let (txs, has_more) = state
         .tx_service
-        .get_txs(from_block_height, to_block_height)
+        .get_txs(
+            from_block_height,
+            to_block_height,
+            limit,
+            query_params.offset.unwrap_or_default(),
+        )
         .await
         .inspect_wrap("get_tx", |err| TxError::Database(err.to_string()))?;
 
-    Ok(Json(TxResponse::new(txs)))
+    Ok(Json(TxResponse::new(txs, has_more)))
+}
+
+#[debug_handler]
+pub async fn stream_txs(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (sender, receiver) = mpsc::channel(1);
+
+    tokio::spawn(push_txs(state.tx_stream_service, sender));
+
+    Sse::new(receiver.map(Ok)).keep_alive(KeepAlive::default())
+}
+
+/// Push the blocks with masp txs committed from now on to `sender`.
+/// Returns once the client disconnects, or if it lags behind the crawler.
+async fn push_txs(service: TxStreamService, mut sender: mpsc::Sender<Event>) {
+    let mut updates = service.subscribe();
+
+    loop {
+        let block = match updates.recv().await {
+            Ok(block) => block,
+            Err(RecvError::Lagged(_)) => {
+                _ = sender
+                    .send(Event::default().event("error").data(
+                        "Subscriber lagged behind, resync from the last \
+                         received height",
+                    ))
+                    .await;
+                return;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let event = Event::default()
+            .event("txs")
+            .json_data(TxsEvent::from(block.as_ref()))
+            .expect("Txs should serialize to JSON");
+
+        if sender.send(event).await.is_err() {
+            return;
+        }
+    }
 }
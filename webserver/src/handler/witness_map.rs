@@ -1,34 +1,99 @@
-use axum::Json;
 use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
 use shared::error::InspectWrap;
 use shared::height::BlockHeight;
 
-use crate::dto::witness::WitnessMapQueryParams;
+use crate::compression::ContentCoding;
+use crate::dto::witness::{WitnessMapQueryParams, WitnessQueryParams};
+use crate::encoding::Encoding;
 use crate::error::witness_map::WitnessMapError;
-use crate::response::witness_map::WitnessMapResponse;
+use crate::response::witness_map::WitnessResponse;
+use crate::service::witness_map::{NoteWitnessLookup, WitnessMapLookup};
 use crate::state::common::CommonState;
 
 #[debug_handler]
 pub async fn get_witness_map(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
+    encoding: Encoding,
+    coding: ContentCoding,
     Query(query_params): Query<WitnessMapQueryParams>,
-) -> Result<Json<WitnessMapResponse>, WitnessMapError> {
-    let witnesses_and_height = state
+) -> Result<Response, WitnessMapError> {
+    if state.notes_map_only {
+        return Err(WitnessMapError::Unavailable);
+    }
+
+    let body = state
         .witness_map_service
-        .get_witnesses(BlockHeight(query_params.height))
+        .get_witnesses(BlockHeight(query_params.height), encoding, coding)
         .await
         .inspect_wrap("get_witness_map", |err| {
             WitnessMapError::Database(err.to_string())
         })?;
 
-    let (witnesses, block_height) =
-        witnesses_and_height.unwrap_or((Vec::new(), query_params.height));
+    Ok(body.into_response())
+}
+
+#[debug_handler]
+pub async fn get_historical_witness_map(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    coding: ContentCoding,
+    Query(query_params): Query<WitnessMapQueryParams>,
+) -> Result<Response, WitnessMapError> {
+    if state.notes_map_only {
+        return Err(WitnessMapError::Unavailable);
+    }
+
+    let lookup = state
+        .witness_map_service
+        .get_witnesses_at(BlockHeight(query_params.height), encoding, coding)
+        .await
+        .inspect_wrap("get_historical_witness_map", |err| {
+            WitnessMapError::Database(err.to_string())
+        })?;
+
+    match lookup {
+        WitnessMapLookup::Found(body) => Ok(body.into_response()),
+        WitnessMapLookup::NotSynced => {
+            Err(WitnessMapError::HeightNotSynced(query_params.height))
+        }
+    }
+}
+
+#[debug_handler]
+pub async fn get_witness(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    Query(query_params): Query<WitnessQueryParams>,
+) -> Result<Response, WitnessMapError> {
+    if state.notes_map_only {
+        return Err(WitnessMapError::Unavailable);
+    }
+
+    let lookup = state
+        .witness_map_service
+        .get_note_witness(query_params.note_position, query_params.height)
+        .await
+        .inspect_wrap("get_witness", |err| {
+            WitnessMapError::Database(err.to_string())
+        })?;
 
-    Ok(Json(WitnessMapResponse::new(
-        BlockHeight(block_height),
-        witnesses,
-    )))
+    match lookup {
+        NoteWitnessLookup::Found(witness) => {
+            Ok(encoding.respond(WitnessResponse::from(witness)))
+        }
+        NoteWitnessLookup::NotSynced => Err(query_params
+            .height
+            .map_or(WitnessMapError::NothingSynced, |height| {
+                WitnessMapError::HeightNotSynced(height)
+            })),
+        NoteWitnessLookup::Untracked => {
+            Err(WitnessMapError::NoteNotTracked(query_params.note_position))
+        }
+    }
 }
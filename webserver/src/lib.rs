@@ -0,0 +1,23 @@
+#![allow(async_fn_in_trait)]
+
+pub mod admin;
+pub mod app;
+pub mod appstate;
+pub mod auth;
+pub mod cache;
+pub mod compression;
+pub mod conditional;
+pub mod config;
+pub mod dto;
+pub mod encoding;
+pub mod error;
+pub mod handler;
+pub mod metrics;
+pub mod openapi;
+pub mod rate_limit;
+pub mod repository;
+pub mod response;
+pub mod service;
+pub mod state;
+pub mod telemetry;
+pub mod utils;
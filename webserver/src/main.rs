@@ -1,31 +1,37 @@
-#![allow(async_fn_in_trait)]
-
-pub mod app;
-pub mod appstate;
-pub mod config;
-pub mod dto;
-pub mod error;
-pub mod handler;
-pub mod repository;
-pub mod response;
-pub mod service;
-pub mod state;
-pub mod utils;
-
 use std::sync::Arc;
 
-use clap::Parser;
-
-use crate::app::ApplicationServer;
-use crate::config::AppConfig;
+use tracing_subscriber::layer::SubscriberExt;
+use webserver::app::ApplicationServer;
+use webserver::config::AppConfig;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config = Arc::new(AppConfig::parse());
+    let config = Arc::new(shared::config::parse::<AppConfig>());
+
+    let (otlp_layer, otlp_error) =
+        match config.otlp_endpoint.as_deref().map(|endpoint| {
+            shared::telemetry::otlp_layer(
+                "namada-masp-indexer-webserver",
+                endpoint,
+            )
+        }) {
+            Some(Ok(layer)) => (Some(layer), None),
+            Some(Err(err)) => (None, Some(err)),
+            None => (None, None),
+        };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(config.log_level)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otlp_layer);
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    if let Some(reason) = otlp_error {
+        tracing::warn!(?reason, "Failed to set up OTLP export of spans");
+    }
 
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::DEBUG)
-        .init();
+    let result = ApplicationServer::serve(config).await;
+    shared::telemetry::shutdown();
 
-    ApplicationServer::serve(config).await
+    result
 }
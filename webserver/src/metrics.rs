@@ -0,0 +1,98 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use axum::Router;
+use axum::extract::MatchedPath;
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::routing::get;
+use prometheus::{
+    Encoder, HistogramVec, IntCounterVec, IntGauge, TextEncoder,
+    register_histogram_vec, register_int_counter_vec, register_int_gauge,
+};
+
+pub static REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "masp_indexer_webserver_request_duration_seconds",
+        "Time taken to serve a request, per route",
+        &["method", "route", "status"],
+        vec![
+            0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0
+        ]
+    )
+    .unwrap()
+});
+
+pub static CACHE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "masp_indexer_webserver_cache_requests_total",
+        "Lookups in the response cache, per kind of data and result",
+        &["kind", "result"]
+    )
+    .unwrap()
+});
+
+pub static CACHE_SIZE_BYTES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "masp_indexer_webserver_cache_size_bytes",
+        "Approximate number of bytes taken up by the response cache"
+    )
+    .unwrap()
+});
+
+/// Middleware recording the latency of each request against the
+/// route it matched.
+pub async fn track_requests<B>(req: Request<B>, next: Next<B>) -> Response {
+    let started_at = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| req.uri().path().to_owned());
+
+    let response = next.run(req).await;
+
+    REQUEST_DURATION
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .observe(started_at.elapsed().as_secs_f64());
+
+    response
+}
+
+pub fn spawn_server(port: u16) {
+    // NB: register all metrics upfront, such that they
+    // are exported before any request gets served
+    LazyLock::force(&REQUEST_DURATION);
+    LazyLock::force(&CACHE_REQUESTS);
+    LazyLock::force(&CACHE_SIZE_BYTES);
+
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+    let router = Router::new().route("/metrics", get(render));
+
+    tokio::spawn(async move {
+        tracing::info!(%addr, "Serving metrics");
+
+        if let Err(reason) = axum::Server::bind(&addr)
+            .serve(router.into_make_service())
+            .await
+        {
+            tracing::error!(?reason, "Metrics server shut down unexpectedly");
+        }
+    });
+}
+
+async fn render() -> Result<String, StatusCode> {
+    let mut buffer = Vec::new();
+
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|reason| {
+            tracing::error!(?reason, "Failed to encode metrics");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    String::from_utf8(buffer).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,28 @@
+use axum::Json;
+use axum::response::{IntoResponse, Response};
+use lazy_static::lazy_static;
+
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+/// OpenAPI 3 document of the API, as maintained along with the handlers.
+const OPENAPI_YAML: &str = include_str!("../../swagger.yml");
+
+lazy_static! {
+    static ref OPENAPI_JSON: Result<serde_json::Value, String> =
+        serde_yaml::from_str(OPENAPI_YAML).map_err(|err| err.to_string());
+}
+
+/// Serves the OpenAPI document of the API as JSON, such that clients can
+/// be generated from it.
+pub async fn get_openapi() -> Response {
+    match &*OPENAPI_JSON {
+        Ok(document) => Json(document).into_response(),
+        Err(reason) => {
+            tracing::error!(reason, "Failed to parse the OpenAPI document");
+            ApiErrorResponse::send(
+                ErrorCode::Internal,
+                "The OpenAPI document is unavailable",
+            )
+        }
+    }
+}
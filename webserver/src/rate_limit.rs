@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderValue, Request, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde_json::json;
+
+use crate::auth::ApiKey;
+use crate::response::api::{ApiErrorResponse, ErrorCode};
+
+/// Maximum number of clients tracked by a rate limiter. Once reached,
+/// the clients whose bucket refilled completely are forgotten, or else
+/// the least recently seen one.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Sustained rate of requests allowed per second, along with the number
+/// of requests that may be sent in a single burst.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (per_second, burst) = match s.split_once(':') {
+            Some((per_second, burst)) => (per_second, Some(burst)),
+            None => (s, None),
+        };
+
+        let per_second = per_second
+            .parse::<f64>()
+            .ok()
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .ok_or_else(|| format!("Invalid requests per second: {s}"))?;
+        let burst = match burst {
+            Some(burst) => burst
+                .parse::<u32>()
+                .ok()
+                .filter(|burst| *burst > 0)
+                .ok_or_else(|| format!("Invalid burst size: {s}"))?
+                as f64,
+            None => per_second.ceil(),
+        };
+
+        Ok(Self { per_second, burst })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ClientId {
+    ApiKey(Arc<str>),
+    Ip(IpAddr),
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn tokens_at(&self, now: Instant, limit: RateLimit) -> f64 {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        (self.tokens + elapsed * limit.per_second).min(limit.burst)
+    }
+}
+
+/// Token bucket rate limiter, keeping a separate bucket per client.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    trust_forwarded_for: bool,
+    buckets: Arc<Mutex<HashMap<ClientId, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit, trust_forwarded_for: bool) -> Self {
+        Self {
+            limit,
+            trust_forwarded_for,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Take a token out of the bucket of `client`, or return how long it
+    /// takes until one is available.
+    fn acquire(&self, client: ClientId) -> Result<(), Duration> {
+        let now = Instant::now();
+        let limit = self.limit;
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS
+            && !buckets.contains_key(&client)
+        {
+            buckets
+                .retain(|_, bucket| bucket.tokens_at(now, limit) < limit.burst);
+
+            // NB: every tracked client may still be throttled, e.g. when
+            // many of them send requests at once, in which case the least
+            // recently seen one is evicted to keep memory usage bounded
+            if buckets.len() >= MAX_TRACKED_CLIENTS {
+                let least_recently_seen = buckets
+                    .iter()
+                    .min_by_key(|(_, bucket)| bucket.refilled_at)
+                    .map(|(client, _)| client.clone());
+                if let Some(least_recently_seen) = least_recently_seen {
+                    buckets.remove(&least_recently_seen);
+                }
+            }
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: limit.burst,
+            refilled_at: now,
+        });
+        bucket.tokens = bucket.tokens_at(now, limit);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / limit.per_second,
+            ))
+        }
+    }
+
+    fn client_ip<B>(&self, req: &Request<B>, peer_addr: SocketAddr) -> IpAddr {
+        let forwarded_for = self
+            .trust_forwarded_for
+            .then(|| req.headers().get("x-forwarded-for"))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|addr| addr.trim().parse().ok());
+
+        forwarded_for.unwrap_or(peer_addr.ip())
+    }
+}
+
+/// Middleware rejecting requests past the rate limit of their client,
+/// identified by its API key if it sent a valid one, or else by its IP.
+pub async fn limit_requests<B>(
+    State(limiter): State<RateLimiter>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let client = match req.extensions().get::<ApiKey>() {
+        Some(ApiKey(api_key)) => ClientId::ApiKey(api_key.clone()),
+        None => ClientId::Ip(limiter.client_ip(&req, peer_addr)),
+    };
+
+    let Err(retry_after) = limiter.acquire(client) else {
+        return next.run(req).await;
+    };
+    let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+
+    let mut response = ApiErrorResponse::send_with_details(
+        ErrorCode::RateLimited,
+        format!("Rate limit exceeded, retry in {retry_after}s"),
+        json!({ "retry_after": retry_after }),
+    );
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+
+    response
+}
@@ -0,0 +1,134 @@
+use anyhow::Context;
+use diesel::dsl::max;
+use diesel::{
+    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    SelectableHelper,
+};
+use orm::anchor::AnchorDb;
+use orm::schema::{anchors, chain_state, commitment_tree};
+use orm::tree::TreeDb;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct AnchorRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait AnchorRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    /// Return the last synced height, along with the closest commitment
+    /// tree at or below `block_height`. If `block_height` is `None`, the
+    /// last synced height is used instead.
+    async fn get_at_height(
+        &self,
+        block_height: Option<i32>,
+    ) -> anyhow::Result<(Option<i32>, Option<TreeDb>)>;
+
+    /// Return the last synced height, along with the anchors recorded
+    /// for the blocks between `from` and `to` (inclusive), in height
+    /// order.
+    async fn get_recorded(
+        &self,
+        from: i32,
+        to: i32,
+    ) -> anyhow::Result<(Option<i32>, Vec<AnchorDb>)>;
+}
+
+impl AnchorRepositoryTrait for AnchorRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_at_height(
+        &self,
+        block_height: Option<i32>,
+    ) -> anyhow::Result<(Option<i32>, Option<TreeDb>)> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            // NB: read the synced height and the tree from the same
+            // snapshot, such that a concurrent commit can't be observed
+            // halfway through
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let last_synced_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to get latest block height from db")?;
+
+                    let Some(block_height) =
+                        block_height.or(last_synced_height)
+                    else {
+                        return anyhow::Ok((None, None));
+                    };
+
+                    let tree = commitment_tree::table
+                        .filter(
+                            commitment_tree::dsl::block_height.le(block_height),
+                        )
+                        .order(commitment_tree::dsl::block_height.desc())
+                        .select(TreeDb::as_select())
+                        .first(conn)
+                        .optional()
+                        .with_context(|| {
+                            format!(
+                                "Failed to look-up commitment tree in the \
+                                 database closest to the provided height \
+                                 {block_height}"
+                            )
+                        })?;
+
+                    anyhow::Ok((last_synced_height, tree))
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn get_recorded(
+        &self,
+        from: i32,
+        to: i32,
+    ) -> anyhow::Result<(Option<i32>, Vec<AnchorDb>)> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let last_synced_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to get latest block height from db")?;
+
+                    let anchors = anchors::table
+                        .filter(anchors::dsl::block_height.between(from, to))
+                        .order(anchors::dsl::block_height.asc())
+                        .select(AnchorDb::as_select())
+                        .load(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to look-up anchors in the database in \
+                                 the range {from}-{to}"
+                            )
+                        })?;
+
+                    anyhow::Ok((last_synced_height, anchors))
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use diesel::{
+    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    SelectableHelper,
+};
+use orm::block_hash::BlockHashDb;
+use orm::schema::block_hash;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct BlockRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait BlockRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    /// Return the indexed block at `block_height`, if any.
+    async fn get_at_height(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<BlockHashDb>>;
+
+    /// Return the last indexed block committed at or before `timestamp`,
+    /// if any.
+    async fn get_at_time(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<Option<BlockHashDb>>;
+}
+
+impl BlockRepositoryTrait for BlockRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_at_height(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<BlockHashDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            block_hash::table
+                .filter(block_hash::dsl::block_height.eq(block_height))
+                .select(BlockHashDb::as_select())
+                .first(conn)
+                .optional()
+        })
+        .await
+        .context_db_interact_error()?
+        .with_context(|| {
+            format!("Failed to get block at height {block_height} from db")
+        })
+    }
+
+    async fn get_at_time(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<Option<BlockHashDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            block_hash::table
+                .filter(block_hash::dsl::timestamp.le(timestamp))
+                .order(block_hash::dsl::timestamp.desc())
+                .then_order_by(block_hash::dsl::block_height.desc())
+                .select(BlockHashDb::as_select())
+                .first(conn)
+                .optional()
+        })
+        .await
+        .context_db_interact_error()?
+        .with_context(|| {
+            format!("Failed to look-up the block at time {timestamp} in db")
+        })
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::Context;
+use diesel::dsl::max;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use orm::compact_output::CompactOutputDb;
+use orm::nullifier::NullifierDb;
+use orm::schema::{chain_state, compact_outputs, nullifiers};
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+/// Rows needed to build the compact blocks of a range of blocks, all
+/// read from the same snapshot of the database.
+pub struct CompactBlockRows {
+    pub last_synced_height: Option<i32>,
+    pub compact_outputs: Vec<CompactOutputDb>,
+    pub nullifiers: Vec<NullifierDb>,
+}
+
+#[derive(Clone)]
+pub struct CompactBlockRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait CompactBlockRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    /// Return the last synced height, along with the compact outputs and
+    /// nullifiers of the masp txs between `from_block_height` and
+    /// `to_block_height` (inclusive), sorted in the order they were
+    /// committed in.
+    async fn get_compact_block_rows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<CompactBlockRows>;
+}
+
+impl CompactBlockRepositoryTrait for CompactBlockRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_compact_block_rows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<CompactBlockRows> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let last_synced_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to get latest block height from db")?;
+
+                    let compact_outputs = compact_outputs::table
+                        .filter(
+                            compact_outputs::dsl::block_height
+                                .ge(from_block_height),
+                        )
+                        .filter(
+                            compact_outputs::dsl::block_height
+                                .le(to_block_height),
+                        )
+                        .order((
+                            compact_outputs::dsl::block_height.asc(),
+                            compact_outputs::dsl::block_index.asc(),
+                            compact_outputs::dsl::masp_tx_index.asc(),
+                            compact_outputs::dsl::output_index.asc(),
+                        ))
+                        .select(CompactOutputDb::as_select())
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to retrieve the compact outputs in \
+                                 the range \
+                                 {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    let nullifiers = nullifiers::table
+                        .filter(
+                            nullifiers::dsl::block_height.ge(from_block_height),
+                        )
+                        .filter(
+                            nullifiers::dsl::block_height.le(to_block_height),
+                        )
+                        .order((
+                            nullifiers::dsl::block_height.asc(),
+                            nullifiers::dsl::block_index.asc(),
+                            nullifiers::dsl::masp_tx_index.asc(),
+                            nullifiers::dsl::id.asc(),
+                        ))
+                        .select(NullifierDb::as_select())
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to retrieve the nullifiers in the \
+                                 range {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    anyhow::Ok(CompactBlockRows {
+                        last_synced_height,
+                        compact_outputs,
+                        nullifiers,
+                    })
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
@@ -0,0 +1,49 @@
+use orm::anchor::AnchorDb;
+use orm::memory::MemoryDb;
+use orm::tree::TreeDb;
+
+use crate::appstate::AppState;
+use crate::repository::anchor::AnchorRepositoryTrait;
+
+/// [`AnchorRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryAnchorRepository {
+    db: MemoryDb,
+}
+
+impl AnchorRepositoryTrait for MemoryAnchorRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_at_height(
+        &self,
+        block_height: Option<i32>,
+    ) -> anyhow::Result<(Option<i32>, Option<TreeDb>)> {
+        let tables = self.db.lock();
+        let last_synced_height = tables.last_synced_height();
+        let tree = block_height
+            .or(last_synced_height)
+            .and_then(|block_height| tables.commitment_tree_at(block_height))
+            .cloned();
+        Ok((last_synced_height, tree))
+    }
+
+    async fn get_recorded(
+        &self,
+        from: i32,
+        to: i32,
+    ) -> anyhow::Result<(Option<i32>, Vec<AnchorDb>)> {
+        let tables = self.db.lock();
+        let anchors = tables
+            .anchors
+            .values()
+            .filter(|anchor| (from..=to).contains(&anchor.block_height))
+            .cloned()
+            .collect();
+        Ok((tables.last_synced_height(), anchors))
+    }
+}
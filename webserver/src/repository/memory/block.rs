@@ -0,0 +1,44 @@
+use chrono::NaiveDateTime;
+use orm::block_hash::BlockHashDb;
+use orm::memory::MemoryDb;
+
+use crate::appstate::AppState;
+use crate::repository::block::BlockRepositoryTrait;
+
+/// [`BlockRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryBlockRepository {
+    db: MemoryDb,
+}
+
+impl BlockRepositoryTrait for MemoryBlockRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_at_height(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<BlockHashDb>> {
+        let tables = self.db.lock();
+        Ok(tables.block_hash.get(&block_height).cloned())
+    }
+
+    async fn get_at_time(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<Option<BlockHashDb>> {
+        let tables = self.db.lock();
+        Ok(tables
+            .block_hash
+            .values()
+            .filter(|block| {
+                block.timestamp.is_some_and(|time| time <= timestamp)
+            })
+            .max_by_key(|block| (block.timestamp, block.block_height))
+            .cloned())
+    }
+}
@@ -0,0 +1,57 @@
+use orm::memory::MemoryDb;
+
+use crate::appstate::AppState;
+use crate::repository::compact_block::{
+    CompactBlockRepositoryTrait, CompactBlockRows,
+};
+
+/// [`CompactBlockRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryCompactBlockRepository {
+    db: MemoryDb,
+}
+
+impl CompactBlockRepositoryTrait for MemoryCompactBlockRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_compact_block_rows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<CompactBlockRows> {
+        let tables = self.db.lock();
+        let in_range = from_block_height..=to_block_height;
+        // NB: the compact outputs are keyed in the order they were
+        // committed in
+        let compact_outputs = tables
+            .compact_outputs
+            .values()
+            .filter(|output| in_range.contains(&output.block_height))
+            .cloned()
+            .collect();
+        let mut nullifiers = tables
+            .nullifiers
+            .values()
+            .filter(|nullifier| in_range.contains(&nullifier.block_height))
+            .cloned()
+            .collect::<Vec<_>>();
+        nullifiers.sort_by_key(|nullifier| {
+            (
+                nullifier.block_height,
+                nullifier.block_index,
+                nullifier.masp_tx_index,
+                nullifier.id,
+            )
+        });
+        Ok(CompactBlockRows {
+            last_synced_height: tables.last_synced_height(),
+            compact_outputs,
+            nullifiers,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+pub mod anchor;
+pub mod block;
+pub mod compact_block;
+pub mod namada_state;
+pub mod notes_index;
+pub mod nullifier;
+pub mod sync;
+pub mod tree;
+pub mod tx;
+pub mod witness_map;
+
+use orm::memory::MemoryDb;
+
+use crate::appstate::AppState;
+
+/// In-memory database served by `app_state`.
+fn memory_db(app_state: &AppState) -> MemoryDb {
+    app_state
+        .memory_db()
+        .cloned()
+        .expect("In-memory repositories require an in-memory app state")
+}
+
+/// Skip the first `offset` rows read from the in-memory database, and
+/// keep at most `limit` of the rest, like `OFFSET` and `LIMIT` do.
+fn paginate<T>(rows: Vec<T>, limit: Option<i64>, offset: i64) -> Vec<T> {
+    rows.into_iter()
+        .skip(offset as usize)
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect()
+}
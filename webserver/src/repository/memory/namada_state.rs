@@ -0,0 +1,54 @@
+use orm::chain_state::ChainStateDb;
+use orm::memory::MemoryDb;
+use shared::height::BlockHeight;
+use xorf::BinaryFuse16;
+
+use crate::appstate::AppState;
+use crate::repository::namada_state::NamadaStateRepositoryTrait;
+
+/// [`NamadaStateRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryNamadaStateRepository {
+    db: MemoryDb,
+}
+
+impl NamadaStateRepositoryTrait for MemoryNamadaStateRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_latest_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let tables = self.db.lock();
+        Ok(tables.last_synced_height().map(BlockHeight::from))
+    }
+
+    async fn get_block_index(
+        &self,
+    ) -> anyhow::Result<Option<(i32, BinaryFuse16)>> {
+        // NB: the block index is built by a separate service, which only
+        // writes to Postgres
+        Ok(None)
+    }
+
+    async fn check_connection(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn get_sync_progress(&self) -> anyhow::Result<Option<ChainStateDb>> {
+        let tables = self.db.lock();
+        Ok(tables.chain_state.clone())
+    }
+
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>> {
+        let tables = self.db.lock();
+        Ok(tables.chain_id.clone())
+    }
+
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let tables = self.db.lock();
+        Ok(tables.pruned_height.map(BlockHeight::from))
+    }
+}
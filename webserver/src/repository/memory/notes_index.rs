@@ -0,0 +1,52 @@
+use orm::memory::MemoryDb;
+use orm::notes_index::NotesIndexDb;
+
+use crate::appstate::AppState;
+use crate::repository::notes_index::NotesIndexRepositoryTrait;
+
+/// [`NotesIndexRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryNotesIndexRepository {
+    db: MemoryDb,
+}
+
+impl NotesIndexRepositoryTrait for MemoryNotesIndexRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_notes_index(
+        &self,
+        from_block_height: Option<i32>,
+        to_block_height: i32,
+        is_fee_unshielding: Option<bool>,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> anyhow::Result<Vec<NotesIndexDb>> {
+        let tables = self.db.lock();
+        let mut notes = tables
+            .notes_index
+            .values()
+            .filter(|note| {
+                note.block_height <= to_block_height
+                    && from_block_height
+                        .is_none_or(|from| note.block_height >= from)
+                    && is_fee_unshielding
+                        .is_none_or(|fee| note.is_fee_unshielding == fee)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        notes.sort_by_key(|note| {
+            (
+                note.block_height,
+                note.block_index,
+                note.masp_tx_index,
+                note.note_position,
+            )
+        });
+        Ok(super::paginate(notes, limit, offset))
+    }
+}
@@ -0,0 +1,49 @@
+use orm::memory::MemoryDb;
+use orm::nullifier::NullifierDb;
+
+use crate::appstate::AppState;
+use crate::repository::nullifier::NullifierRepositoryTrait;
+
+/// [`NullifierRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryNullifierRepository {
+    db: MemoryDb,
+}
+
+impl NullifierRepositoryTrait for MemoryNullifierRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_nullifiers(
+        &self,
+        from_block_height: i32,
+        to_block_height: Option<i32>,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> anyhow::Result<Vec<NullifierDb>> {
+        let tables = self.db.lock();
+        let mut nullifiers = tables
+            .nullifiers
+            .values()
+            .filter(|nullifier| {
+                nullifier.block_height >= from_block_height
+                    && to_block_height
+                        .is_none_or(|to| nullifier.block_height <= to)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        nullifiers.sort_by_key(|nullifier| {
+            (
+                nullifier.block_height,
+                nullifier.block_index,
+                nullifier.masp_tx_index,
+                nullifier.id,
+            )
+        });
+        Ok(super::paginate(nullifiers, limit, offset))
+    }
+}
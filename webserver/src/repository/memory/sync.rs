@@ -0,0 +1,86 @@
+use orm::memory::MemoryDb;
+
+use super::witness_map::witness_replay_rows;
+use crate::appstate::AppState;
+use crate::repository::sync::{SyncRepositoryTrait, SyncRows};
+
+/// [`SyncRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemorySyncRepository {
+    db: MemoryDb,
+}
+
+impl SyncRepositoryTrait for MemorySyncRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_sync_rows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        with_tree: bool,
+    ) -> anyhow::Result<SyncRows> {
+        let tables = self.db.lock();
+        let Some(last_synced_height) = tables
+            .last_synced_height()
+            .filter(|&height| height >= to_block_height)
+        else {
+            return Ok(SyncRows {
+                last_synced_height: tables.last_synced_height(),
+                commitment_tree: None,
+                witnesses: None,
+                notes_index: vec![],
+                txs: vec![],
+            });
+        };
+
+        let (commitment_tree, witnesses) = if with_tree {
+            let witnesses = witness_replay_rows(
+                &tables,
+                last_synced_height,
+                to_block_height,
+            );
+            (
+                tables.commitment_tree_at(to_block_height).cloned(),
+                Some(witnesses),
+            )
+        } else {
+            (None, None)
+        };
+
+        let in_range = from_block_height..=to_block_height;
+        let mut notes_index = tables
+            .notes_index
+            .values()
+            .filter(|note| in_range.contains(&note.block_height))
+            .cloned()
+            .collect::<Vec<_>>();
+        notes_index.sort_by_key(|note| {
+            (
+                note.block_height,
+                note.block_index,
+                note.masp_tx_index,
+                note.note_position,
+            )
+        });
+
+        let txs = tables
+            .tx
+            .iter()
+            .filter(|tx| in_range.contains(&tx.block_height))
+            .cloned()
+            .collect();
+
+        Ok(SyncRows {
+            last_synced_height: Some(last_synced_height),
+            commitment_tree,
+            witnesses,
+            notes_index,
+            txs,
+        })
+    }
+}
@@ -0,0 +1,28 @@
+use orm::memory::MemoryDb;
+use orm::tree::TreeDb;
+
+use crate::appstate::AppState;
+use crate::repository::tree::TreeRepositoryTrait;
+
+/// [`TreeRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryTreeRepository {
+    db: MemoryDb,
+}
+
+impl TreeRepositoryTrait for MemoryTreeRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_at_height(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<TreeDb>> {
+        let tables = self.db.lock();
+        Ok(tables.commitment_tree_at(block_height).cloned())
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use orm::memory::MemoryDb;
+use orm::tx::TxDb;
+
+use crate::appstate::AppState;
+use crate::repository::tx::TxRepositoryTrait;
+
+/// [`TxRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryTxRepository {
+    db: MemoryDb,
+}
+
+impl TxRepositoryTrait for MemoryTxRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_txs(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<TxDb>> {
+        let tables = self.db.lock();
+        let block_height = tables.last_synced_height().unwrap_or_default();
+        if block_height < to_block_height {
+            anyhow::bail!(
+                "Requested range {from_block_height} -- {to_block_height} \
+                 exceeds latest block height ({block_height})."
+            )
+        }
+
+        let in_range = from_block_height..=to_block_height;
+        let slots = tables
+            .tx
+            .iter()
+            .filter(|tx| in_range.contains(&tx.block_height))
+            .map(|tx| (tx.block_height, tx.block_index))
+            .collect::<BTreeSet<_>>();
+        let slots =
+            super::paginate(slots.into_iter().collect(), Some(limit), offset);
+
+        let (Some(&first_slot), Some(&last_slot)) =
+            (slots.first(), slots.last())
+        else {
+            return Ok(vec![]);
+        };
+
+        // NB: the txs are stored in the order of their ids
+        Ok(tables
+            .tx
+            .iter()
+            .filter(|tx| {
+                let slot = (tx.block_height, tx.block_index);
+                first_slot <= slot && slot <= last_slot
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn get_tx_slots(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<Vec<(i32, i32, i32)>> {
+        let tables = self.db.lock();
+        Ok(tables
+            .tx
+            .iter()
+            .filter(|tx| {
+                (from_block_height..=to_block_height).contains(&tx.block_height)
+            })
+            .map(|tx| (tx.block_height, tx.block_index, tx.masp_tx_index))
+            .collect())
+    }
+}
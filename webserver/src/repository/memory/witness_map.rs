@@ -0,0 +1,121 @@
+use orm::memory::{MemoryDb, Tables};
+
+use crate::appstate::AppState;
+use crate::repository::witness_map::{
+    NoteWitnessRows, WitnessMapRepositoryTrait, WitnessReplayRows,
+};
+
+/// [`WitnessMapRepositoryTrait`] implementation reading from the in-memory
+/// database written by the crawler in tests.
+#[derive(Clone)]
+pub struct MemoryWitnessMapRepository {
+    db: MemoryDb,
+}
+
+impl WitnessMapRepositoryTrait for MemoryWitnessMapRepository {
+    fn new(app_state: AppState) -> Self {
+        Self {
+            db: super::memory_db(&app_state),
+        }
+    }
+
+    async fn get_note_witness(
+        &self,
+        note_position: i32,
+        block_height: Option<i32>,
+    ) -> anyhow::Result<NoteWitnessRows> {
+        let tables = self.db.lock();
+        let last_synced_height = tables.last_synced_height();
+
+        let Some(block_height) = block_height.or(last_synced_height) else {
+            return Ok((None, None, None, None));
+        };
+        let Some(closest_height) = tables.witness_checkpoint_at(block_height)
+        else {
+            return Ok((last_synced_height, None, None, None));
+        };
+
+        let witness = tables
+            .witness
+            .iter()
+            .find(|witness| {
+                witness.block_height == closest_height
+                    && witness.witness_idx == note_position
+            })
+            .cloned();
+
+        let mut anchor_height = block_height;
+        let mut tree = tables.commitment_tree_at(block_height);
+
+        if tree.is_some_and(|tree| tree.block_height > closest_height) {
+            anchor_height = closest_height;
+            tree = tables.commitment_tree_at(closest_height);
+        }
+
+        Ok((
+            last_synced_height,
+            witness,
+            tree.cloned(),
+            Some(anchor_height),
+        ))
+    }
+
+    async fn get_witness_replay_rows(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<WitnessReplayRows> {
+        let tables = self.db.lock();
+        let Some(last_synced_height) = tables.last_synced_height() else {
+            return Ok(WitnessReplayRows::default());
+        };
+
+        Ok(witness_replay_rows(
+            &tables,
+            last_synced_height,
+            block_height.min(last_synced_height),
+        ))
+    }
+}
+
+/// In-memory counterpart of [`load_witness_replay_rows`].
+///
+/// [`load_witness_replay_rows`]: crate::repository::witness_map::load_witness_replay_rows
+pub(crate) fn witness_replay_rows(
+    tables: &Tables,
+    last_synced_height: i32,
+    block_height: i32,
+) -> WitnessReplayRows {
+    let tree_height = tables
+        .commitment_tree_at(block_height)
+        .map(|tree| tree.block_height);
+    let checkpoint_height = tables.witness_checkpoint_at(block_height);
+    let (witnesses, commitment_tree) = match checkpoint_height {
+        Some(checkpoint_height) => (
+            tables.witnesses_at(checkpoint_height),
+            tables.commitment_tree_at(checkpoint_height).cloned(),
+        ),
+        None => (vec![], None),
+    };
+
+    let mut txs = tables
+        .tx
+        .iter()
+        .filter(|tx| {
+            tx.block_height > checkpoint_height.unwrap_or(0)
+                && tx.block_height <= block_height
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    // NB: stable sort, to preserve the insertion order
+    // of txs with the same keys
+    txs.sort_by_key(|tx| (tx.block_height, tx.masp_tx_index));
+
+    WitnessReplayRows {
+        last_synced_height: Some(last_synced_height),
+        tree_height,
+        checkpoint_height,
+        witnesses,
+        commitment_tree,
+        txs,
+    }
+}
@@ -1,5 +1,12 @@
+pub mod anchor;
+pub mod block;
+pub mod compact_block;
+#[cfg(feature = "test-utils")]
+pub mod memory;
 pub mod namada_state;
 pub mod notes_index;
+pub mod nullifier;
+pub mod sync;
 pub mod tree;
 pub mod tx;
 pub mod witness_map;
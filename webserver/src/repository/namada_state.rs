@@ -1,6 +1,7 @@
 use anyhow::Context;
 use diesel::dsl::max;
 use diesel::{OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper};
+use orm::chain_state::ChainStateDb;
 use shared::error::ContextDbInteractError;
 use shared::height::BlockHeight;
 use xorf::BinaryFuse16;
@@ -20,6 +21,18 @@ pub trait NamadaStateRepositoryTrait {
     async fn get_block_index(
         &self,
     ) -> anyhow::Result<Option<(i32, BinaryFuse16)>>;
+
+    async fn check_connection(&self) -> anyhow::Result<()>;
+
+    async fn get_sync_progress(&self) -> anyhow::Result<Option<ChainStateDb>>;
+
+    /// Chain id of the network indexed by the crawler, if it was
+    /// recorded.
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>>;
+
+    /// Highest block height whose masp txs, notes, nullifiers and compact
+    /// outputs were pruned by the crawler, if any.
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>>;
 }
 
 impl NamadaStateRepositoryTrait for NamadaStateRepository {
@@ -93,4 +106,78 @@ impl NamadaStateRepositoryTrait for NamadaStateRepository {
                 .transpose()
         })
     }
+
+    async fn check_connection(&self) -> anyhow::Result<()> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| diesel::sql_query("SELECT 1").execute(conn))
+            .await
+            .context_db_interact_error()?
+            .context("Failed to query the db")?;
+
+        Ok(())
+    }
+
+    async fn get_sync_progress(&self) -> anyhow::Result<Option<ChainStateDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            use orm::schema::chain_state::dsl::chain_state;
+
+            chain_state
+                .select(ChainStateDb::as_select())
+                .first(conn)
+                .optional()
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to get sync progress from db")
+    }
+
+    async fn get_chain_id(&self) -> anyhow::Result<Option<String>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            use orm::schema::chain_id;
+
+            chain_id::table
+                .select(chain_id::dsl::chain_id)
+                .first::<String>(conn)
+                .optional()
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to get chain id from db")
+    }
+
+    async fn get_pruned_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        let block_height = conn
+            .interact(move |conn| {
+                use orm::schema::pruned_height;
+
+                pruned_height::table
+                    .select(pruned_height::dsl::block_height)
+                    .first::<i32>(conn)
+                    .optional()
+            })
+            .await
+            .context_db_interact_error()?
+            .context("Failed to get pruned height from db")?;
+
+        Ok(block_height.map(BlockHeight::from))
+    }
 }
@@ -15,7 +15,11 @@ pub trait NotesIndexRepositoryTrait {
     fn new(app_state: AppState) -> Self;
     async fn get_notes_index(
         &self,
-        block_height: i32,
+        from_block_height: Option<i32>,
+        to_block_height: i32,
+        is_fee_unshielding: Option<bool>,
+        limit: Option<i64>,
+        offset: i64,
     ) -> anyhow::Result<Vec<NotesIndexDb>>;
 }
 
@@ -26,7 +30,11 @@ impl NotesIndexRepositoryTrait for NotesIndexRepository {
 
     async fn get_notes_index(
         &self,
-        block_height: i32,
+        from_block_height: Option<i32>,
+        to_block_height: i32,
+        is_fee_unshielding: Option<bool>,
+        limit: Option<i64>,
+        offset: i64,
     ) -> anyhow::Result<Vec<NotesIndexDb>> {
         let conn = self.app_state.get_db_connection().await.context(
             "Failed to retrieve connection from the pool of database \
@@ -34,16 +42,38 @@ impl NotesIndexRepositoryTrait for NotesIndexRepository {
         )?;
 
         conn.interact(move |conn| {
-            notes_index::table
-                .filter(notes_index::dsl::block_height.le(block_height))
+            let mut query = notes_index::table
+                .filter(notes_index::dsl::block_height.le(to_block_height))
+                .order((
+                    notes_index::dsl::block_height.asc(),
+                    notes_index::dsl::block_index.asc(),
+                    notes_index::dsl::masp_tx_index.asc(),
+                    notes_index::dsl::note_position.asc(),
+                ))
+                .offset(offset)
                 .select(NotesIndexDb::as_select())
-                .get_results(conn)
-                .with_context(|| {
-                    format!(
-                        "Failed to retrieve the notes map up to block height \
-                         {block_height}"
-                    )
-                })
+                .into_boxed();
+
+            if let Some(from_block_height) = from_block_height {
+                query = query.filter(
+                    notes_index::dsl::block_height.ge(from_block_height),
+                );
+            }
+            if let Some(is_fee_unshielding) = is_fee_unshielding {
+                query = query.filter(
+                    notes_index::dsl::is_fee_unshielding.eq(is_fee_unshielding),
+                );
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit);
+            }
+
+            query.get_results(conn).with_context(|| {
+                format!(
+                    "Failed to retrieve the notes map up to block height \
+                     {to_block_height}"
+                )
+            })
         })
         .await
         .context_db_interact_error()?
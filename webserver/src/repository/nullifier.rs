@@ -0,0 +1,73 @@
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use orm::nullifier::NullifierDb;
+use orm::schema::nullifiers;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct NullifierRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait NullifierRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+    async fn get_nullifiers(
+        &self,
+        from_block_height: i32,
+        to_block_height: Option<i32>,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> anyhow::Result<Vec<NullifierDb>>;
+}
+
+impl NullifierRepositoryTrait for NullifierRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_nullifiers(
+        &self,
+        from_block_height: i32,
+        to_block_height: Option<i32>,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> anyhow::Result<Vec<NullifierDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            let mut query = nullifiers::table
+                .filter(nullifiers::dsl::block_height.ge(from_block_height))
+                .order((
+                    nullifiers::dsl::block_height.asc(),
+                    nullifiers::dsl::block_index.asc(),
+                    nullifiers::dsl::masp_tx_index.asc(),
+                    nullifiers::dsl::id.asc(),
+                ))
+                .offset(offset)
+                .select(NullifierDb::as_select())
+                .into_boxed();
+
+            if let Some(to_block_height) = to_block_height {
+                query = query
+                    .filter(nullifiers::dsl::block_height.le(to_block_height));
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit);
+            }
+
+            query.get_results(conn).with_context(|| {
+                format!(
+                    "Failed to retrieve the nullifiers from block height \
+                     {from_block_height}"
+                )
+            })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
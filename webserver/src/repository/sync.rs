@@ -0,0 +1,172 @@
+use anyhow::Context;
+use diesel::dsl::max;
+use diesel::{
+    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    SelectableHelper,
+};
+use orm::notes_index::NotesIndexDb;
+use orm::schema::{chain_state, commitment_tree, notes_index, tx};
+use orm::tree::TreeDb;
+use orm::tx::TxDb;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+use crate::repository::witness_map::{
+    WitnessReplayRows, load_witness_replay_rows,
+};
+
+/// Rows needed by a light client to sync a range of blocks, all read
+/// from the same snapshot of the database.
+pub struct SyncRows {
+    pub last_synced_height: Option<i32>,
+    pub commitment_tree: Option<TreeDb>,
+    /// Rows needed to rebuild the witness map at the height of the
+    /// commitment tree.
+    pub witnesses: Option<WitnessReplayRows>,
+    pub notes_index: Vec<NotesIndexDb>,
+    pub txs: Vec<TxDb>,
+}
+
+#[derive(Clone)]
+pub struct SyncRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait SyncRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    /// Return the last synced height, along with the commitment tree at
+    /// the closest height at or below `to_block_height`, the rows needed
+    /// to rebuild the witness map up to `to_block_height`, and the notes
+    /// map and masp txs between `from_block_height` and
+    /// `to_block_height` (inclusive). The tree and witness map are not
+    /// looked up if `with_tree` is `false`.
+    async fn get_sync_rows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        with_tree: bool,
+    ) -> anyhow::Result<SyncRows>;
+}
+
+impl SyncRepositoryTrait for SyncRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_sync_rows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        with_tree: bool,
+    ) -> anyhow::Result<SyncRows> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            // NB: read all rows from the same snapshot, such that
+            // the crawler cannot commit blocks in between
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let last_synced_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to get latest block height from db")?;
+
+                    let Some(last_synced_height) = last_synced_height
+                        .filter(|&height| height >= to_block_height)
+                    else {
+                        return anyhow::Ok(SyncRows {
+                            last_synced_height,
+                            commitment_tree: None,
+                            witnesses: None,
+                            notes_index: vec![],
+                            txs: vec![],
+                        });
+                    };
+
+                    let (commitment_tree, witnesses) = if with_tree {
+                        let commitment_tree = commitment_tree::table
+                            .filter(
+                                commitment_tree::dsl::block_height
+                                    .le(to_block_height),
+                            )
+                            .order(commitment_tree::dsl::block_height.desc())
+                            .select(TreeDb::as_select())
+                            .first(conn)
+                            .optional()
+                            .with_context(|| {
+                                format!(
+                                    "Failed to look-up commitment tree in the \
+                                     database closest to the provided height \
+                                     {to_block_height}"
+                                )
+                            })?;
+
+                        // NB: the witness map is rebuilt up to the
+                        // height of the tree, from the same snapshot
+                        let witnesses = load_witness_replay_rows(
+                            conn,
+                            last_synced_height,
+                            to_block_height,
+                        )?;
+
+                        (commitment_tree, Some(witnesses))
+                    } else {
+                        (None, None)
+                    };
+
+                    let notes_index = notes_index::table
+                        .filter(
+                            notes_index::dsl::block_height
+                                .ge(from_block_height),
+                        )
+                        .filter(
+                            notes_index::dsl::block_height.le(to_block_height),
+                        )
+                        .order((
+                            notes_index::dsl::block_height.asc(),
+                            notes_index::dsl::block_index.asc(),
+                            notes_index::dsl::masp_tx_index.asc(),
+                            notes_index::dsl::note_position.asc(),
+                        ))
+                        .select(NotesIndexDb::as_select())
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to retrieve the notes map in the \
+                                 range {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    let txs = tx::table
+                        .filter(tx::dsl::block_height.ge(from_block_height))
+                        .filter(tx::dsl::block_height.le(to_block_height))
+                        .order(tx::dsl::id.asc())
+                        .select(TxDb::as_select())
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to get transactions from the database \
+                                 in the range \
+                                 {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    anyhow::Ok(SyncRows {
+                        last_synced_height: Some(last_synced_height),
+                        commitment_tree,
+                        witnesses,
+                        notes_index,
+                        txs,
+                    })
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
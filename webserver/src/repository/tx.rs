@@ -1,10 +1,12 @@
 use anyhow::Context;
+use deadpool_diesel::Connection;
+use deadpool_diesel::postgres::Object;
 use diesel::{
-    BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl,
-    RunQueryDsl, SelectableHelper,
+    BoolExpressionMethods, ExpressionMethods, OptionalExtension, PgConnection,
+    QueryDsl, RunQueryDsl, SelectableHelper,
 };
 use orm::schema::{chain_state, tx};
-use orm::tx::TxDb;
+use orm::tx::{COMMITTED_TXS_CHANNEL, TxDb};
 use shared::error::ContextDbInteractError;
 
 use crate::appstate::AppState;
@@ -16,11 +18,24 @@ pub struct TxRepository {
 
 pub trait TxRepositoryTrait {
     fn new(app_state: AppState) -> Self;
+    /// Return the masp txs of at most `limit` batches between
+    /// `from_block_height` and `to_block_height` (inclusive), after
+    /// skipping the first `offset` batches.
     async fn get_txs(
         &self,
         from_block_height: i32,
         to_block_height: i32,
+        limit: i64,
+        offset: i64,
     ) -> anyhow::Result<Vec<TxDb>>;
+
+    /// Return the block height, block index and masp tx index of the
+    /// txs between `from_block_height` and `to_block_height` (inclusive).
+    async fn get_tx_slots(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<Vec<(i32, i32, i32)>>;
 }
 
 impl TxRepositoryTrait for TxRepository {
@@ -32,6 +47,8 @@ impl TxRepositoryTrait for TxRepository {
         &self,
         from_block_height: i32,
         to_block_height: i32,
+        limit: i64,
+        offset: i64,
     ) -> anyhow::Result<Vec<TxDb>> {
         let conn = self.app_state.get_db_connection().await.context(
             "Failed to retrieve connection from the pool of database \
@@ -39,40 +56,181 @@ impl TxRepositoryTrait for TxRepository {
         )?;
 
         conn.interact(move |conn| {
-            conn.build_transaction().read_only().run(move |conn| {
-                let block_height: i32 = chain_state::table
-                    .select(chain_state::dsl::block_height)
-                    .get_result(conn)
-                    .optional()
-                    .with_context(|| {
-                        "Failed to get the latest block height from the \
-                         database"
-                    })?
-                    .unwrap_or_default();
-                if block_height < to_block_height {
-                    anyhow::bail!(
-                        "Requested range {from_block_height} -- \
-                         {to_block_height} exceeds latest block height \
-                         ({block_height})."
+            conn.build_transaction().read_only().repeatable_read().run(
+                move |conn| {
+                    let block_height: i32 = chain_state::table
+                        .select(chain_state::dsl::block_height)
+                        .get_result(conn)
+                        .optional()
+                        .with_context(|| {
+                            "Failed to get the latest block height from the \
+                             database"
+                        })?
+                        .unwrap_or_default();
+                    if block_height < to_block_height {
+                        anyhow::bail!(
+                            "Requested range {from_block_height} -- \
+                             {to_block_height} exceeds latest block height \
+                             ({block_height})."
+                        )
+                    }
+
+                    let in_range = tx::dsl::block_height
+                        .ge(from_block_height)
+                        .and(tx::dsl::block_height.le(to_block_height));
+
+                    // NB: paginate over the slots of the batches, such
+                    // that a batch is never split across pages
+                    let slots: Vec<(i32, i32)> = tx::table
+                        .filter(in_range)
+                        .select((tx::dsl::block_height, tx::dsl::block_index))
+                        .distinct()
+                        .order((
+                            tx::dsl::block_height.asc(),
+                            tx::dsl::block_index.asc(),
+                        ))
+                        .limit(limit)
+                        .offset(offset)
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to get transaction slots from the \
+                                 database in the range \
+                                 {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    let (Some(&first_slot), Some(&last_slot)) =
+                        (slots.first(), slots.last())
+                    else {
+                        return anyhow::Ok(vec![]);
+                    };
+
+                    let txs = tx::table
+                        .filter(
+                            tx::dsl::block_height
+                                .ge(first_slot.0)
+                                .and(tx::dsl::block_height.le(last_slot.0)),
+                        )
+                        .order(tx::dsl::id.asc())
+                        .select(TxDb::as_select())
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to get transactions from the database \
+                                 in the range \
+                                 {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    anyhow::Ok(
+                        txs.into_iter()
+                            .filter(|tx| {
+                                let slot = (tx.block_height, tx.block_index);
+                                first_slot <= slot && slot <= last_slot
+                            })
+                            .collect(),
                     )
-                }
-                tx::table
-                    .filter(
-                        tx::dsl::block_height
-                            .ge(from_block_height)
-                            .and(tx::dsl::block_height.le(to_block_height)),
+                },
+            )
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn get_tx_slots(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<Vec<(i32, i32, i32)>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            tx::table
+                .filter(
+                    tx::dsl::block_height
+                        .ge(from_block_height)
+                        .and(tx::dsl::block_height.le(to_block_height)),
+                )
+                .order(tx::dsl::id.asc())
+                .select((
+                    tx::dsl::block_height,
+                    tx::dsl::block_index,
+                    tx::dsl::masp_tx_index,
+                ))
+                .get_results(conn)
+                .with_context(|| {
+                    format!(
+                        "Failed to get transactions from the database in the \
+                         range {from_block_height}-{to_block_height}"
                     )
-                    .select(TxDb::as_select())
-                    .get_results(conn)
-                    .with_context(|| {
-                        format!(
-                            "Failed to get transations from the database in \
-                             the range {from_block_height}-{to_block_height}"
-                        )
-                    })
-            })
+                })
         })
         .await
         .context_db_interact_error()?
     }
 }
+
+/// Dedicated database connection listening for notifications of
+/// committed masp txs. The connection is taken out of the pool, such
+/// that it is never handed out while still listening, and closed once
+/// the listener is dropped.
+pub struct CommittedTxsListener {
+    conn: Connection<PgConnection>,
+}
+
+impl CommittedTxsListener {
+    pub async fn new(app_state: &AppState) -> anyhow::Result<Self> {
+        let conn = app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+        let conn = Object::take(conn);
+
+        conn.interact(|conn| {
+            diesel::sql_query(format!("LISTEN {COMMITTED_TXS_CHANNEL}"))
+                .execute(conn)
+                .context("Failed to listen for committed masp txs")
+        })
+        .await
+        .context_db_interact_error()??;
+
+        Ok(Self { conn })
+    }
+
+    /// Return the range of block heights of the masp txs committed since
+    /// the previous call, if any.
+    pub async fn poll(&self) -> anyhow::Result<Option<(i32, i32)>> {
+        let payloads = self
+            .conn
+            .interact(|conn| {
+                conn.notifications_iter()
+                    .map(|notification| notification.map(|n| n.payload))
+                    .collect::<Result<Vec<_>, _>>()
+                    .context("Failed to receive notifications from db")
+            })
+            .await
+            .context_db_interact_error()??;
+
+        payloads.into_iter().try_fold(None, |range, payload| {
+            let (from, to) = payload
+                .split_once(':')
+                .and_then(|(from, to)| {
+                    Some((from.parse().ok()?, to.parse().ok()?))
+                })
+                .with_context(|| {
+                    format!(
+                        "Invalid committed masp txs notification {payload:?}"
+                    )
+                })?;
+
+            anyhow::Ok(Some(match range {
+                Some((min, max)) => (i32::min(min, from), i32::max(max, to)),
+                None => (from, to),
+            }))
+        })
+    }
+}
@@ -1,14 +1,39 @@
 use anyhow::Context;
+use diesel::dsl::max;
 use diesel::{
-    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
     SelectableHelper,
 };
-use orm::schema::witness;
+use orm::schema::{chain_state, commitment_tree, tx, witness};
+use orm::tree::TreeDb;
+use orm::tx::TxDb;
 use orm::witness::WitnessDb;
 use shared::error::ContextDbInteractError;
 
 use crate::appstate::AppState;
-use crate::utils::sql::abs;
+
+/// Last synced height, witness, commitment tree and anchor height.
+pub type NoteWitnessRows =
+    (Option<i32>, Option<WitnessDb>, Option<TreeDb>, Option<i32>);
+
+/// Rows needed to rebuild the witness map at some block height, all read
+/// from the same snapshot of the database.
+#[derive(Default)]
+pub struct WitnessReplayRows {
+    pub last_synced_height: Option<i32>,
+    /// Height of the last commitment tree at or below the height the
+    /// witness map is rebuilt at.
+    pub tree_height: Option<i32>,
+    /// Height of the last witness map checkpoint at or below the
+    /// requested height.
+    pub checkpoint_height: Option<i32>,
+    pub witnesses: Vec<WitnessDb>,
+    /// Commitment tree as of the checkpoint height.
+    pub commitment_tree: Option<TreeDb>,
+    /// Masp txs committed after the checkpoint, up to the requested
+    /// height, sorted in the order they were processed in.
+    pub txs: Vec<TxDb>,
+}
 
 #[derive(Clone)]
 pub struct WitnessMapRepository {
@@ -17,10 +42,26 @@ pub struct WitnessMapRepository {
 
 pub trait WitnessMapRepositoryTrait {
     fn new(app_state: AppState) -> Self;
-    async fn get_witnesses(
+
+    /// Return the last synced height, along with the witness of the note
+    /// at `note_position` and the commitment tree, both taken from the
+    /// closest height at or below `block_height`, and the height of the
+    /// anchor they agree on. If `block_height` is `None`, the last synced
+    /// height is used instead.
+    async fn get_note_witness(
+        &self,
+        note_position: i32,
+        block_height: Option<i32>,
+    ) -> anyhow::Result<NoteWitnessRows>;
+
+    /// Return the last synced height, along with the last witness map
+    /// checkpoint at or below `block_height`, and the commitment tree
+    /// and masp txs needed to bring it up to `block_height`. Heights
+    /// above the last synced height are clamped down to it.
+    async fn get_witness_replay_rows(
         &self,
         block_height: i32,
-    ) -> anyhow::Result<(Vec<WitnessDb>, i32)>;
+    ) -> anyhow::Result<WitnessReplayRows>;
 }
 
 impl WitnessMapRepositoryTrait for WitnessMapRepository {
@@ -28,47 +69,235 @@ impl WitnessMapRepositoryTrait for WitnessMapRepository {
         Self { app_state }
     }
 
-    async fn get_witnesses(
+    async fn get_note_witness(
+        &self,
+        note_position: i32,
+        block_height: Option<i32>,
+    ) -> anyhow::Result<NoteWitnessRows> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            // NB: read the witness and the tree from the same snapshot,
+            // such that they are consistent with each other
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let last_synced_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to get latest block height from db")?;
+
+                    let Some(block_height) =
+                        block_height.or(last_synced_height)
+                    else {
+                        return anyhow::Ok((None, None, None, None));
+                    };
+
+                    let closest_height = witness::table
+                        .filter(witness::dsl::block_height.le(block_height))
+                        .select(max(witness::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to fetch height from the db closest \
+                                 to the provided height {block_height}"
+                            )
+                        })?;
+
+                    let Some(closest_height) = closest_height else {
+                        return anyhow::Ok((
+                            last_synced_height,
+                            None,
+                            None,
+                            None,
+                        ));
+                    };
+
+                    let witness = witness::table
+                        .filter(witness::dsl::block_height.eq(closest_height))
+                        .filter(witness::dsl::witness_idx.eq(note_position))
+                        .select(WitnessDb::as_select())
+                        .first(conn)
+                        .optional()
+                        .with_context(|| {
+                            format!(
+                                "Failed to fetch witness of note \
+                                 {note_position} from the db at height \
+                                 {closest_height}"
+                            )
+                        })?;
+
+                    let get_tree =
+                        |conn: &mut PgConnection, block_height: i32| {
+                            commitment_tree::table
+                                .filter(
+                                    commitment_tree::dsl::block_height
+                                        .le(block_height),
+                                )
+                                .order(
+                                    commitment_tree::dsl::block_height.desc(),
+                                )
+                                .select(TreeDb::as_select())
+                                .first(conn)
+                                .optional()
+                                .with_context(|| {
+                                    format!(
+                                        "Failed to look-up commitment tree in \
+                                         the database closest to the provided \
+                                         height {block_height}"
+                                    )
+                                })
+                        };
+
+                    let mut anchor_height = block_height;
+                    let mut tree = get_tree(conn, block_height)?;
+
+                    // NB: the witness map may have been checkpointed before
+                    // the tree last changed, in which case the witness is
+                    // served against the anchor at the checkpoint height
+                    if tree
+                        .as_ref()
+                        .is_some_and(|tree| tree.block_height > closest_height)
+                    {
+                        anchor_height = closest_height;
+                        tree = get_tree(conn, closest_height)?;
+                    }
+
+                    anyhow::Ok((
+                        last_synced_height,
+                        witness,
+                        tree,
+                        Some(anchor_height),
+                    ))
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn get_witness_replay_rows(
         &self,
         block_height: i32,
-    ) -> anyhow::Result<(Vec<WitnessDb>, i32)> {
+    ) -> anyhow::Result<WitnessReplayRows> {
         let conn = self.app_state.get_db_connection().await.context(
             "Failed to retrieve connection from the pool of database \
              connections",
         )?;
 
         conn.interact(move |conn| {
-            let Some(closest_height) = witness::table
-                .order(abs(witness::dsl::block_height - block_height).asc())
-                .filter(witness::dsl::block_height.le(block_height))
-                .select(witness::dsl::block_height)
-                .first(conn)
-                .optional()
-                .with_context(|| {
-                    format!(
-                        "Failed to fetch height from the db closest to the \
-                         provided height {block_height}"
+            // NB: read the checkpoint and the txs from the same snapshot,
+            // such that the crawler cannot commit blocks in between
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let last_synced_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to get latest block height from db")?;
+
+                    let Some(last_synced_height) = last_synced_height else {
+                        return anyhow::Ok(WitnessReplayRows::default());
+                    };
+
+                    load_witness_replay_rows(
+                        conn,
+                        last_synced_height,
+                        block_height.min(last_synced_height),
                     )
-                })?
-            else {
-                return anyhow::Ok((vec![], block_height));
-            };
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
+
+/// Read the rows needed to rebuild the witness map at `block_height`,
+/// which must not be above `last_synced_height`. Meant to be called from
+/// within a repeatable read transaction.
+pub(crate) fn load_witness_replay_rows(
+    conn: &mut PgConnection,
+    last_synced_height: i32,
+    block_height: i32,
+) -> anyhow::Result<WitnessReplayRows> {
+    let tree_height = commitment_tree::table
+        .filter(commitment_tree::dsl::block_height.le(block_height))
+        .select(max(commitment_tree::dsl::block_height))
+        .first::<Option<i32>>(conn)
+        .with_context(|| {
+            format!(
+                "Failed to look-up commitment tree height in the database \
+                 closest to the provided height {block_height}"
+            )
+        })?;
+
+    let checkpoint_height = witness::table
+        .filter(witness::dsl::block_height.le(block_height))
+        .select(max(witness::dsl::block_height))
+        .first::<Option<i32>>(conn)
+        .with_context(|| {
+            format!(
+                "Failed to fetch height from the db closest to the provided \
+                 height {block_height}"
+            )
+        })?;
 
+    let (witnesses, commitment_tree) = match checkpoint_height {
+        Some(checkpoint_height) => {
             let witnesses = witness::table
-                .filter(witness::dsl::block_height.eq(closest_height))
+                .filter(witness::dsl::block_height.eq(checkpoint_height))
                 .select(WitnessDb::as_select())
-                .get_results::<WitnessDb>(conn)
+                .get_results(conn)
                 .with_context(|| {
                     format!(
                         "Failed to fetch witnesses from the db at height \
-                         {closest_height} (the closest to the provided height \
-                         {block_height})"
+                         {checkpoint_height}"
                     )
                 })?;
 
-            anyhow::Ok((witnesses, closest_height))
-        })
-        .await
-        .context_db_interact_error()?
-    }
+            let commitment_tree = commitment_tree::table
+                .filter(
+                    commitment_tree::dsl::block_height.le(checkpoint_height),
+                )
+                .order(commitment_tree::dsl::block_height.desc())
+                .select(TreeDb::as_select())
+                .first(conn)
+                .optional()
+                .with_context(|| {
+                    format!(
+                        "Failed to look-up commitment tree in the database \
+                         closest to the provided height {checkpoint_height}"
+                    )
+                })?;
+
+            (witnesses, commitment_tree)
+        }
+        None => (vec![], None),
+    };
+
+    let txs = tx::table
+        .filter(tx::dsl::block_height.gt(checkpoint_height.unwrap_or(0)))
+        .filter(tx::dsl::block_height.le(block_height))
+        .order((tx::dsl::block_height.asc(), tx::dsl::masp_tx_index.asc()))
+        .select(TxDb::as_select())
+        .get_results(conn)
+        .with_context(|| {
+            format!(
+                "Failed to get transactions from the database up to height \
+                 {block_height}"
+            )
+        })?;
+
+    Ok(WitnessReplayRows {
+        last_synced_height: Some(last_synced_height),
+        tree_height,
+        checkpoint_height,
+        witnesses,
+        commitment_tree,
+        txs,
+    })
 }
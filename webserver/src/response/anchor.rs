@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::service::anchor::Anchor;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct AnchorResponse {
+    pub anchor: Vec<u8>,
+    pub tree_size: u64,
+    pub block_height: u64,
+}
+
+impl From<Anchor> for AnchorResponse {
+    fn from(anchor: Anchor) -> Self {
+        Self {
+            anchor: anchor.root,
+            tree_size: anchor.tree_size,
+            block_height: anchor.block_height,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct AnchorsResponse {
+    pub anchors: Vec<AnchorResponse>,
+}
+
+impl AnchorsResponse {
+    pub fn new(anchors: Vec<Anchor>) -> Self {
+        Self {
+            anchors: anchors.into_iter().map(AnchorResponse::from).collect(),
+        }
+    }
+}
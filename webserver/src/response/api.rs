@@ -8,11 +8,60 @@ pub struct ApiSuccessResponse<T: Serialize> {
     data: T,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// Machine readable code of an error, from which its HTTP status is
+/// derived.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidRequest,
+    InvalidRange,
+    RangeTooLarge,
+    InvalidTimestamp,
+    Unauthorized,
+    NotFound,
+    HeightNotSynced,
+    HeightNotIndexed,
+    NothingSynced,
+    NoteNotTracked,
+    NoBlockAtTime,
+    Timeout,
+    Pruned,
+    RateLimited,
+    Internal,
+    Unavailable,
+}
+
+impl ErrorCode {
+    pub fn status_code(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::InvalidRange
+            | ErrorCode::RangeTooLarge
+            | ErrorCode::InvalidTimestamp => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::NotFound
+            | ErrorCode::HeightNotSynced
+            | ErrorCode::HeightNotIndexed
+            | ErrorCode::NothingSynced
+            | ErrorCode::NoteNotTracked
+            | ErrorCode::NoBlockAtTime => StatusCode::NOT_FOUND,
+            ErrorCode::Timeout => StatusCode::REQUEST_TIMEOUT,
+            ErrorCode::Pruned => StatusCode::GONE,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::Unavailable => StatusCode::NOT_IMPLEMENTED,
+        }
+    }
+}
+
+/// Body of all error responses.
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct ApiErrorResponse {
-    message: Option<String>,
-    #[serde(rename = "code")]
-    status: u16,
+    code: ErrorCode,
+    message: String,
+    /// Values the error refers to, e.g. the requested block height.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<serde_json::Value>,
 }
 
 impl<T: Serialize> ApiSuccessResponse<T>
@@ -26,18 +75,34 @@ where
 }
 
 impl ApiErrorResponse {
-    pub(crate) fn send(status: u16, message: Option<String>) -> Response {
-        ApiErrorResponse { message, status }.into_response()
+    pub(crate) fn send(
+        code: ErrorCode,
+        message: impl Into<String>,
+    ) -> Response {
+        ApiErrorResponse {
+            code,
+            message: message.into(),
+            details: None,
+        }
+        .into_response()
+    }
+
+    pub(crate) fn send_with_details(
+        code: ErrorCode,
+        message: impl Into<String>,
+        details: serde_json::Value,
+    ) -> Response {
+        ApiErrorResponse {
+            code,
+            message: message.into(),
+            details: Some(details),
+        }
+        .into_response()
     }
 }
 
 impl IntoResponse for ApiErrorResponse {
     fn into_response(self) -> Response {
-        (
-            StatusCode::from_u16(self.status)
-                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-            Json(self),
-        )
-            .into_response()
+        (self.code.status_code(), Json(self)).into_response()
     }
 }
@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::service::block::BlockMetadata;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlockResponse {
+    pub block_height: u64,
+    pub hash: String,
+    pub timestamp: Option<NaiveDateTime>,
+    pub epoch: Option<u64>,
+}
+
+impl From<BlockMetadata> for BlockResponse {
+    fn from(block: BlockMetadata) -> Self {
+        Self {
+            block_height: block.block_height,
+            hash: block.hash,
+            timestamp: block.timestamp,
+            epoch: block.epoch,
+        }
+    }
+}
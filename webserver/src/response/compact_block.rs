@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use namada_core::borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+
+use crate::service::compact_block as service;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct CompactBlocksResponse {
+    pub from_block_height: u64,
+    pub to_block_height: u64,
+    pub blocks: Vec<CompactBlock>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct CompactBlock {
+    pub block_height: u64,
+    pub txs: Vec<CompactTx>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct CompactTx {
+    pub block_index: u64,
+    pub masp_tx_index: u64,
+    pub nullifiers: Vec<Vec<u8>>,
+    pub outputs: Vec<CompactOutput>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct CompactOutput {
+    pub cmu: Vec<u8>,
+    pub ephemeral_key: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl CompactBlocksResponse {
+    pub fn new(
+        from_block_height: u64,
+        to_block_height: u64,
+        blocks: BTreeMap<u64, service::CompactBlock>,
+    ) -> Self {
+        Self {
+            from_block_height,
+            to_block_height,
+            blocks: blocks
+                .into_iter()
+                .map(|(block_height, txs)| CompactBlock {
+                    block_height,
+                    txs: txs
+                        .into_iter()
+                        .map(|((block_index, masp_tx_index), tx)| CompactTx {
+                            block_index,
+                            masp_tx_index,
+                            nullifiers: tx.nullifiers,
+                            outputs: tx
+                                .outputs
+                                .into_iter()
+                                .map(|output| CompactOutput {
+                                    cmu: output.cmu,
+                                    ephemeral_key: output.ephemeral_key,
+                                    ciphertext: output.ciphertext,
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Encodes the same fields as the JSON response, in declaration order.
+impl BorshSerialize for CompactBlocksResponse {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.from_block_height.serialize(writer)?;
+        self.to_block_height.serialize(writer)?;
+        self.blocks.serialize(writer)
+    }
+}
+
+impl BorshSerialize for CompactBlock {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.block_height.serialize(writer)?;
+        self.txs.serialize(writer)
+    }
+}
+
+impl BorshSerialize for CompactTx {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.block_index.serialize(writer)?;
+        self.masp_tx_index.serialize(writer)?;
+        self.nullifiers.serialize(writer)?;
+        self.outputs.serialize(writer)
+    }
+}
+
+impl BorshSerialize for CompactOutput {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.cmu.serialize(writer)?;
+        self.ephemeral_key.serialize(writer)?;
+        self.ciphertext.serialize(writer)
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use xorf::BinaryFuse16;
 
@@ -11,3 +12,35 @@ pub struct BlockIndexResponse {
     pub block_height: u64,
     pub index: BinaryFuse16,
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InfoResponse {
+    pub chain_id: Option<String>,
+    /// Highest block height whose masp txs, notes, nullifiers and compact
+    /// outputs were pruned.
+    pub pruned_height: Option<u64>,
+    pub version: String,
+    pub commit: String,
+    pub notes_map_only: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HealthResponse {
+    pub commit: String,
+    pub version: String,
+    pub database: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReadinessResponse {
+    pub database: bool,
+    /// Whether CometBFT is reachable, or `None` if it is not configured.
+    pub cometbft: Option<bool>,
+    pub last_synced_height: Option<u64>,
+    pub chain_tip: Option<u64>,
+    pub lag_blocks: Option<u64>,
+    pub caught_up: bool,
+    pub last_commit_at: Option<NaiveDateTime>,
+    /// Whether the last commit is older than the configured bound.
+    pub stale: bool,
+}
@@ -1,38 +1,21 @@
 use serde::{Deserialize, Serialize};
+pub use shared::response::{Note, NotesIndexResponse};
 
+/// Notes pushed to subscribers of the notes map stream.
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct NotesIndexResponse {
+pub struct NotesIndexEvent {
     pub notes_index: Vec<Note>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct Note {
-    pub block_height: u64,
-    pub block_index: u64,
-    pub masp_tx_index: u64,
-    pub note_position: u64,
-}
-
-impl NotesIndexResponse {
-    pub fn new(notes_index: Vec<(u64, u64, u64, u64)>) -> Self {
+impl NotesIndexEvent {
+    pub fn new<'a>(
+        notes_index: impl IntoIterator<Item = &'a (u64, u64, u64, u64, bool)>,
+    ) -> Self {
         Self {
             notes_index: notes_index
                 .into_iter()
-                .map(
-                    |(
-                        block_height,
-                        block_index,
-                        masp_tx_index,
-                        note_position,
-                    )| {
-                        Note {
-                            block_height,
-                            block_index,
-                            masp_tx_index,
-                            note_position,
-                        }
-                    },
-                )
+                .copied()
+                .map(Note::from)
                 .collect(),
         }
     }
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct NullifiersResponse {
+    pub nullifiers: Vec<Nullifier>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct Nullifier {
+    pub nullifier: Vec<u8>,
+    pub block_height: u64,
+    pub block_index: u64,
+    pub masp_tx_index: u64,
+}
+
+impl NullifiersResponse {
+    pub fn new(
+        nullifiers: Vec<(Vec<u8>, u64, u64, u64)>,
+        has_more: bool,
+    ) -> Self {
+        Self {
+            nullifiers: nullifiers
+                .into_iter()
+                .map(|(nullifier, block_height, block_index, masp_tx_index)| {
+                    Nullifier {
+                        nullifier,
+                        block_height,
+                        block_index,
+                        masp_tx_index,
+                    }
+                })
+                .collect(),
+            has_more,
+        }
+    }
+}
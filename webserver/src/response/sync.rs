@@ -0,0 +1,35 @@
+pub use shared::response::SyncResponse;
+
+use crate::response::notes_index::Note;
+use crate::response::tree::TreeResponse;
+use crate::response::tx::TxResponse;
+use crate::response::witness_map::WitnessMapResponse;
+use crate::service::sync::SyncData;
+
+/// Build the response to a request to sync the blocks between
+/// `from_block_height` and `to_block_height`.
+pub fn sync_response(
+    from_block_height: u64,
+    to_block_height: u64,
+    sync_data: SyncData,
+) -> SyncResponse {
+    SyncResponse {
+        from_block_height,
+        to_block_height,
+        commitment_tree: sync_data.commitment_tree.map(
+            |(commitment_tree, block_height)| TreeResponse {
+                commitment_tree,
+                block_height,
+            },
+        ),
+        witness_map: sync_data.witnesses.map(|(witnesses, block_height)| {
+            WitnessMapResponse::new(block_height.into(), witnesses)
+        }),
+        notes_index: sync_data
+            .notes_index
+            .into_iter()
+            .map(Note::from)
+            .collect(),
+        txs: TxResponse::new(sync_data.txs, false).txs,
+    }
+}
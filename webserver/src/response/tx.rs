@@ -1,42 +1,36 @@
 use serde::{Deserialize, Serialize};
+pub use shared::response::TxResponse;
 
-#[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct TxResponse {
-    pub txs: Vec<Tx>,
-}
+use crate::service::tx_stream::CommittedBlock;
 
+/// Masp txs of a newly committed block, pushed to subscribers of the
+/// txs stream.
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct Tx {
+pub struct TxsEvent {
     pub block_height: u64,
-    pub block_index: u64,
-    pub batch: Vec<TxSlot>,
+    pub txs: Vec<TxPosition>,
+    pub note_positions: Vec<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct TxSlot {
+pub struct TxPosition {
+    pub block_index: u64,
     pub masp_tx_index: u64,
-    pub bytes: Vec<u8>,
 }
 
-impl TxResponse {
-    pub fn new(
-        txs: impl IntoIterator<Item = (Vec<(u64, Vec<u8>)>, u64, u64)>,
-    ) -> Self {
+impl From<&CommittedBlock> for TxsEvent {
+    fn from(block: &CommittedBlock) -> Self {
         Self {
-            txs: txs
-                .into_iter()
-                .map(|(batch, block_height, block_index)| Tx {
-                    batch: batch
-                        .into_iter()
-                        .map(|(masp_tx_index, bytes)| TxSlot {
-                            masp_tx_index,
-                            bytes,
-                        })
-                        .collect(),
-                    block_height,
+            block_height: block.block_height,
+            txs: block
+                .txs
+                .iter()
+                .map(|&(block_index, masp_tx_index)| TxPosition {
                     block_index,
+                    masp_tx_index,
                 })
                 .collect(),
+            note_positions: block.note_positions.clone(),
         }
     }
 }
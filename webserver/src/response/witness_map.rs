@@ -1,29 +1,36 @@
+use std::io::{self, Write};
+
+use namada_core::borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
-use shared::height::BlockHeight;
+pub use shared::response::WitnessMapResponse;
+
+use crate::service::witness_map::NoteWitness;
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct WitnessMapResponse {
-    pub witnesses: Vec<Witness>,
+pub struct WitnessResponse {
+    pub note_position: u64,
+    pub path: Vec<u8>,
+    pub anchor: Vec<u8>,
     pub block_height: u64,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, Default)]
-pub struct Witness {
-    pub bytes: Vec<u8>,
-    pub index: u64,
+/// Encodes the same fields as the JSON response, in declaration order.
+impl BorshSerialize for WitnessResponse {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.note_position.serialize(writer)?;
+        self.path.serialize(writer)?;
+        self.anchor.serialize(writer)?;
+        self.block_height.serialize(writer)
+    }
 }
 
-impl WitnessMapResponse {
-    pub fn new(
-        block_height: BlockHeight,
-        witnesses: Vec<(Vec<u8>, u64)>,
-    ) -> Self {
+impl From<NoteWitness> for WitnessResponse {
+    fn from(witness: NoteWitness) -> Self {
         Self {
-            witnesses: witnesses
-                .into_iter()
-                .map(|(bytes, index)| Witness { bytes, index })
-                .collect(),
-            block_height: block_height.0,
+            note_position: witness.note_position,
+            path: witness.path,
+            anchor: witness.anchor.root,
+            block_height: witness.anchor.block_height,
         }
     }
 }
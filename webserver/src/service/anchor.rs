@@ -0,0 +1,127 @@
+use anyhow::Context;
+use namada_core::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_core::masp_primitives::merkle_tree::CommitmentTree;
+use namada_core::masp_primitives::sapling::Node;
+use orm::anchor::AnchorDb;
+use orm::tree::TreeDb;
+
+use crate::appstate::AppState;
+use crate::repository::anchor::{AnchorRepository, AnchorRepositoryTrait};
+
+/// Anchor of the commitment tree at some block height.
+pub struct Anchor {
+    pub root: Vec<u8>,
+    pub tree_size: u64,
+    pub block_height: u64,
+}
+
+#[derive(Clone)]
+pub struct AnchorService {
+    anchor_repo: AnchorRepository,
+}
+
+impl AnchorService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            anchor_repo: AnchorRepository::new(app_state),
+        }
+    }
+
+    /// Return the anchor at `block_height`, or `None` if the height has
+    /// not been synced yet.
+    pub async fn get_at_height(
+        &self,
+        block_height: u64,
+    ) -> anyhow::Result<Option<Anchor>> {
+        let (last_synced_height, recorded) = self
+            .anchor_repo
+            .get_recorded(block_height as i32, block_height as i32)
+            .await?;
+
+        if last_synced_height.is_none_or(|h| block_height > h as u64) {
+            return Ok(None);
+        }
+        if let Some(anchor) = recorded.into_iter().next() {
+            return Ok(Some(anchor.into()));
+        }
+
+        // NB: blocks committed before anchors were recorded get theirs
+        // computed from the closest commitment tree
+        let (last_synced_height, tree) = self
+            .anchor_repo
+            .get_at_height(Some(block_height as i32))
+            .await?;
+
+        if last_synced_height.is_none_or(|h| block_height > h as u64) {
+            return Ok(None);
+        }
+
+        Self::compute_anchor(tree, block_height).map(Some)
+    }
+
+    /// Return the anchors recorded for the blocks between `from` and
+    /// `to` (inclusive), or `None` if `to` has not been synced yet.
+    /// Blocks committed before anchors were recorded are left out.
+    pub async fn get_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> anyhow::Result<Option<Vec<Anchor>>> {
+        let (last_synced_height, anchors) = self
+            .anchor_repo
+            .get_recorded(from as i32, to as i32)
+            .await?;
+
+        if last_synced_height.is_none_or(|h| to > h as u64) {
+            return Ok(None);
+        }
+
+        Ok(Some(anchors.into_iter().map(Anchor::from).collect()))
+    }
+
+    /// Return the anchor at the last synced height, or `None` if no
+    /// blocks have been synced yet.
+    pub async fn get_latest(&self) -> anyhow::Result<Option<Anchor>> {
+        let (last_synced_height, tree) =
+            self.anchor_repo.get_at_height(None).await?;
+
+        last_synced_height
+            .map(|h| Self::compute_anchor(tree, h as u64))
+            .transpose()
+    }
+
+    pub(crate) fn compute_anchor(
+        tree: Option<TreeDb>,
+        block_height: u64,
+    ) -> anyhow::Result<Anchor> {
+        // NB: no tree below a synced height means no notes
+        // have been committed yet
+        let tree = tree
+            .map(|tree| {
+                tokio::task::block_in_place(|| {
+                    CommitmentTree::<Node>::try_from_slice(&tree.tree)
+                })
+                .context(
+                    "Failed to deserialize commitment tree returned from db",
+                )
+            })
+            .transpose()?
+            .unwrap_or_else(CommitmentTree::empty);
+
+        Ok(Anchor {
+            root: tree.root().serialize_to_vec(),
+            tree_size: tree.size() as u64,
+            block_height,
+        })
+    }
+}
+
+impl From<AnchorDb> for Anchor {
+    fn from(anchor: AnchorDb) -> Self {
+        Self {
+            root: anchor.root,
+            tree_size: anchor.tree_size as u64,
+            block_height: anchor.block_height as u64,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+use orm::block_hash::BlockHashDb;
+
+use crate::appstate::AppState;
+use crate::repository::block::{BlockRepository, BlockRepositoryTrait};
+
+/// Metadata of an indexed block.
+pub struct BlockMetadata {
+    pub block_height: u64,
+    pub hash: String,
+    /// Time the block was committed at, unknown for blocks indexed
+    /// before timestamps were recorded.
+    pub timestamp: Option<NaiveDateTime>,
+    /// Epoch of the block, unknown for blocks indexed before epochs
+    /// were recorded.
+    pub epoch: Option<u64>,
+}
+
+impl From<BlockHashDb> for BlockMetadata {
+    fn from(block: BlockHashDb) -> Self {
+        Self {
+            block_height: block.block_height as u64,
+            hash: block.hash,
+            timestamp: block.timestamp,
+            epoch: block.epoch.map(|epoch| epoch as u64),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct BlockService {
+    block_repo: BlockRepository,
+}
+
+impl BlockService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            block_repo: BlockRepository::new(app_state),
+        }
+    }
+
+    /// Return the metadata of the block at `block_height`, or `None` if
+    /// it has not been indexed.
+    pub async fn get_at_height(
+        &self,
+        block_height: u64,
+    ) -> anyhow::Result<Option<BlockMetadata>> {
+        Ok(self
+            .block_repo
+            .get_at_height(block_height as i32)
+            .await?
+            .map(BlockMetadata::from))
+    }
+
+    /// Return the metadata of the last indexed block committed at or
+    /// before `timestamp`, or `None` if there is no such block.
+    pub async fn get_at_time(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<Option<BlockMetadata>> {
+        Ok(self
+            .block_repo
+            .get_at_time(timestamp)
+            .await?
+            .map(BlockMetadata::from))
+    }
+}
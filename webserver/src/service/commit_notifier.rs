@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+
+use crate::appstate::AppState;
+use crate::repository::namada_state::{
+    NamadaStateRepository, NamadaStateRepositoryTrait,
+};
+use crate::repository::tx::CommittedTxsListener;
+
+/// Number of ranges buffered for each subscriber, before it starts
+/// lagging behind.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Forwards the notifications of blocks with masp txs committed by the
+/// crawler, as ranges of block heights (both inclusive), to the
+/// streaming services.
+#[derive(Clone)]
+pub struct CommitNotifier {
+    sender: broadcast::Sender<(i32, i32)>,
+    app_state: AppState,
+    namada_state_repo: NamadaStateRepository,
+}
+
+impl CommitNotifier {
+    /// Create a new notifier, and spawn a task that checks for
+    /// notifications of committed masp txs every `poll_interval`.
+    pub fn new(app_state: AppState, poll_interval: Duration) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        let notifier = Self {
+            sender,
+            namada_state_repo: NamadaStateRepository::new(app_state.clone()),
+            app_state,
+        };
+
+        tokio::spawn(notifier.clone().listen(poll_interval));
+
+        notifier
+    }
+
+    /// Subscribe to the ranges of block heights with masp txs committed
+    /// from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<(i32, i32)> {
+        self.sender.subscribe()
+    }
+
+    async fn listen(self, poll_interval: Duration) {
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // NB: last synced height as of the last forwarded range, from
+        // which forwarding resumes once listening on a new connection
+        let mut last_height = None;
+
+        loop {
+            interval.tick().await;
+
+            let listener =
+                match CommittedTxsListener::new(&self.app_state).await {
+                    Ok(listener) => listener,
+                    Err(err) => {
+                        tracing::warn!(
+                            reason = err.to_string(),
+                            "Failed to listen for committed masp txs"
+                        );
+                        continue;
+                    }
+                };
+
+            // NB: notifications sent while no connection was listening
+            // are lost, so the blocks committed in the meantime are
+            // forwarded instead. blocks committed right after listening
+            // again may be forwarded twice
+            let latest_height =
+                match self.namada_state_repo.get_latest_height().await {
+                    Ok(latest_height) => latest_height.map(|h| h.0 as i32),
+                    Err(err) => {
+                        tracing::warn!(
+                            reason = err.to_string(),
+                            "Failed to read the last synced height"
+                        );
+                        continue;
+                    }
+                };
+            let missed = last_height.zip(latest_height).filter(
+                |(last_height, latest_height)| latest_height > last_height,
+            );
+            if let Some((last_height, latest_height)) = missed {
+                _ = self.sender.send((last_height + 1, latest_height));
+            }
+            last_height = latest_height.or(last_height);
+
+            loop {
+                interval.tick().await;
+
+                let range = match listener.poll().await {
+                    Ok(Some(range)) => range,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        // NB: the connection may have been closed,
+                        // so start listening on a new one
+                        tracing::warn!(
+                            reason = err.to_string(),
+                            "Failed to poll committed masp txs"
+                        );
+                        break;
+                    }
+                };
+
+                _ = self.sender.send(range);
+                last_height = Some(range.1);
+            }
+        }
+    }
+}
+
+/// Spawn a task that polls the last synced height every
+/// `poll_interval`, and invalidates the response cache of `app_state`
+/// once a new height gets synced. Unlike the streams, it must observe
+/// blocks without masp txs too, which are never notified.
+pub fn spawn_cache_invalidation(app_state: AppState, poll_interval: Duration) {
+    let cache = app_state.cache().clone();
+    let namada_state_repo = NamadaStateRepository::new(app_state);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            match namada_state_repo.get_latest_height().await {
+                Ok(block_height) => {
+                    cache.set_synced_height(
+                        block_height.map(|h| h.0).unwrap_or_default(),
+                    );
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        reason = err.to_string(),
+                        "Failed to poll the last synced height"
+                    );
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use crate::appstate::AppState;
+use crate::repository::compact_block::{
+    CompactBlockRepository, CompactBlockRepositoryTrait,
+};
+
+/// Leading bytes of a note ciphertext, along with the note commitment
+/// and ephemeral key needed to trial decrypt it.
+pub struct CompactOutput {
+    pub cmu: Vec<u8>,
+    pub ephemeral_key: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Nullifiers and compact outputs of a masp tx.
+#[derive(Default)]
+pub struct CompactTx {
+    pub nullifiers: Vec<Vec<u8>>,
+    pub outputs: Vec<CompactOutput>,
+}
+
+/// Masp txs of a block, keyed by their block index and masp tx index.
+pub type CompactBlock = BTreeMap<(u64, u64), CompactTx>;
+
+pub enum CompactBlocksLookup {
+    /// Compact blocks keyed by their height. Blocks without masp txs
+    /// are left out.
+    Found(BTreeMap<u64, CompactBlock>),
+    /// The requested range has not been fully synced yet.
+    NotSynced,
+}
+
+#[derive(Clone)]
+pub struct CompactBlockService {
+    compact_block_repo: CompactBlockRepository,
+}
+
+impl CompactBlockService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            compact_block_repo: CompactBlockRepository::new(app_state),
+        }
+    }
+
+    /// Return the compact blocks between `from_block_height` and
+    /// `to_block_height` (inclusive).
+    pub async fn get_compact_blocks(
+        &self,
+        from_block_height: u64,
+        to_block_height: u64,
+    ) -> anyhow::Result<CompactBlocksLookup> {
+        let rows = self
+            .compact_block_repo
+            .get_compact_block_rows(
+                from_block_height as i32,
+                to_block_height as i32,
+            )
+            .await?;
+
+        if rows
+            .last_synced_height
+            .is_none_or(|h| to_block_height > h as u64)
+        {
+            return Ok(CompactBlocksLookup::NotSynced);
+        }
+
+        let mut blocks: BTreeMap<u64, CompactBlock> = BTreeMap::new();
+
+        for nullifier in rows.nullifiers {
+            blocks
+                .entry(nullifier.block_height as u64)
+                .or_default()
+                .entry((
+                    nullifier.block_index as u64,
+                    nullifier.masp_tx_index as u64,
+                ))
+                .or_default()
+                .nullifiers
+                .push(nullifier.nullifier);
+        }
+        for output in rows.compact_outputs {
+            blocks
+                .entry(output.block_height as u64)
+                .or_default()
+                .entry((output.block_index as u64, output.masp_tx_index as u64))
+                .or_default()
+                .outputs
+                .push(CompactOutput {
+                    cmu: output.cmu,
+                    ephemeral_key: output.ephemeral_key,
+                    ciphertext: output.ciphertext,
+                });
+        }
+
+        Ok(CompactBlocksLookup::Found(blocks))
+    }
+}
@@ -1,5 +1,13 @@
+pub mod anchor;
+pub mod block;
+pub mod commit_notifier;
+pub mod compact_block;
 pub mod namada_state;
 pub mod notes_index;
+pub mod notes_stream;
+pub mod nullifier;
+pub mod sync;
 pub mod tree;
 pub mod tx;
+pub mod tx_stream;
 pub mod witness_map;
@@ -1,4 +1,7 @@
+use anyhow::Context;
+use chrono::NaiveDateTime;
 use shared::height::BlockHeight;
+use tendermint_rpc::{Client, HttpClient};
 
 use crate::appstate::AppState;
 use crate::repository::namada_state::{
@@ -8,12 +11,17 @@ use crate::repository::namada_state::{
 #[derive(Clone)]
 pub struct NamadaStateService {
     namada_state_repo: NamadaStateRepository,
+    cometbft_client: Option<HttpClient>,
 }
 
 impl NamadaStateService {
-    pub fn new(app_state: AppState) -> Self {
+    pub fn new(
+        app_state: AppState,
+        cometbft_client: Option<HttpClient>,
+    ) -> Self {
         Self {
             namada_state_repo: NamadaStateRepository::new(app_state),
+            cometbft_client,
         }
     }
 
@@ -34,4 +42,54 @@ impl NamadaStateService {
                     .map(|(height, filter)| (BlockHeight(height as _), filter))
             })
     }
+
+    pub async fn get_chain_id(&self) -> anyhow::Result<Option<String>> {
+        self.namada_state_repo.get_chain_id().await
+    }
+
+    pub async fn get_pruned_height(
+        &self,
+    ) -> anyhow::Result<Option<BlockHeight>> {
+        self.namada_state_repo.get_pruned_height().await
+    }
+
+    pub async fn check_connection(&self) -> anyhow::Result<()> {
+        self.namada_state_repo.check_connection().await
+    }
+
+    /// Returns the last synced height, the chain tip observed when it was
+    /// committed, and the time of that commit.
+    pub async fn get_sync_progress(
+        &self,
+    ) -> anyhow::Result<Option<(BlockHeight, BlockHeight, NaiveDateTime)>> {
+        self.namada_state_repo
+            .get_sync_progress()
+            .await
+            .map(|option| {
+                option.map(|state| {
+                    (
+                        BlockHeight::from(state.block_height),
+                        BlockHeight::from(state.chain_tip),
+                        state.committed_at,
+                    )
+                })
+            })
+    }
+
+    /// Returns the latest block height reported by CometBFT, or `None`
+    /// if no CometBFT endpoint was configured.
+    pub async fn get_chain_tip(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let Some(client) = &self.cometbft_client else {
+            return Ok(None);
+        };
+
+        let status = client
+            .status()
+            .await
+            .context("Failed to query CometBFT's node status")?;
+
+        Ok(Some(BlockHeight::from(
+            status.sync_info.latest_block_height,
+        )))
+    }
 }
@@ -1,4 +1,5 @@
 use crate::appstate::AppState;
+use crate::cache::{CacheKey, ResponseCache};
 use crate::repository::notes_index::{
     NotesIndexRepository, NotesIndexRepositoryTrait,
 };
@@ -6,32 +7,72 @@ use crate::repository::notes_index::{
 #[derive(Clone)]
 pub struct NotesIndexService {
     notes_index_repo: NotesIndexRepository,
+    cache: ResponseCache,
 }
 
 impl NotesIndexService {
     pub fn new(app_state: AppState) -> Self {
         Self {
+            cache: app_state.cache().clone(),
             notes_index_repo: NotesIndexRepository::new(app_state),
         }
     }
 
+    /// Return a page of the notes map, along with whether more
+    /// notes remain past the end of the page.
     pub async fn get_notes_index(
         &self,
-        from_block_height: u64,
-    ) -> anyhow::Result<Vec<(u64, u64, u64, u64)>> {
-        Ok(self
-            .notes_index_repo
-            .get_notes_index(from_block_height as i32)
-            .await?
-            .into_iter()
-            .map(|notes_index_entry| {
-                (
-                    notes_index_entry.block_height as u64,
-                    notes_index_entry.block_index as u64,
-                    notes_index_entry.masp_tx_index as u64,
-                    notes_index_entry.note_position as u64,
-                )
+        from_block_height: Option<u64>,
+        to_block_height: u64,
+        is_fee_unshielding: Option<bool>,
+        limit: Option<u64>,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<(u64, u64, u64, u64, bool)>, bool)> {
+        let key = CacheKey::NotesIndex {
+            from_block_height,
+            to_block_height,
+            is_fee_unshielding,
+            limit,
+            offset,
+        };
+
+        self.cache
+            .get_or_load(key, || async {
+                // NB: fetch one extra row to find out if there are more pages
+                let mut notes_index = self
+                    .notes_index_repo
+                    .get_notes_index(
+                        from_block_height.map(|height| height as i32),
+                        to_block_height as i32,
+                        is_fee_unshielding,
+                        limit.map(|limit| {
+                            limit.saturating_add(1).min(i64::MAX as u64) as i64
+                        }),
+                        offset.min(i64::MAX as u64) as i64,
+                    )
+                    .await?;
+
+                let has_more =
+                    limit.is_some_and(|limit| notes_index.len() as u64 > limit);
+                if let Some(limit) = limit {
+                    notes_index.truncate(limit as usize);
+                }
+
+                let notes_index = notes_index
+                    .into_iter()
+                    .map(|notes_index_entry| {
+                        (
+                            notes_index_entry.block_height as u64,
+                            notes_index_entry.block_index as u64,
+                            notes_index_entry.masp_tx_index as u64,
+                            notes_index_entry.note_position as u64,
+                            notes_index_entry.is_fee_unshielding,
+                        )
+                    })
+                    .collect();
+
+                Ok((notes_index, has_more))
             })
-            .collect())
+            .await
     }
 }
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use orm::notes_index::NotesIndexDb;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::appstate::AppState;
+use crate::repository::namada_state::{
+    NamadaStateRepository, NamadaStateRepositoryTrait,
+};
+use crate::repository::notes_index::{
+    NotesIndexRepository, NotesIndexRepositoryTrait,
+};
+use crate::service::commit_notifier::CommitNotifier;
+
+/// Number of updates buffered for each subscriber, before it starts
+/// lagging behind.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Notes committed by the crawler since the previous update.
+#[derive(Clone, Debug)]
+pub struct NotesUpdate {
+    /// Highest block height of the committed notes.
+    pub block_height: u64,
+    pub notes_index: Arc<Vec<(u64, u64, u64, u64, bool)>>,
+}
+
+#[derive(Clone)]
+pub struct NotesStreamService {
+    sender: broadcast::Sender<NotesUpdate>,
+    notes_index_repo: NotesIndexRepository,
+    namada_state_repo: NamadaStateRepository,
+}
+
+impl NotesStreamService {
+    /// Create a new service, and spawn a task that reads the notes
+    /// committed in the ranges of block heights sent by `notifier`.
+    pub fn new(app_state: AppState, notifier: &CommitNotifier) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        let service = Self {
+            sender,
+            notes_index_repo: NotesIndexRepository::new(app_state.clone()),
+            namada_state_repo: NamadaStateRepository::new(app_state),
+        };
+
+        tokio::spawn(service.clone().listen(notifier.subscribe()));
+
+        service
+    }
+
+    /// Subscribe to the notes committed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<NotesUpdate> {
+        self.sender.subscribe()
+    }
+
+    pub async fn get_latest_height(&self) -> anyhow::Result<u64> {
+        let block_height = self.namada_state_repo.get_latest_height().await?;
+        Ok(block_height.map(|h| h.0).unwrap_or_default())
+    }
+
+    pub async fn get_pruned_height(&self) -> anyhow::Result<Option<u64>> {
+        let block_height = self.namada_state_repo.get_pruned_height().await?;
+        Ok(block_height.map(|h| h.0))
+    }
+
+    /// Return a page of the notes committed between `from_block_height`
+    /// and `to_block_height` (both inclusive).
+    pub async fn get_notes_index(
+        &self,
+        from_block_height: u64,
+        to_block_height: u64,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<Vec<(u64, u64, u64, u64, bool)>> {
+        let notes_index = self
+            .notes_index_repo
+            .get_notes_index(
+                Some(from_block_height as i32),
+                to_block_height as i32,
+                None,
+                Some(limit as i64),
+                offset as i64,
+            )
+            .await?;
+
+        Ok(notes_index.into_iter().map(into_note).collect())
+    }
+
+    async fn listen(self, mut ranges: broadcast::Receiver<(i32, i32)>) {
+        loop {
+            let (from_height, to_height) = match ranges.recv().await {
+                Ok(range) => range,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        skipped,
+                        "Missed notifications of committed notes"
+                    );
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            if self.sender.receiver_count() == 0 {
+                continue;
+            }
+
+            match self
+                .notes_index_repo
+                .get_notes_index(Some(from_height), to_height, None, None, 0)
+                .await
+            {
+                Ok(notes_index) => {
+                    let notes_index =
+                        notes_index.into_iter().map(into_note).collect();
+
+                    _ = self.sender.send(NotesUpdate {
+                        block_height: to_height as u64,
+                        notes_index: Arc::new(notes_index),
+                    });
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        reason = err.to_string(),
+                        "Failed to read committed notes"
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn into_note(notes_index_entry: NotesIndexDb) -> (u64, u64, u64, u64, bool) {
+    (
+        notes_index_entry.block_height as u64,
+        notes_index_entry.block_index as u64,
+        notes_index_entry.masp_tx_index as u64,
+        notes_index_entry.note_position as u64,
+        notes_index_entry.is_fee_unshielding,
+    )
+}
@@ -0,0 +1,60 @@
+use crate::appstate::AppState;
+use crate::repository::nullifier::{
+    NullifierRepository, NullifierRepositoryTrait,
+};
+
+#[derive(Clone)]
+pub struct NullifierService {
+    nullifier_repo: NullifierRepository,
+}
+
+impl NullifierService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            nullifier_repo: NullifierRepository::new(app_state),
+        }
+    }
+
+    /// Return a page of the revealed nullifiers, along with whether more
+    /// nullifiers remain past the end of the page.
+    pub async fn get_nullifiers(
+        &self,
+        from_block_height: u64,
+        to_block_height: Option<u64>,
+        limit: Option<u64>,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<(Vec<u8>, u64, u64, u64)>, bool)> {
+        // NB: fetch one extra row to find out if there are more pages
+        let mut nullifiers = self
+            .nullifier_repo
+            .get_nullifiers(
+                from_block_height as i32,
+                to_block_height.map(|height| height as i32),
+                limit.map(|limit| {
+                    limit.saturating_add(1).min(i64::MAX as u64) as i64
+                }),
+                offset.min(i64::MAX as u64) as i64,
+            )
+            .await?;
+
+        let has_more =
+            limit.is_some_and(|limit| nullifiers.len() as u64 > limit);
+        if let Some(limit) = limit {
+            nullifiers.truncate(limit as usize);
+        }
+
+        let nullifiers = nullifiers
+            .into_iter()
+            .map(|nullifier| {
+                (
+                    nullifier.nullifier,
+                    nullifier.block_height as u64,
+                    nullifier.block_index as u64,
+                    nullifier.masp_tx_index as u64,
+                )
+            })
+            .collect();
+
+        Ok((nullifiers, has_more))
+    }
+}
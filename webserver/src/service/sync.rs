@@ -0,0 +1,106 @@
+use namada_core::borsh::BorshSerializeExt;
+
+use crate::appstate::AppState;
+use crate::repository::sync::{SyncRepository, SyncRepositoryTrait};
+use crate::service::tx::group_txs;
+use crate::service::witness_map::replay_witnesses;
+
+/// Everything a light client needs to sync a range of blocks.
+pub struct SyncData {
+    /// Commitment tree bytes and the height they were taken at.
+    pub commitment_tree: Option<(Vec<u8>, u64)>,
+    /// Witness map and the height it was taken at, which is always that
+    /// of the commitment tree.
+    pub witnesses: Option<(Vec<(Vec<u8>, u64)>, u64)>,
+    pub notes_index: Vec<(u64, u64, u64, u64, bool)>,
+    pub txs: Vec<(Vec<(u64, Vec<u8>)>, u64, u64)>,
+}
+
+pub enum SyncLookup {
+    Found(SyncData),
+    /// The requested range has not been fully synced yet.
+    NotSynced,
+}
+
+#[derive(Clone)]
+pub struct SyncService {
+    sync_repo: SyncRepository,
+}
+
+impl SyncService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            sync_repo: SyncRepository::new(app_state),
+        }
+    }
+
+    /// Return the data needed to sync the blocks between
+    /// `from_block_height` and `to_block_height` (inclusive). The
+    /// commitment tree and witness map are omitted if `with_tree` is
+    /// `false`.
+    pub async fn get_sync_data(
+        &self,
+        from_block_height: u64,
+        to_block_height: u64,
+        with_tree: bool,
+    ) -> anyhow::Result<SyncLookup> {
+        let rows = self
+            .sync_repo
+            .get_sync_rows(
+                from_block_height as i32,
+                to_block_height as i32,
+                with_tree,
+            )
+            .await?;
+
+        if rows
+            .last_synced_height
+            .is_none_or(|h| to_block_height > h as u64)
+        {
+            return Ok(SyncLookup::NotSynced);
+        }
+
+        let commitment_tree = rows
+            .commitment_tree
+            .map(|tree| (tree.tree, tree.block_height as u64));
+
+        let witnesses = match rows.witnesses {
+            Some(witness_rows) => {
+                let witnesses_height = witness_rows
+                    .tree_height
+                    .map_or(to_block_height, |height| height as u64);
+                let witnesses = tokio::task::block_in_place(|| {
+                    replay_witnesses(witness_rows)
+                })?
+                .into_iter()
+                .map(|(note_pos, witness)| {
+                    (witness.serialize_to_vec(), note_pos)
+                })
+                .collect();
+                Some((witnesses, witnesses_height))
+            }
+            None => None,
+        };
+
+        let notes_index = rows
+            .notes_index
+            .into_iter()
+            .map(|notes_index_entry| {
+                (
+                    notes_index_entry.block_height as u64,
+                    notes_index_entry.block_index as u64,
+                    notes_index_entry.masp_tx_index as u64,
+                    notes_index_entry.note_position as u64,
+                    notes_index_entry.is_fee_unshielding,
+                )
+            })
+            .collect();
+
+        Ok(SyncLookup::Found(SyncData {
+            commitment_tree,
+            witnesses,
+            notes_index,
+            txs: group_txs(rows.txs),
+        }))
+    }
+}
@@ -1,24 +1,54 @@
+use shared::commitment_tree::empty as empty_tree;
+
 use crate::appstate::AppState;
+use crate::cache::{CacheKey, ResponseCache};
+use crate::compression::{ContentCoding, EncodedBody};
+use crate::encoding::Encoding;
 use crate::repository::tree::{TreeRepository, TreeRepositoryTrait};
+use crate::response::tree::TreeResponse;
 
 #[derive(Clone)]
 pub struct TreeService {
     tree_repo: TreeRepository,
+    cache: ResponseCache,
 }
 
 impl TreeService {
     pub fn new(app_state: AppState) -> Self {
         Self {
+            cache: app_state.cache().clone(),
             tree_repo: TreeRepository::new(app_state),
         }
     }
 
+    /// Return the commitment tree at `block_height`, or the empty tree if
+    /// none was committed by then, as a response body encoded with
+    /// `encoding` and compressed with `coding`.
     pub async fn get_at_height(
         &self,
         block_height: u64,
-    ) -> anyhow::Result<Option<(Vec<u8>, u64)>> {
-        let commiment_tree =
-            self.tree_repo.get_at_height(block_height as i32).await?;
-        Ok(commiment_tree.map(|tree| (tree.tree, tree.block_height as u64)))
+        encoding: Encoding,
+        coding: ContentCoding,
+    ) -> anyhow::Result<EncodedBody> {
+        let key = CacheKey::Tree(block_height).encoded(encoding, coding);
+
+        self.cache
+            .get_or_load(key, || async {
+                let commiment_tree =
+                    self.tree_repo.get_at_height(block_height as i32).await?;
+                let (commitment_tree, block_height) = commiment_tree
+                    .map(|tree| (tree.tree, tree.block_height as u64))
+                    .unwrap_or_else(|| (empty_tree(), block_height));
+
+                EncodedBody::new(
+                    &TreeResponse {
+                        commitment_tree,
+                        block_height,
+                    },
+                    encoding,
+                    coding,
+                )
+            })
+            .await
     }
 }
@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use orm::tx::TxDb;
 
 use crate::appstate::AppState;
 use crate::repository::tx::{TxRepository, TxRepositoryTrait};
@@ -15,31 +16,50 @@ impl TxService {
         }
     }
 
+    /// Return a page of at most `limit` batches of masp txs, along
+    /// with whether more batches remain past the end of the page.
     pub async fn get_txs(
         &self,
         from_block_height: u64,
         to_block_height: u64,
-    ) -> anyhow::Result<impl IntoIterator<Item = (Vec<(u64, Vec<u8>)>, u64, u64)>>
-    {
-        Ok(self
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<(Vec<(u64, Vec<u8>)>, u64, u64)>, bool)> {
+        // NB: fetch one extra batch to find out if there are more pages
+        let txs = self
             .tx_repo
-            .get_txs(from_block_height as i32, to_block_height as i32)
-            .await?
-            .into_iter()
-            // NB: the returned txs are guaranteed to be sorted
-            // by their insertion order in the database, so
-            // chunking should work as expected
-            .chunk_by(|tx| {
-                // NB: group batched txs by their slot in a block
-                (tx.block_height, tx.block_index)
-            })
-            .into_iter()
-            .map(|((block_height, block_index), tx_batch)| {
-                let tx_batch: Vec<_> = tx_batch
-                    .map(|tx| (tx.masp_tx_index as u64, tx.tx_bytes))
-                    .collect();
-                (tx_batch, block_height as u64, block_index as u64)
-            })
-            .collect::<Vec<_>>())
+            .get_txs(
+                from_block_height as i32,
+                to_block_height as i32,
+                limit.saturating_add(1).min(i64::MAX as u64) as i64,
+                offset.min(i64::MAX as u64) as i64,
+            )
+            .await?;
+
+        let mut txs = group_txs(txs);
+        let has_more = txs.len() as u64 > limit;
+        txs.truncate(limit as usize);
+
+        Ok((txs, has_more))
     }
 }
+
+/// Group masp txs by the slot of their batch in a block.
+pub fn group_txs(txs: Vec<TxDb>) -> Vec<(Vec<(u64, Vec<u8>)>, u64, u64)> {
+    txs.into_iter()
+        // NB: the returned txs are guaranteed to be sorted
+        // by their insertion order in the database, so
+        // chunking should work as expected
+        .chunk_by(|tx| {
+            // NB: group batched txs by their slot in a block
+            (tx.block_height, tx.block_index)
+        })
+        .into_iter()
+        .map(|((block_height, block_index), tx_batch)| {
+            let tx_batch: Vec<_> = tx_batch
+                .map(|tx| (tx.masp_tx_index as u64, tx.tx_bytes))
+                .collect();
+            (tx_batch, block_height as u64, block_index as u64)
+        })
+        .collect()
+}
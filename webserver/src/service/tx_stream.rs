@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::appstate::AppState;
+use crate::repository::notes_index::{
+    NotesIndexRepository, NotesIndexRepositoryTrait,
+};
+use crate::repository::tx::{TxRepository, TxRepositoryTrait};
+use crate::service::commit_notifier::CommitNotifier;
+
+/// Number of updates buffered for each subscriber, before it starts
+/// lagging behind.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Masp txs committed by the crawler in a single block.
+#[derive(Clone, Debug, Default)]
+pub struct CommittedBlock {
+    pub block_height: u64,
+    /// Block index and masp tx index of each tx.
+    pub txs: Vec<(u64, u64)>,
+    pub note_positions: Vec<u64>,
+}
+
+#[derive(Clone)]
+pub struct TxStreamService {
+    sender: broadcast::Sender<Arc<CommittedBlock>>,
+    tx_repo: TxRepository,
+    notes_index_repo: NotesIndexRepository,
+}
+
+impl TxStreamService {
+    /// Create a new service, and spawn a task that reads the masp txs
+    /// committed in the ranges of block heights sent by `notifier`.
+    pub fn new(app_state: AppState, notifier: &CommitNotifier) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        let service = Self {
+            sender,
+            tx_repo: TxRepository::new(app_state.clone()),
+            notes_index_repo: NotesIndexRepository::new(app_state),
+        };
+
+        tokio::spawn(service.clone().listen(notifier.subscribe()));
+
+        service
+    }
+
+    /// Subscribe to the blocks with masp txs committed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<CommittedBlock>> {
+        self.sender.subscribe()
+    }
+
+    async fn listen(self, mut ranges: broadcast::Receiver<(i32, i32)>) {
+        loop {
+            match ranges.recv().await {
+                Ok(range) => self.deliver(range).await,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        skipped,
+                        "Missed notifications of committed masp txs"
+                    );
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Send the masp txs committed in the given range of block heights
+    /// to the subscribers, if any.
+    async fn deliver(&self, range: (i32, i32)) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+
+        match self.get_committed_blocks(range).await {
+            Ok(blocks) => {
+                for block in blocks {
+                    _ = self.sender.send(Arc::new(block));
+                }
+            }
+            Err(err) => {
+                tracing::warn!(
+                    reason = err.to_string(),
+                    "Failed to read committed masp txs"
+                );
+            }
+        }
+    }
+
+    async fn get_committed_blocks(
+        &self,
+        (from_block_height, to_block_height): (i32, i32),
+    ) -> anyhow::Result<Vec<CommittedBlock>> {
+        let txs = self
+            .tx_repo
+            .get_tx_slots(from_block_height, to_block_height)
+            .await?;
+        let notes_index = self
+            .notes_index_repo
+            .get_notes_index(
+                Some(from_block_height),
+                to_block_height,
+                None,
+                None,
+                0,
+            )
+            .await?;
+
+        let mut blocks = BTreeMap::new();
+
+        for (block_height, block_index, masp_tx_index) in txs {
+            block_at(&mut blocks, block_height)
+                .txs
+                .push((block_index as u64, masp_tx_index as u64));
+        }
+        for note in notes_index {
+            block_at(&mut blocks, note.block_height)
+                .note_positions
+                .push(note.note_position as u64);
+        }
+
+        Ok(blocks.into_values().collect())
+    }
+}
+
+fn block_at(
+    blocks: &mut BTreeMap<u64, CommittedBlock>,
+    block_height: i32,
+) -> &mut CommittedBlock {
+    let block_height = block_height as u64;
+
+    blocks
+        .entry(block_height)
+        .or_insert_with(|| CommittedBlock {
+            block_height,
+            ..Default::default()
+        })
+}
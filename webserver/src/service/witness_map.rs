@@ -1,35 +1,272 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use namada_core::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_core::masp_primitives::ff::PrimeField;
+use namada_core::masp_primitives::merkle_tree::{
+    CommitmentTree, IncrementalWitness,
+};
+use namada_core::masp_primitives::sapling::Node;
+use namada_core::masp_primitives::transaction::Transaction;
 use shared::height::BlockHeight;
 
 use crate::appstate::AppState;
+use crate::cache::{CacheKey, ResponseCache};
+use crate::compression::{ContentCoding, EncodedBody};
+use crate::encoding::Encoding;
 use crate::repository::witness_map::{
-    WitnessMapRepository, WitnessMapRepositoryTrait,
+    WitnessMapRepository, WitnessMapRepositoryTrait, WitnessReplayRows,
 };
+use crate::response::witness_map::WitnessMapResponse;
+use crate::service::anchor::{Anchor, AnchorService};
+
+/// Merkle path of a note, along with the anchor it leads up to.
+pub struct NoteWitness {
+    pub note_position: u64,
+    pub path: Vec<u8>,
+    pub anchor: Anchor,
+}
+
+pub enum NoteWitnessLookup {
+    Found(NoteWitness),
+    /// The requested height has not been synced yet.
+    NotSynced,
+    /// No witness of the note is tracked at the requested height.
+    Untracked,
+}
+
+pub enum WitnessMapLookup {
+    /// Response body holding the witnesses of all the notes, sorted by
+    /// note position.
+    Found(EncodedBody),
+    /// The requested height has not been synced yet.
+    NotSynced,
+}
 
 #[derive(Clone)]
 pub struct WitnessMapService {
     witness_map_repo: WitnessMapRepository,
+    cache: ResponseCache,
 }
 
 impl WitnessMapService {
     pub fn new(app_state: AppState) -> Self {
         Self {
+            cache: app_state.cache().clone(),
             witness_map_repo: WitnessMapRepository::new(app_state),
         }
     }
 
+    /// Return the witness map of the commitment tree at the closest
+    /// height at or below `block_height` (see [`TreeService`]), or an
+    /// empty one if there is none, as a response body encoded with
+    /// `encoding` and compressed with `coding`. The witness map is
+    /// rebuilt from the last checkpoint, such that its height is always
+    /// that of the tree.
+    ///
+    /// [`TreeService`]: crate::service::tree::TreeService
     pub async fn get_witnesses(
         &self,
         block_height: BlockHeight,
-    ) -> anyhow::Result<Option<(Vec<(Vec<u8>, u64)>, u64)>> {
-        let (witnesses, closest_height) = self
+        encoding: Encoding,
+        coding: ContentCoding,
+    ) -> anyhow::Result<EncodedBody> {
+        let key =
+            CacheKey::WitnessMap(block_height.0).encoded(encoding, coding);
+
+        self.cache
+            .get_or_load(key, || async {
+                let rows = self
+                    .witness_map_repo
+                    .get_witness_replay_rows(block_height.0 as i32)
+                    .await?;
+                let response_height = rows
+                    .tree_height
+                    .map_or(block_height, |height| BlockHeight(height as u64));
+
+                let witnesses =
+                    tokio::task::block_in_place(|| replay_witnesses(rows))?;
+                let witnesses = witnesses
+                    .into_iter()
+                    .map(|(note_pos, witness)| {
+                        (witness.serialize_to_vec(), note_pos)
+                    })
+                    .collect::<Vec<_>>();
+
+                EncodedBody::new(
+                    &WitnessMapResponse::new(response_height, witnesses),
+                    encoding,
+                    coding,
+                )
+            })
+            .await
+    }
+
+    /// Return the witness map as of exactly `block_height`, rebuilt from
+    /// the last checkpoint at or below it, and the masp txs committed
+    /// in between. The witness map is returned as a response body
+    /// encoded with `encoding` and compressed with `coding`.
+    pub async fn get_witnesses_at(
+        &self,
+        block_height: BlockHeight,
+        encoding: Encoding,
+        coding: ContentCoding,
+    ) -> anyhow::Result<WitnessMapLookup> {
+        // NB: `None` stands for a height that was not synced yet
+        let body = self
+            .cache
+            .get_or_load(
+                CacheKey::HistoricalWitnessMap(block_height.0)
+                    .encoded(encoding, coding),
+                || async {
+                    let rows = self
+                        .witness_map_repo
+                        .get_witness_replay_rows(block_height.0 as i32)
+                        .await?;
+
+                    if rows
+                        .last_synced_height
+                        .is_none_or(|height| (height as u64) < block_height.0)
+                    {
+                        return Ok(None);
+                    }
+
+                    let witnesses =
+                        tokio::task::block_in_place(|| replay_witnesses(rows))?;
+
+                    let witnesses = witnesses
+                        .into_iter()
+                        .map(|(note_pos, witness)| {
+                            (witness.serialize_to_vec(), note_pos)
+                        })
+                        .collect::<Vec<_>>();
+
+                    EncodedBody::new(
+                        &WitnessMapResponse::new(block_height, witnesses),
+                        encoding,
+                        coding,
+                    )
+                    .map(Some)
+                },
+            )
+            .await?;
+
+        Ok(body.map_or(WitnessMapLookup::NotSynced, WitnessMapLookup::Found))
+    }
+
+    /// Return the witness of the note at `note_position`, against the
+    /// anchor at `block_height`, or at the last synced height if
+    /// `block_height` is `None`.
+    pub async fn get_note_witness(
+        &self,
+        note_position: u64,
+        block_height: Option<u64>,
+    ) -> anyhow::Result<NoteWitnessLookup> {
+        let (last_synced_height, witness, tree, anchor_height) = self
             .witness_map_repo
-            .get_witnesses(block_height.0 as i32)
+            .get_note_witness(
+                note_position as i32,
+                block_height.map(|h| h as i32),
+            )
             .await?;
-        let witnesses = witnesses
-            .into_iter()
-            .map(|witness| (witness.witness_bytes, witness.witness_idx as u64))
+
+        let Some(last_synced_height) = last_synced_height else {
+            return Ok(NoteWitnessLookup::NotSynced);
+        };
+        let block_height = block_height.unwrap_or(last_synced_height as u64);
+
+        if block_height > last_synced_height as u64 {
+            return Ok(NoteWitnessLookup::NotSynced);
+        }
+
+        let Some(witness) = witness else {
+            return Ok(NoteWitnessLookup::Untracked);
+        };
+
+        let witness = tokio::task::block_in_place(|| {
+            IncrementalWitness::<Node>::try_from_slice(&witness.witness_bytes)
+        })
+        .context("Failed to deserialize note witness returned from db")?;
+        let path = witness.path().with_context(|| {
+            format!("Failed to build merkle path of note {note_position}")
+        })?;
+
+        let anchor_height = anchor_height.map_or(block_height, |h| h as u64);
+        let anchor = AnchorService::compute_anchor(tree, anchor_height)?;
+
+        // NB: the witness and the tree are read at the same
+        // height, so they must always agree on the root
+        if witness.root().serialize_to_vec() != anchor.root {
+            anyhow::bail!(
+                "Witness of note {note_position} does not match the anchor at \
+                 height {anchor_height}"
+            );
+        }
+
+        Ok(NoteWitnessLookup::Found(NoteWitness {
+            note_position,
+            path: path.serialize_to_vec(),
+            anchor,
+        }))
+    }
+}
+
+/// Apply the masp txs in `rows` on top of the witness map checkpoint in
+/// it, the same way the crawler does while indexing them.
+pub(crate) fn replay_witnesses(
+    rows: WitnessReplayRows,
+) -> anyhow::Result<BTreeMap<u64, IncrementalWitness<Node>>> {
+    let mut commitment_tree = match &rows.commitment_tree {
+        Some(tree) => CommitmentTree::<Node>::try_from_slice(&tree.tree)
+            .context(
+                "Failed to deserialize commitment tree returned from db",
+            )?,
+        None => CommitmentTree::empty(),
+    };
+
+    let mut witnesses = rows
+        .witnesses
+        .iter()
+        .map(|witness| {
+            let note_pos = witness.witness_idx as u64;
+            IncrementalWitness::<Node>::try_from_slice(&witness.witness_bytes)
+                .map(|witness| (note_pos, witness))
+                .with_context(|| {
+                    format!(
+                        "Failed to deserialize witness of note {note_pos} \
+                         returned from db"
+                    )
+                })
+        })
+        .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+    let mut blocks: BTreeMap<i32, Vec<Transaction>> = BTreeMap::new();
+    for tx in rows.txs {
+        let masp_tx = Transaction::try_from_slice(&tx.tx_bytes)
+            .context("Failed to deserialize shielded tx returned from db")?;
+        blocks.entry(tx.block_height).or_default().push(masp_tx);
+    }
+
+    for (block_height, txs) in blocks {
+        let note_commitments = txs
+            .iter()
+            .filter_map(|tx| tx.sapling_bundle())
+            .flat_map(|bundle| &bundle.shielded_outputs)
+            .map(|output| Node::new(output.cmu.to_repr()))
             .collect::<Vec<_>>();
-        let non_empty_witnesses = !witnesses.is_empty();
-        Ok(non_empty_witnesses.then_some((witnesses, closest_height as u64)))
+
+        let first_note_pos = commitment_tree.size() as u64;
+
+        let new_witnesses = shared::witness::append_notes(
+            &mut commitment_tree,
+            &mut witnesses,
+            &note_commitments,
+        )
+        .with_context(|| {
+            format!("Failed to replay the notes of block {block_height}")
+        })?;
+        witnesses.extend((first_note_pos..).zip(new_witnesses));
     }
+
+    Ok(witnesses)
 }
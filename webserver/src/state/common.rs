@@ -1,27 +1,80 @@
+use std::time::Duration;
+
+use tendermint_rpc::HttpClient;
+
 use crate::appstate::AppState;
+use crate::service::anchor::AnchorService;
+use crate::service::block::BlockService;
+use crate::service::commit_notifier::{
+    CommitNotifier, spawn_cache_invalidation,
+};
+use crate::service::compact_block::CompactBlockService;
 use crate::service::namada_state::NamadaStateService;
 use crate::service::notes_index::NotesIndexService;
+use crate::service::notes_stream::NotesStreamService;
+use crate::service::nullifier::NullifierService;
+use crate::service::sync::SyncService;
 use crate::service::tree::TreeService;
 use crate::service::tx::TxService;
+use crate::service::tx_stream::TxStreamService;
 use crate::service::witness_map::WitnessMapService;
 
 #[derive(Clone)]
 pub struct CommonState {
     pub tree_service: TreeService,
+    pub anchor_service: AnchorService,
     pub witness_map_service: WitnessMapService,
     pub notes_index_service: NotesIndexService,
+    pub notes_stream_service: NotesStreamService,
+    pub nullifier_service: NullifierService,
     pub tx_service: TxService,
+    pub tx_stream_service: TxStreamService,
+    pub sync_service: SyncService,
+    pub compact_block_service: CompactBlockService,
     pub namada_state_service: NamadaStateService,
+    pub block_service: BlockService,
+    pub notes_map_only: bool,
+    pub caught_up_threshold: u64,
+    pub max_commit_age: Duration,
 }
 
 impl CommonState {
-    pub fn new(data: AppState) -> Self {
+    pub fn new(
+        data: AppState,
+        notes_map_only: bool,
+        notes_stream_poll_interval: Duration,
+        caught_up_threshold: u64,
+        max_commit_age: Duration,
+        cometbft_client: Option<HttpClient>,
+    ) -> Self {
+        // NB: both streams are driven by the same notifications, while
+        // the cache follows every synced height, with or without masp txs
+        let notifier =
+            CommitNotifier::new(data.clone(), notes_stream_poll_interval);
+        spawn_cache_invalidation(data.clone(), notes_stream_poll_interval);
+
         Self {
             tree_service: TreeService::new(data.clone()),
+            anchor_service: AnchorService::new(data.clone()),
             witness_map_service: WitnessMapService::new(data.clone()),
             notes_index_service: NotesIndexService::new(data.clone()),
+            notes_stream_service: NotesStreamService::new(
+                data.clone(),
+                &notifier,
+            ),
+            nullifier_service: NullifierService::new(data.clone()),
             tx_service: TxService::new(data.clone()),
-            namada_state_service: NamadaStateService::new(data),
+            tx_stream_service: TxStreamService::new(data.clone(), &notifier),
+            sync_service: SyncService::new(data.clone()),
+            compact_block_service: CompactBlockService::new(data.clone()),
+            block_service: BlockService::new(data.clone()),
+            namada_state_service: NamadaStateService::new(
+                data,
+                cometbft_client,
+            ),
+            notes_map_only,
+            caught_up_threshold,
+            max_commit_age,
         }
     }
 }
@@ -0,0 +1,43 @@
+use axum::extract::MatchedPath;
+use axum::http::{HeaderMap, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::propagation::Extractor;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Middleware wrapping each request in a span named after the route it
+/// matched. The span continues the trace of the caller, if it sent a
+/// `traceparent` header.
+pub async fn trace_requests<B>(req: Request<B>, next: Next<B>) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| req.uri().path().to_owned());
+
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        route,
+    );
+    let parent_context =
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(req.headers()))
+        });
+    span.set_parent(parent_context);
+
+    next.run(req).instrument(span).await
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}